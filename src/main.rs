@@ -1,8 +1,17 @@
-use rusted_engine::engine_controller::EngineController;
+use rusted_engine::{dialogue::dialogue_runner::ConsolePresenter, engine_controller::EngineController, events::event_bus::EngineEvent};
 
 mod rusted_engine;
 
 fn main() {
-    let mut example_app_controller = EngineController::new();
+    // Games that draw their own dialogue boxes pass in their own DialoguePresenter
+    let mut example_app_controller = EngineController::new(Box::new(ConsolePresenter));
+
+    // Game code can listen in on the engine without going through triggers
+    example_app_controller.get_event_bus().write().unwrap().subscribe(|event| {
+        if let EngineEvent::SceneLoaded { .. } = event {
+            println!("{}", event);
+        }
+    });
+
     example_app_controller.init();
 }
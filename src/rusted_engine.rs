@@ -3,6 +3,10 @@ pub mod events;
 pub mod entities;
 pub mod scenes;
 pub mod audio;
+pub mod cutscenes;
+pub mod dialogue;
 pub mod input;
+pub mod quests;
 pub mod util;
-pub mod game_state;
\ No newline at end of file
+pub mod game_state;
+pub mod scripting;
\ No newline at end of file
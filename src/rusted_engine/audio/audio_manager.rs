@@ -1,4 +1,4 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, RwLock};
 use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
 use serde::{Deserialize, Serialize};
@@ -123,6 +123,10 @@ impl AudioManager {
         }
     }
 
+    pub fn get_sound_names(&self) -> HashSet<String> {
+        self.sounds.read().unwrap().keys().cloned().collect()
+    }
+
     // Load a sound
     pub fn load_sound(&self, name: &str, path: &str) -> Result<(), String> {
         let mut sounds = self.sounds.write().unwrap();
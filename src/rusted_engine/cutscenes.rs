@@ -0,0 +1,2 @@
+pub mod cutscene;
+pub mod cutscene_player;
\ No newline at end of file
@@ -0,0 +1,105 @@
+use std::{fs::File, io::Read};
+
+use serde::{Deserialize, Serialize};
+
+use crate::rusted_engine::{audio::audio_manager::AudioType, events::triggers::Outcome};
+
+/// A timeline of things to happen during a scripted moment. Every track is keyed on seconds from the start,
+/// the cutscene ends once the last key of any track has passed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Cutscene {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub lock_input: bool, // Keeps key driven scene triggers from moving anything while it plays
+    #[serde(default = "default_true")]
+    pub skippable: bool, // Whether the player can skip it with escape, a SkipCutscene outcome works either way
+    #[serde(default)]
+    pub outcome_track: Vec<OutcomeKey>,
+    #[serde(default)]
+    pub position_tracks: Vec<PositionTrack>,
+    #[serde(default)]
+    pub camera_zoom_track: Vec<CameraZoomKey>,
+    #[serde(default)]
+    pub camera_target_track: Vec<CameraTargetKey>,
+    #[serde(default)]
+    pub audio_track: Vec<AudioCue>,
+    #[serde(default)]
+    pub on_finished: Vec<Outcome>, // Queued when the cutscene ends or is skipped
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutcomeKey {
+    pub time: f32,
+    pub outcome: Vec<Outcome>,
+}
+
+/// Moves an object in a straight line from key to key
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PositionTrack {
+    pub object_name: String,
+    pub keys: Vec<PositionKey>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PositionKey {
+    pub time: f32,
+    pub position: Vec<f32>, // Like TeleportObject, a missing z keeps the object's own
+}
+
+/// Zoom is eased linearly between keys
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CameraZoomKey {
+    pub time: f32,
+    pub zoom: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CameraTargetKey {
+    pub time: f32,
+    pub target: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AudioCue {
+    pub time: f32,
+    pub audio_name: String,
+    pub audio_type: AudioType,
+    pub volume: f32,
+}
+
+impl Cutscene {
+    /// The time of the last key on any track
+    pub fn get_duration(&self) -> f32 {
+        let times = self.outcome_track.iter().map(|key| key.time)
+            .chain(self.position_tracks.iter().flat_map(|track| track.keys.iter().map(|key| key.time)))
+            .chain(self.camera_zoom_track.iter().map(|key| key.time))
+            .chain(self.camera_target_track.iter().map(|key| key.time))
+            .chain(self.audio_track.iter().map(|key| key.time));
+        times.fold(0.0, f32::max)
+    }
+
+    /// Puts every track in time order, the player relies on it. Keys on the same time keep the order they were written in.
+    pub fn sort_keys(&mut self) {
+        self.outcome_track.sort_by(|a, b| a.time.total_cmp(&b.time));
+        for track in self.position_tracks.iter_mut() {
+            track.keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        }
+        self.camera_zoom_track.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.camera_target_track.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.audio_track.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+}
+
+/// Loads a cutscenes file, a JSON list of cutscenes
+pub fn load_cutscenes_from_json(file_path: &str) -> Result<Vec<Cutscene>, Box<dyn std::error::Error>> {
+    let mut file = File::open(file_path)?;
+    let mut data = String::new();
+    file.read_to_string(&mut data)?;
+
+    let cutscenes: Vec<Cutscene> = serde_json::from_str(&data)?;
+    Ok(cutscenes)
+}
@@ -0,0 +1,231 @@
+use std::{collections::{HashMap, HashSet}, fs};
+
+use glfw::Key;
+
+use crate::rusted_engine::{events::triggers::{EnqueueAudioArgs, Outcome, SetCameraTrackingTargetArgs, SetCameraZoomArgs, TeleportObjectArgs}, input::key_states::KeyStates};
+
+use super::cutscene::{load_cutscenes_from_json, Cutscene};
+
+struct PlayingCutscene {
+    cutscene: Cutscene,
+    time: f32,
+    paused: bool,
+    next_outcome_key: usize,
+    next_camera_target_key: usize,
+    next_audio_cue: usize,
+}
+
+/// Plays one cutscene at a time. Everything it does comes out as outcomes, so it can be stepped without anything on screen.
+pub struct CutscenePlayer {
+    cutscenes: HashMap<String, Cutscene>,
+    playing: Option<PlayingCutscene>,
+}
+
+impl CutscenePlayer {
+    pub fn new() -> Self {
+        Self {
+            cutscenes: HashMap::new(),
+            playing: None,
+        }
+    }
+
+    pub fn add_cutscene(&mut self, mut cutscene: Cutscene) {
+        cutscene.sort_keys();
+        self.cutscenes.insert(cutscene.name.clone(), cutscene);
+    }
+
+    /// Loads every cutscene JSON file in the directory
+    pub fn load_cutscenes_from_directory(&mut self, dir_path: &str) -> Result<(), String> {
+        let paths = fs::read_dir(dir_path).map_err(|_| "Failed to read directory".to_string())?;
+
+        for path in paths {
+            let entry = path.map_err(|_| "Failed to read directory entry".to_string())?;
+            let full_path = entry.path();
+
+            if full_path.is_file() && full_path.extension().is_some_and(|extension| extension == "json") {
+                let cutscenes = load_cutscenes_from_json(full_path.to_str().unwrap())
+                    .map_err(|e| format!("Error loading cutscenes '{}': {}", full_path.display(), e))?;
+                for cutscene in cutscenes {
+                    self.add_cutscene(cutscene);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_cutscene_names(&self) -> HashSet<String> {
+        self.cutscenes.keys().cloned().collect()
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing.is_some()
+    }
+
+    /// Whether gameplay input should be ignored right now. A paused cutscene still holds the lock.
+    pub fn is_locking_input(&self) -> bool {
+        self.playing.as_ref().is_some_and(|playing| playing.cutscene.lock_input)
+    }
+
+    /// The objects the playing cutscene moves along its position tracks
+    pub fn get_tracked_object_names(&self) -> Vec<String> {
+        match &self.playing {
+            Some(playing) => playing.cutscene.position_tracks.iter().map(|track| track.object_name.clone()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Starts a cutscene from the beginning, cutting off any that was playing. Returns whatever is keyed at the very start.
+    pub fn play(&mut self, cutscene_name: &str) -> Vec<Outcome> {
+        let cutscene = match self.cutscenes.get(cutscene_name) {
+            Some(cutscene) => cutscene.clone(),
+            None => {
+                println!("No cutscene found for cutscene name: {}", cutscene_name);
+                return Vec::new();
+            }
+        };
+        if let Some(playing) = &self.playing {
+            println!("Cutscene {} was cut off by {}.", playing.cutscene.name, cutscene_name);
+        }
+
+        self.playing = Some(PlayingCutscene {
+            cutscene,
+            time: 0.0,
+            paused: false,
+            next_outcome_key: 0,
+            next_camera_target_key: 0,
+            next_audio_cue: 0,
+        });
+        self.advance(f32::NEG_INFINITY, 0.0, true)
+    }
+
+    pub fn pause(&mut self, cutscene_name: &str) {
+        if let Some(playing) = self.playing_named(cutscene_name) {
+            playing.paused = true;
+        }
+    }
+
+    pub fn resume(&mut self, cutscene_name: &str) {
+        if let Some(playing) = self.playing_named(cutscene_name) {
+            playing.paused = false;
+        }
+    }
+
+    /// Jumps to the end. Keyed outcomes that haven't run yet still do and everything lands where it would have, only the audio cues are dropped.
+    pub fn skip(&mut self, cutscene_name: &str) -> Vec<Outcome> {
+        let (time, duration) = match self.playing_named(cutscene_name) {
+            Some(playing) => (playing.time, playing.cutscene.get_duration()),
+            None => return Vec::new(),
+        };
+        let mut outcomes = self.advance(time, duration.max(time), false);
+        outcomes.extend(self.finish());
+        outcomes
+    }
+
+    /// Escape skips a skippable cutscene
+    pub fn process_input(&mut self, key_states: &KeyStates) -> Vec<Outcome> {
+        let skippable_name = match &self.playing {
+            Some(playing) if playing.cutscene.skippable => playing.cutscene.name.clone(),
+            _ => return Vec::new(),
+        };
+        if key_states.is_key_pressed(Key::Escape) {
+            self.skip(&skippable_name)
+        }
+        else {
+            Vec::new()
+        }
+    }
+
+    /// Moves the cutscene on by delta_time and returns what happens in that time, followed by on_finished if it ended
+    pub fn update(&mut self, delta_time: f32) -> Vec<Outcome> {
+        let (previous_time, time, duration) = match &mut self.playing {
+            Some(playing) if !playing.paused => {
+                let previous_time = playing.time;
+                playing.time += delta_time;
+                (previous_time, playing.time, playing.cutscene.get_duration())
+            }
+            _ => return Vec::new(),
+        };
+
+        let mut outcomes = self.advance(previous_time, time, true);
+        if time >= duration {
+            outcomes.extend(self.finish());
+        }
+        outcomes
+    }
+
+    fn playing_named(&mut self, cutscene_name: &str) -> Option<&mut PlayingCutscene> {
+        self.playing.as_mut().filter(|playing| playing.cutscene.name == cutscene_name)
+    }
+
+    fn finish(&mut self) -> Vec<Outcome> {
+        match self.playing.take() {
+            Some(playing) => playing.cutscene.on_finished,
+            None => Vec::new(),
+        }
+    }
+
+    /// The outcomes for the stretch of the timeline after previous_time up to and including time
+    fn advance(&mut self, previous_time: f32, time: f32, play_audio: bool) -> Vec<Outcome> {
+        let playing = match &mut self.playing {
+            Some(playing) => playing,
+            None => return Vec::new(),
+        };
+        let cutscene = &playing.cutscene;
+        let mut outcomes = Vec::new();
+
+        while let Some(key) = cutscene.outcome_track.get(playing.next_outcome_key).filter(|key| key.time <= time) {
+            outcomes.extend(key.outcome.iter().cloned());
+            playing.next_outcome_key += 1;
+        }
+
+        while let Some(key) = cutscene.camera_target_track.get(playing.next_camera_target_key).filter(|key| key.time <= time) {
+            outcomes.push(Outcome::SetCameraTrackingTarget(SetCameraTrackingTargetArgs { tracking_target: key.target.clone() }));
+            playing.next_camera_target_key += 1;
+        }
+
+        while let Some(cue) = cutscene.audio_track.get(playing.next_audio_cue).filter(|cue| cue.time <= time) {
+            if play_audio {
+                outcomes.push(Outcome::EnqueueAudio(EnqueueAudioArgs { audio_name: cue.audio_name.clone(), audio_type: cue.audio_type.clone(), volume: cue.volume, looping: false }));
+            }
+            playing.next_audio_cue += 1;
+        }
+
+        for track in cutscene.position_tracks.iter() {
+            let times: Vec<f32> = track.keys.iter().map(|key| key.time).collect();
+            if let Some((index, amount)) = sample(&times, previous_time, time) {
+                let from = &track.keys[index].position;
+                let to = &track.keys[(index + 1).min(track.keys.len() - 1)].position;
+                let new_position = from.iter().zip(to.iter()).map(|(from, to)| from + (to - from) * amount).collect();
+                outcomes.push(Outcome::TeleportObject(TeleportObjectArgs { object_name: track.object_name.clone(), new_position, relative_to: None }));
+            }
+        }
+
+        let times: Vec<f32> = cutscene.camera_zoom_track.iter().map(|key| key.time).collect();
+        if let Some((index, amount)) = sample(&times, previous_time, time) {
+            let from = cutscene.camera_zoom_track[index].zoom;
+            let to = cutscene.camera_zoom_track[(index + 1).min(times.len() - 1)].zoom;
+            outcomes.push(Outcome::SetCameraZoom(SetCameraZoomArgs { zoom: from + (to - from) * amount }));
+        }
+
+        outcomes
+    }
+}
+
+/// Where time falls on a track of sorted key times, as the key it is past and how far it is towards the next one.
+/// None before the first key, and once the last key was already reached on an earlier update.
+fn sample(times: &[f32], previous_time: f32, time: f32) -> Option<(usize, f32)> {
+    let first = *times.first()?;
+    let last = *times.last()?;
+    if time < first || previous_time >= last {
+        return None;
+    }
+    if time >= last {
+        return Some((times.len() - 1, 0.0));
+    }
+
+    let index = times.iter().rposition(|key_time| *key_time <= time)?;
+    let span = times[index + 1] - times[index];
+    let amount = if span > 0.0 { (time - times[index]) / span } else { 1.0 };
+    Some((index, amount))
+}
@@ -0,0 +1,2 @@
+pub mod dialogue_runner;
+pub mod dialogue_tree;
\ No newline at end of file
@@ -0,0 +1,402 @@
+use std::{collections::{HashMap, HashSet}, fs};
+
+use glfw::Key;
+
+use crate::rusted_engine::{events::{conditions::check_variable_condition, triggers::Outcome}, game_state::GameState, input::key_states::KeyStates};
+
+use super::dialogue_tree::{load_dialogue_trees_from_json, DialogueChoice, DialogueNode, DialogueTree};
+
+/// Keys that pick the first nine choices on offer
+const CHOICE_KEYS: [Key; 9] = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9];
+
+/// Shows dialogue to the player. The runner only decides what is said, the presenter decides how it looks.
+pub trait DialoguePresenter {
+    /// Called whenever a node is entered, choices holds the text of the choices on offer in the order they can be picked
+    fn show_line(&mut self, speaker: &str, text: &str, choices: &[String]);
+    fn close(&mut self);
+}
+
+/// Prints dialogue to the console, until something better is hooked up
+pub struct ConsolePresenter;
+
+impl DialoguePresenter for ConsolePresenter {
+    fn show_line(&mut self, speaker: &str, text: &str, choices: &[String]) {
+        println!("{}: {}", speaker, text);
+        for (index, choice) in choices.iter().enumerate() {
+            println!("  {}. {}", index + 1, choice);
+        }
+    }
+
+    fn close(&mut self) {
+        println!("(The conversation ends)");
+    }
+}
+
+/// Keeps track of the dialogue being played. Nothing here renders or reads the keyboard directly,
+/// so a conversation can be stepped through with just a GameState.
+/// A node is only shown once the outcomes that led to it have been applied, see present_pending_line.
+pub struct DialogueRunner {
+    trees: HashMap<String, DialogueTree>,
+    current: Option<(String, String)>, // (tree name, node id) of the node being shown
+    shown_choices: Option<Vec<usize>>, // Indices of the choices the current node was shown with, None until it has been shown
+    presenter: Box<dyn DialoguePresenter + Send + Sync>,
+}
+
+impl DialogueRunner {
+    pub fn new(presenter: Box<dyn DialoguePresenter + Send + Sync>) -> Self {
+        Self {
+            trees: HashMap::new(),
+            current: None,
+            shown_choices: None,
+            presenter,
+        }
+    }
+
+    pub fn add_tree(&mut self, dialogue_tree: DialogueTree) {
+        self.trees.insert(dialogue_tree.name.clone(), dialogue_tree);
+    }
+
+    /// Loads every dialogue JSON file in the directory
+    pub fn load_dialogues_from_directory(&mut self, dir_path: &str) -> Result<(), String> {
+        let paths = fs::read_dir(dir_path).map_err(|_| "Failed to read directory".to_string())?;
+
+        for path in paths {
+            let entry = path.map_err(|_| "Failed to read directory entry".to_string())?;
+            let full_path = entry.path();
+
+            if full_path.is_file() && full_path.extension().is_some_and(|extension| extension == "json") {
+                let dialogue_trees = load_dialogue_trees_from_json(full_path.to_str().unwrap())
+                    .map_err(|e| format!("Error loading dialogue '{}': {}", full_path.display(), e))?;
+                for dialogue_tree in dialogue_trees {
+                    self.add_tree(dialogue_tree);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_dialogue_names(&self) -> HashSet<String> {
+        self.trees.keys().cloned().collect()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.current.is_some()
+    }
+
+    pub fn get_current_node(&self) -> Option<&DialogueNode> {
+        let (tree_name, node_id) = self.current.as_ref()?;
+        self.trees.get(tree_name)?.nodes.get(node_id)
+    }
+
+    /// The choices on offer, in the order they are numbered for the player.
+    /// Once the node has been shown these are the choices it was shown with, before that the ones whose conditions hold.
+    pub fn get_available_choices(&self, game_state: &GameState) -> Vec<&DialogueChoice> {
+        let Some(node) = self.get_current_node() else { return Vec::new(); };
+        match &self.shown_choices {
+            Some(shown_choices) => shown_choices.iter().filter_map(|index| node.choices.get(*index)).collect(),
+            None => node.choices.iter()
+                .filter(|choice| choice.conditions.iter().all(|condition| check_variable_condition(condition, game_state)))
+                .collect(),
+        }
+    }
+
+    /// Shows the current node if it hasn't been shown yet. Called after the outcomes that led to the node have been applied,
+    /// so the choices on offer are the ones whose conditions hold after them.
+    pub fn present_pending_line(&mut self, game_state: &GameState) {
+        if self.shown_choices.is_some() {
+            return;
+        }
+        let Some(node) = self.get_current_node() else { return; };
+
+        let shown_choices: Vec<usize> = node.choices.iter().enumerate()
+            .filter(|(_, choice)| choice.conditions.iter().all(|condition| check_variable_condition(condition, game_state)))
+            .map(|(index, _)| index)
+            .collect();
+        let choice_texts: Vec<String> = shown_choices.iter().map(|index| node.choices[*index].text.clone()).collect();
+        let (speaker, text) = (node.speaker.clone(), node.text.clone());
+
+        self.presenter.show_line(&speaker, &text, &choice_texts);
+        self.shown_choices = Some(shown_choices);
+    }
+
+    /// Starts a dialogue from its first node, replacing any that was running. Returns the first node's on_enter outcomes.
+    pub fn start(&mut self, dialogue_name: &str) -> Vec<Outcome> {
+        let start_node = match self.trees.get(dialogue_name) {
+            Some(dialogue_tree) => dialogue_tree.start_node.clone(),
+            None => {
+                println!("No dialogue found for dialogue name: {}", dialogue_name);
+                return Vec::new();
+            }
+        };
+        self.enter_node(dialogue_name.to_owned(), Some(start_node))
+    }
+
+    /// Picks one of the available choices, numbered from 0. Returns the choice's outcomes followed by the next node's on_enter outcomes.
+    pub fn choose(&mut self, choice_index: usize, game_state: &GameState) -> Vec<Outcome> {
+        self.present_pending_line(game_state);
+        let (outcomes, next) = match self.get_available_choices(game_state).get(choice_index) {
+            Some(choice) => (choice.outcome.clone(), choice.next.clone()),
+            None => return Vec::new(),
+        };
+        let tree_name = match &self.current {
+            Some((tree_name, _)) => tree_name.clone(),
+            None => return Vec::new(),
+        };
+
+        let mut outcomes = outcomes;
+        outcomes.extend(self.enter_node(tree_name, next));
+        outcomes
+    }
+
+    /// Moves past a node with no choices on offer. Nodes with choices have to be answered with choose.
+    pub fn advance(&mut self, game_state: &GameState) -> Vec<Outcome> {
+        self.present_pending_line(game_state);
+        if !self.get_available_choices(game_state).is_empty() {
+            return Vec::new();
+        }
+        let next = match self.get_current_node() {
+            Some(node) => node.next.clone(),
+            None => return Vec::new(),
+        };
+        let tree_name = match &self.current {
+            Some((tree_name, _)) => tree_name.clone(),
+            None => return Vec::new(),
+        };
+        self.enter_node(tree_name, next)
+    }
+
+    pub fn stop(&mut self) {
+        self.shown_choices = None;
+        if self.current.take().is_some() {
+            self.presenter.close();
+        }
+    }
+
+    /// Number keys pick a choice, space or enter move past a node without any.
+    /// While a dialogue is open those keys are consumed, so they don't also reach the scene triggers and actions.
+    pub fn process_input(&mut self, key_states: &mut KeyStates, game_state: &GameState) -> Vec<Outcome> {
+        if !self.is_active() {
+            return Vec::new();
+        }
+
+        let pressed_keys: Vec<Key> = CHOICE_KEYS.iter().chain([Key::Space, Key::Enter].iter())
+            .copied()
+            .filter(|key| key_states.is_key_pressed(*key))
+            .collect();
+        for key in pressed_keys.iter() {
+            key_states.consume_key(*key);
+        }
+
+        self.present_pending_line(game_state);
+        if !self.get_available_choices(game_state).is_empty() {
+            match CHOICE_KEYS.iter().position(|key| pressed_keys.contains(key)) {
+                Some(choice_index) => self.choose(choice_index, game_state),
+                None => Vec::new(),
+            }
+        }
+        else if pressed_keys.contains(&Key::Space) || pressed_keys.contains(&Key::Enter) {
+            self.advance(game_state)
+        }
+        else {
+            Vec::new()
+        }
+    }
+
+    /// Makes the node current and returns its on_enter outcomes, it is shown by present_pending_line
+    fn enter_node(&mut self, tree_name: String, node_id: Option<String>) -> Vec<Outcome> {
+        let node_id = match node_id {
+            Some(node_id) => node_id,
+            None => {
+                self.stop();
+                return Vec::new();
+            }
+        };
+
+        let node = match self.trees.get(&tree_name).and_then(|dialogue_tree| dialogue_tree.nodes.get(&node_id)) {
+            Some(node) => node.clone(),
+            None => {
+                println!("Dialogue {} has no node {}, ending it.", tree_name, node_id);
+                self.stop();
+                return Vec::new();
+            }
+        };
+
+        self.current = Some((tree_name, node_id));
+        self.shown_choices = None;
+        node.on_enter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use glfw::{Action, Modifiers, WindowEvent};
+
+    use crate::rusted_engine::game_state::GameVariable;
+
+    use super::*;
+
+    /// Keeps every line shown as "speaker: text [choices]", and "closed" when the dialogue ends
+    struct RecordingPresenter {
+        lines: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl DialoguePresenter for RecordingPresenter {
+        fn show_line(&mut self, speaker: &str, text: &str, choices: &[String]) {
+            self.lines.lock().unwrap().push(format!("{}: {} {:?}", speaker, text, choices));
+        }
+
+        fn close(&mut self) {
+            self.lines.lock().unwrap().push("closed".to_owned());
+        }
+    }
+
+    /// A shopkeeper who sells apples for 3 coins for as long as there are coins to pay with
+    fn shop_runner() -> (DialogueRunner, Arc<Mutex<Vec<String>>>) {
+        let dialogue_tree: DialogueTree = serde_json::from_str(r#"{
+            "name": "shop",
+            "start_node": "greeting",
+            "nodes": {
+                "greeting": { "speaker": "Shopkeeper", "text": "Welcome.", "next": "counter" },
+                "counter": {
+                    "speaker": "Shopkeeper",
+                    "text": "What will it be?",
+                    "choices": [
+                        {
+                            "text": "An apple.",
+                            "conditions": [{ "variable_name": "coins", "comparison": "GreaterOrEqual", "value": 3 }],
+                            "outcome": [{ "IncrementVariable": { "variable_name": "coins", "amount": -3 } }],
+                            "next": "counter"
+                        },
+                        { "text": "Nothing." }
+                    ]
+                }
+            }
+        }"#).unwrap();
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let mut dialogue_runner = DialogueRunner::new(Box::new(RecordingPresenter { lines: lines.clone() }));
+        dialogue_runner.add_tree(dialogue_tree);
+        (dialogue_runner, lines)
+    }
+
+    fn game_state_with_coins(coins: i64) -> GameState {
+        let mut game_state = GameState::new();
+        game_state.set_variable("coins".to_owned(), GameVariable::Int(coins));
+        game_state
+    }
+
+    fn press(key_states: &mut KeyStates, key: Key) {
+        key_states.handle_key_event(WindowEvent::Key(key, 0, Action::Press, Modifiers::empty()));
+    }
+
+    #[test]
+    fn nodes_are_followed_through_next_and_shown_once_presented() {
+        let (mut dialogue_runner, lines) = shop_runner();
+        let game_state = game_state_with_coins(0);
+
+        dialogue_runner.start("shop");
+        assert!(dialogue_runner.is_active());
+        // Nothing is shown until the outcomes that led to the node have had their turn
+        assert!(lines.lock().unwrap().is_empty());
+
+        dialogue_runner.present_pending_line(&game_state);
+        dialogue_runner.present_pending_line(&game_state);
+        dialogue_runner.advance(&game_state);
+        dialogue_runner.present_pending_line(&game_state);
+
+        assert_eq!(*lines.lock().unwrap(), vec![
+            "Shopkeeper: Welcome. []",
+            "Shopkeeper: What will it be? [\"Nothing.\"]",
+        ]);
+    }
+
+    #[test]
+    fn choices_whose_conditions_fail_are_left_out_of_the_numbering() {
+        let (mut dialogue_runner, _) = shop_runner();
+        let game_state = game_state_with_coins(0);
+        dialogue_runner.start("shop");
+        dialogue_runner.advance(&game_state);
+
+        // With no coins the first choice on offer is leaving
+        let outcomes = dialogue_runner.choose(0, &game_state);
+        assert!(outcomes.is_empty());
+        assert!(!dialogue_runner.is_active());
+    }
+
+    #[test]
+    fn choices_are_worked_out_after_the_chosen_outcomes_are_applied() {
+        let (mut dialogue_runner, lines) = shop_runner();
+        let mut game_state = game_state_with_coins(3);
+        dialogue_runner.start("shop");
+        dialogue_runner.advance(&game_state);
+
+        let outcomes = dialogue_runner.choose(0, &game_state);
+        assert_eq!(outcomes.len(), 1);
+        // The event handler applies the outcome before the counter is shown again
+        game_state.increment_variable("coins", GameVariable::Int(-3));
+        dialogue_runner.present_pending_line(&game_state);
+
+        assert_eq!(lines.lock().unwrap().last().unwrap(), "Shopkeeper: What will it be? [\"Nothing.\"]");
+        assert_eq!(dialogue_runner.get_available_choices(&game_state).len(), 1);
+    }
+
+    #[test]
+    fn the_choices_shown_stay_on_offer_until_one_is_picked() {
+        let (mut dialogue_runner, _) = shop_runner();
+        let mut game_state = game_state_with_coins(3);
+        dialogue_runner.start("shop");
+        dialogue_runner.advance(&game_state);
+        dialogue_runner.present_pending_line(&game_state);
+
+        // Spending the coins elsewhere doesn't renumber what the player is looking at
+        game_state.set_variable("coins".to_owned(), GameVariable::Int(0));
+        assert_eq!(dialogue_runner.get_available_choices(&game_state).len(), 2);
+    }
+
+    #[test]
+    fn the_dialogue_ends_on_a_node_that_leads_nowhere() {
+        let (mut dialogue_runner, lines) = shop_runner();
+        let game_state = game_state_with_coins(0);
+        dialogue_runner.start("shop");
+        dialogue_runner.advance(&game_state);
+        dialogue_runner.choose(0, &game_state);
+
+        assert!(!dialogue_runner.is_active());
+        assert!(dialogue_runner.get_current_node().is_none());
+        assert_eq!(lines.lock().unwrap().last().unwrap(), "closed");
+        // Nothing left to answer
+        assert!(dialogue_runner.advance(&game_state).is_empty());
+    }
+
+    #[test]
+    fn the_keys_a_dialogue_uses_are_consumed() {
+        let (mut dialogue_runner, _) = shop_runner();
+        let game_state = game_state_with_coins(3);
+        let mut key_states = KeyStates::new();
+        dialogue_runner.start("shop");
+
+        press(&mut key_states, Key::Space);
+        dialogue_runner.process_input(&mut key_states, &game_state);
+        assert!(!key_states.is_key_pressed(Key::Space));
+        assert!(!key_states.is_key_pressed_raw(Key::Space));
+
+        press(&mut key_states, Key::Num1);
+        let outcomes = dialogue_runner.process_input(&mut key_states, &game_state);
+        assert_eq!(outcomes.len(), 1);
+        assert!(!key_states.is_key_pressed_raw(Key::Num1));
+    }
+
+    #[test]
+    fn keys_are_left_alone_without_a_dialogue() {
+        let (mut dialogue_runner, _) = shop_runner();
+        let game_state = game_state_with_coins(3);
+        let mut key_states = KeyStates::new();
+
+        press(&mut key_states, Key::Space);
+        dialogue_runner.process_input(&mut key_states, &game_state);
+        assert!(key_states.is_key_pressed(Key::Space));
+    }
+}
@@ -0,0 +1,46 @@
+use std::{collections::HashMap, fs::File, io::Read};
+
+use serde::{Deserialize, Serialize};
+
+use crate::rusted_engine::events::triggers::{Outcome, VariableCondition};
+
+/// A conversation. Starts at start_node and follows choices, or next on nodes without any, until it reaches a node that leads nowhere.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DialogueTree {
+    pub name: String,
+    pub start_node: String,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DialogueNode {
+    pub speaker: String,
+    pub text: String,
+    #[serde(default)]
+    pub on_enter: Vec<Outcome>, // Queued as soon as the node is shown
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+    #[serde(default)]
+    pub next: Option<String>, // Where continuing leads on a node without choices, the dialogue ends if missing
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DialogueChoice {
+    pub text: String,
+    #[serde(default)]
+    pub conditions: Vec<VariableCondition>, // All of them have to hold for the choice to be offered
+    #[serde(default)]
+    pub outcome: Vec<Outcome>, // Queued when the choice is picked
+    #[serde(default)]
+    pub next: Option<String>, // The dialogue ends if missing
+}
+
+/// Loads a dialogue file, a JSON list of dialogue trees
+pub fn load_dialogue_trees_from_json(file_path: &str) -> Result<Vec<DialogueTree>, Box<dyn std::error::Error>> {
+    let mut file = File::open(file_path)?;
+    let mut data = String::new();
+    file.read_to_string(&mut data)?;
+
+    let dialogue_trees: Vec<DialogueTree> = serde_json::from_str(&data)?;
+    Ok(dialogue_trees)
+}
@@ -1,9 +1,9 @@
-use std::sync::{Arc, RwLock};
+use std::{fs, sync::{Arc, RwLock, TryLockError}};
 
 use glfw::{Context, GlfwReceiver, WindowEvent};
 use rusted_open::framework::{framework_controller::FrameworkController, graphics::{texture_manager::TextureManager, util::master_graphics_list::MasterGraphicsList}};
 
-use super::{audio::audio_manager::AudioManager, entities::util::master_entity_list::MasterEntityList, events::{event_handler::EventHandler, piano_sequences, player_movement::{self, gravity}}, game_state::GameState, input::{key_states::KeyStates, piano::Piano}, scenes::scene_manager::SceneManager, util::master_clock::MasterClock};
+use super::{audio::audio_manager::AudioManager, cutscenes::cutscene_player::CutscenePlayer, dialogue::dialogue_runner::{DialoguePresenter, DialogueRunner}, entities::util::master_entity_list::MasterEntityList, events::{event_bus::EventBus, event_handler::{EventHandler, EventHandlerResources}, trace_log::{TraceLog, DEFAULT_TRACE_CAPACITY}, piano_sequences, player_movement::{self, buoyancy, gravity}}, game_state::GameState, input::{action_map::ActionMap, gamepad_states::GamepadStates, key_states::KeyStates, piano::Piano}, quests::quest_tracker::QuestTracker, scenes::{scene_manager::SceneManager, scene_validator::{names_in_directories, validate_outcome_files, validate_scenes_in_directory, OutcomeFileKind, ValidationContext}}, util::master_clock::MasterClock};
 
 pub struct EngineController {
     glfw: glfw::Glfw,
@@ -15,12 +15,19 @@ pub struct EngineController {
     scene_manager: Arc<RwLock<SceneManager>>,
     audio_manager: Arc<RwLock<AudioManager>>,
     key_states: Arc<RwLock<KeyStates>>,
+    gamepad_states: Arc<RwLock<GamepadStates>>,
     game_state: Arc<RwLock<GameState>>,
+    event_bus: Arc<RwLock<EventBus>>,
+    trace_log: Arc<RwLock<TraceLog>>,
+    dialogue_runner: Arc<RwLock<DialogueRunner>>,
+    cutscene_player: Arc<RwLock<CutscenePlayer>>,
+    quest_tracker: Arc<RwLock<QuestTracker>>,
+    action_map: Arc<RwLock<ActionMap>>,
 }
 
 impl EngineController {
-    /// Creates a new EntryPoint instance.
-    pub fn new() -> Self {
+    /// Creates a new EntryPoint instance. Dialogue is shown through the presenter, ConsolePresenter prints it.
+    pub fn new(dialogue_presenter: Box<dyn DialoguePresenter + Send + Sync>) -> Self {
         let mut glfw = glfw::init(glfw::fail_on_errors).unwrap();
 
         glfw.window_hint(glfw::WindowHint::Resizable(false));
@@ -63,12 +70,61 @@ impl EngineController {
             scene_manager: Arc::new(RwLock::new(SceneManager::new())),
             audio_manager: Arc::new(RwLock::new(AudioManager::new())),
             key_states: Arc::new(RwLock::new(KeyStates::new())),
+            gamepad_states: Arc::new(RwLock::new(GamepadStates::new())),
             game_state: Arc::new(RwLock::new(GameState::new())),
+            event_bus: Arc::new(RwLock::new(EventBus::new())),
+            trace_log: Arc::new(RwLock::new(TraceLog::new(DEFAULT_TRACE_CAPACITY))),
+            dialogue_runner: Arc::new(RwLock::new(DialogueRunner::new(dialogue_presenter))),
+            cutscene_player: Arc::new(RwLock::new(CutscenePlayer::new())),
+            quest_tracker: Arc::new(RwLock::new(QuestTracker::new())),
+            action_map: Arc::new(RwLock::new(ActionMap::new())),
         }
     }
 
+    /// Subscribe to this before calling init to hear about collisions, scene swaps and the like
+    pub fn get_event_bus(&self) -> Arc<RwLock<EventBus>> {
+        self.event_bus.clone()
+    }
+
+    /// Rebind actions through this, the changes are saved to src\resources\input\user_bindings.json
+    pub fn get_action_map(&self) -> Arc<RwLock<ActionMap>> {
+        self.action_map.clone()
+    }
+
+    /// Change the stick and trigger dead zones through this
+    pub fn get_gamepad_states(&self) -> Arc<RwLock<GamepadStates>> {
+        self.gamepad_states.clone()
+    }
+
+    /// Dumps the trace log before the default panic message, so a crash leaves a record of the scene logic that led up to it
+    fn install_panic_trace_dump(&self) {
+        let trace_log = self.trace_log.clone();
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |panic_info| {
+            // The panic may have happened while the log was locked, a poisoned log is still worth writing out
+            let trace_log = match trace_log.try_read() {
+                Ok(trace_log) => Some(trace_log),
+                Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+                Err(TryLockError::WouldBlock) => None,
+            };
+            match trace_log {
+                Some(trace_log) => {
+                    let _ = fs::create_dir_all("src\\resources\\traces");
+                    match trace_log.dump_to_json("src\\resources\\traces\\panic_trace.json", None, None) {
+                        Ok(()) => println!("Dumped the trace log to src\\resources\\traces\\panic_trace.json"),
+                        Err(e) => println!("Failed to dump the trace log on panic: {}", e),
+                    }
+                }
+                None => println!("The trace log was in use when the panic happened, it could not be dumped."),
+            }
+            default_hook(panic_info);
+        }));
+    }
+
     // Call from main to start everything
     pub fn init(&mut self) {
+        self.install_panic_trace_dump();
+
         let window_size = self.window.get_size();
         self.set_resolution(window_size.0 as f32, window_size.1 as f32);
 
@@ -76,7 +132,23 @@ impl EngineController {
         let texture_manager = self.framework_controller.get_texture_manager();
         let master_graphics_list = self.framework_controller.get_master_graphics_list();
         let camera = self.framework_controller.get_camera();
-        let mut event_handler = EventHandler::new(camera.clone(), self.master_entity_list.clone(), master_graphics_list.clone(), texture_manager.clone(), self.audio_manager.clone(), self.scene_manager.clone(), self.game_state.clone(), self.key_states.clone());
+        let mut event_handler = EventHandler::new(EventHandlerResources {
+            camera: camera.clone(),
+            master_entity_list: self.master_entity_list.clone(),
+            master_graphics_list: master_graphics_list.clone(),
+            texture_manager: texture_manager.clone(),
+            audio_manager: self.audio_manager.clone(),
+            scene_manager: self.scene_manager.clone(),
+            game_state: self.game_state.clone(),
+            key_states: self.key_states.clone(),
+            event_bus: self.event_bus.clone(),
+            trace_log: self.trace_log.clone(),
+            dialogue_runner: self.dialogue_runner.clone(),
+            cutscene_player: self.cutscene_player.clone(),
+            quest_tracker: self.quest_tracker.clone(),
+            action_map: self.action_map.clone(),
+            gamepad_states: self.gamepad_states.clone(),
+        });
         camera.write().unwrap().set_tracking_target(Some("player".to_owned()));
         camera.write().unwrap().set_zoom(1.0);
 
@@ -84,15 +156,17 @@ impl EngineController {
 
         // Go into this function to see how the loading is done.
         self.load_resources(&texture_manager.write().unwrap());
+        // Scripts are compiled by the event handler, which is what runs them
+        event_handler.load_scripts_from_directory("src\\resources\\scripts");
 
         // Create a Piano instance
-        let mut piano = Piano::new(self.audio_manager.clone(), self.key_states.clone());
+        let mut piano = Piano::new(self.audio_manager.clone(), self.key_states.clone(), self.gamepad_states.clone(), self.action_map.clone());
 
         let mut flag = false;
 
-        // Load the test scene from the manager into the master graphics list
+        // Load the test scene from the manager into the master graphics list, going through the event handler so its SceneEnter and OnSpawn triggers fire
         let scene_name = "testscene";
-        self.scene_manager.read().unwrap().load_scene(&mut self.game_state.write().unwrap(), &self.master_entity_list.read().unwrap(), &master_graphics_list.read().unwrap(), scene_name.to_owned());
+        event_handler.swap_scene_without_saving(scene_name.to_owned());
 
         while flag == false {
             flag = self.main_loop(&mut event_handler, master_graphics_list.clone(), &mut piano);
@@ -106,6 +180,7 @@ impl EngineController {
 
         // Thou shalt not use frame-based physics.
         let delta_time = self.master_clock.read().unwrap().get_delta_time();
+        self.trace_log.write().unwrap().next_frame();
 
         self.execute_tick(delta_time);
 
@@ -113,7 +188,17 @@ impl EngineController {
             return true;
         }
 
-        gravity(self.game_state.read().unwrap().get_gravity(), self.game_state.read().unwrap().get_terminal_velocity(), &self.master_entity_list.read().unwrap(), delta_time);
+        {
+            let game_state = self.game_state.read().unwrap();
+            gravity(game_state.get_gravity(), game_state.get_terminal_velocity(), game_state.get_physics_zones(), &self.master_entity_list.read().unwrap(), &master_graphics_list.read().unwrap(), delta_time);
+        }
+
+        // Fluids push back against gravity, so this has to come straight after it
+        let submerged_entities = {
+            let game_state = self.game_state.read().unwrap();
+            buoyancy(game_state.get_gravity(), game_state.get_physics_zones(), game_state.get_fluid_volumes(), &self.master_entity_list.read().unwrap(), &master_graphics_list.read().unwrap(), delta_time)
+        };
+        event_handler.process_fluid_transitions(submerged_entities);
 
         //Print debug info about the player entity
         //self.master_entity_list.read().unwrap().get_entity("player").unwrap().read().unwrap().print_debug();
@@ -122,21 +207,39 @@ impl EngineController {
         // Do movement inputs
         player_movement::process_all_entities_fake_friction(1.5, 0.1, &self.master_entity_list.read().unwrap(), true, delta_time);
 
-        // Process piano inputs, returns true if a piano input was made
-        if piano.process_piano_keys() {
-            piano_sequences::check_piano_sequences(piano, &event_handler);
+        // A key pressed while an action is being rebound becomes its new binding
+        self.action_map.write().unwrap().process_rebind(&mut self.key_states.write().unwrap(), &mut self.gamepad_states.write().unwrap());
+
+        // An open dialogue takes its answers before the piano and the scene triggers see the keys
+        event_handler.process_dialogue_input();
+
+        // Process piano inputs, returns true if a piano input was made. The piano stays silent while a cutscene has the controls.
+        let input_locked = self.game_state.read().unwrap().is_input_locked();
+        if !input_locked && piano.process_piano_keys() {
+            piano_sequences::check_piano_sequences(piano, event_handler);
         }
 
+        // A cutscene can be skipped
+        event_handler.process_cutscene_input();
+
         // These triggers have priority, they are checked before any others, which means their events are added to the event_handler first.
-        event_handler.check_scene_triggers();
+        event_handler.check_scene_triggers(delta_time);
 
         // Call the collision checking method
         event_handler.process_collisions();
 
+        event_handler.check_area_and_speed_triggers();
+
+        // Entity scripts get their turn after the triggers, anything they queue is processed with the rest
+        event_handler.run_script_updates(delta_time);
+
         event_handler.process_event_outcomes(delta_time);
 
         player_movement::process_movement(&self.master_entity_list.read().unwrap(), &master_graphics_list.read().unwrap(), delta_time);
 
+        // Subscribers hear about the whole frame at once, after everything above has settled
+        self.event_bus.write().unwrap().dispatch();
+
         return false;
     }
 
@@ -160,6 +263,8 @@ impl EngineController {
                 }
             }
         }
+        // Gamepads don't send events, they are read directly
+        self.gamepad_states.write().unwrap().update(&self.glfw);
 
         self.framework_controller.render(&mut self.window, delta_time);
     }
@@ -169,16 +274,65 @@ impl EngineController {
         let mut scene_manager = self.scene_manager.write().unwrap();
         let audio_manager = self.audio_manager.read().unwrap();
 
+        // Bindings first, the piano and the scene triggers only know about actions
+        if let Err(e) = self.action_map.write().unwrap().load_bindings("src\\resources\\input\\bindings.json", "src\\resources\\input\\user_bindings.json") {
+            println!("Failed to load the key bindings: {}", e);
+        }
+
         // Load the texture files and the scenes from their respective directories into memory
         let _ = texture_manager.load_textures_from_directory("src\\resources\\textures");
         let _ = texture_manager.load_textures_from_directory("src\\resources\\localonly\\textures");
+        // Materials first, scenes resolve them while loading
+        if let Err(e) = scene_manager.load_materials_from_json("src\\resources\\materials\\materials.json") {
+            println!("{}", e);
+        }
         let _ = scene_manager.load_scenes_from_directory("src\\resources\\scenes", &texture_manager);
+        if let Err(e) = scene_manager.load_sequences_from_directory("src\\resources\\sequences") {
+            println!("{}", e);
+        }
+        if let Err(e) = scene_manager.load_piano_sequences_from_directory("src\\resources\\pianosequences") {
+            println!("{}", e);
+        }
         let _ = audio_manager.load_sounds_from_directory("src\\resources\\sounds");
         let _ = audio_manager.load_sounds_from_directory("src\\resources\\sounds\\piano");
         let _ = audio_manager.load_sounds_from_directory("src\\resources\\sounds\\pianosequences");
         // Load resources which should not be uploaded
         let _ = audio_manager.load_sounds_from_directory("src\\resources\\localonly\\music");
         let _ = audio_manager.load_sounds_from_directory("src\\resources\\localonly\\sounds");
+        if let Err(e) = self.dialogue_runner.write().unwrap().load_dialogues_from_directory("src\\resources\\dialogues") {
+            println!("{}", e);
+        }
+        if let Err(e) = self.cutscene_player.write().unwrap().load_cutscenes_from_directory("src\\resources\\cutscenes") {
+            println!("{}", e);
+        }
+        if let Err(e) = self.quest_tracker.write().unwrap().load_quests_from_directory("src\\resources\\quests") {
+            println!("{}", e);
+        }
+        self.quest_tracker.read().unwrap().start_auto_quests(&mut self.game_state.write().unwrap());
+
+        // With everything loaded, check that the scenes only refer to things that exist
+        let validation_context = ValidationContext {
+            scene_names: scene_manager.list_scenes().into_iter().collect(),
+            sound_names: audio_manager.get_sound_names(),
+            texture_names: names_in_directories(&["src\\resources\\textures", "src\\resources\\localonly\\textures"], &["png", "jpg", "jpeg"]),
+            material_names: scene_manager.get_material_names(),
+            sequence_names: scene_manager.get_sequence_names(),
+            script_names: names_in_directories(&["src\\resources\\scripts"], &["rhai"]),
+            dialogue_names: self.dialogue_runner.read().unwrap().get_dialogue_names(),
+            cutscene_names: self.cutscene_player.read().unwrap().get_cutscene_names(),
+            quest_names: self.quest_tracker.read().unwrap().get_quest_names(),
+            action_names: self.action_map.read().unwrap().get_action_names(),
+        };
+        let mut validation_report = validate_scenes_in_directory("src\\resources\\scenes", &validation_context);
+        let outcome_dirs = [
+            ("src\\resources\\dialogues", OutcomeFileKind::Dialogues),
+            ("src\\resources\\cutscenes", OutcomeFileKind::Cutscenes),
+            ("src\\resources\\quests", OutcomeFileKind::Quests),
+            ("src\\resources\\sequences", OutcomeFileKind::Sequences),
+            ("src\\resources\\pianosequences", OutcomeFileKind::PianoSequences),
+        ];
+        validation_report.append(validate_outcome_files("src\\resources\\scenes", &outcome_dirs, &validation_context));
+        validation_report.print();
     }
 
     pub fn set_resolution(&mut self, width: f32, height: f32) {
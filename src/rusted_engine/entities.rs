@@ -1,2 +1,3 @@
 pub mod generic_entity;
+pub mod physics_material;
 pub mod util;
\ No newline at end of file
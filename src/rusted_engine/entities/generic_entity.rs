@@ -4,17 +4,54 @@ use nalgebra::Vector2;
 
 use crate::rusted_engine::events::triggers::Trigger;
 
+// Below these speeds an entity counts as being at rest
+const SLEEP_LINEAR_THRESHOLD: f32 = 0.01;
+const SLEEP_ANGULAR_THRESHOLD: f32 = 0.01;
+// How long an entity has to be at rest before it falls asleep, in seconds
+const TIME_UNTIL_SLEEP: f32 = 1.0;
+
 pub struct GenericEntity {
     name: String,
     weight: f32,
     velocity: Vector2<f32>,
+    angular_velocity: f32,
+    moment_of_inertia: f32,
+    torque: f32, // Accumulated torque, consumed on the next integration step
     affected_by_gravity: bool,
+    gravity_scale: f32,
+    linear_drag: f32,
     is_static: bool,
     elasticity: f32,
+    friction: f32,
     active_collision: bool,
     collision_priority: u64,
     collision_modes: HashSet<CollisionMode>,
     triggers: Vec<Trigger>,
+    allow_sleep: bool,
+    sleeping: bool,
+    rest_time: f32, // How long the entity has been at rest while awake
+    script: Option<String>, // Name of the script whose on_update and on_collision hooks run for this entity
+}
+
+/// What an entity starts out with, everything else about it (torque, sleep) starts empty
+pub struct EntityConfig {
+    pub name: String,
+    pub weight: f32,
+    pub velocity: Vector2<f32>,
+    pub angular_velocity: f32,
+    pub moment_of_inertia: f32,
+    pub affected_by_gravity: bool,
+    pub gravity_scale: f32,
+    pub linear_drag: f32,
+    pub is_static: bool,
+    pub elasticity: f32,
+    pub friction: f32,
+    pub active_collision: bool,
+    pub collision_priority: u64,
+    pub collision_modes: HashSet<CollisionMode>,
+    pub triggers: Vec<Trigger>,
+    pub allow_sleep: bool,
+    pub script: Option<String>,
 }
 
 impl Clone for GenericEntity {
@@ -23,30 +60,51 @@ impl Clone for GenericEntity {
             name: self.name.clone(),
             weight: self.weight,
             velocity: self.velocity,
+            angular_velocity: self.angular_velocity,
+            moment_of_inertia: self.moment_of_inertia,
+            torque: self.torque,
             affected_by_gravity: self.affected_by_gravity,
+            gravity_scale: self.gravity_scale,
+            linear_drag: self.linear_drag,
             is_static: self.is_static,
             elasticity: self.elasticity,
+            friction: self.friction,
             active_collision: self.active_collision,
             collision_priority: self.collision_priority,
             collision_modes: self.collision_modes.clone(),
             triggers: self.triggers.clone(),
+            allow_sleep: self.allow_sleep,
+            sleeping: self.sleeping,
+            rest_time: self.rest_time,
+            script: self.script.clone(),
         }
     }
 }
 
 impl GenericEntity {
-    pub fn new(name: String, weight: f32, velocity: Vector2<f32>, affected_by_gravity: bool, is_static: bool, elasticity: f32, active_collision: bool, collision_priority: u64, collision_modes: HashSet<CollisionMode>, triggers: Vec<Trigger>,) -> Self {
+    pub fn new(config: EntityConfig) -> Self {
+        let EntityConfig { name, weight, velocity, angular_velocity, moment_of_inertia, affected_by_gravity, gravity_scale, linear_drag, is_static, elasticity, friction, active_collision, collision_priority, collision_modes, triggers, allow_sleep, script } = config;
         GenericEntity {
             name,
             weight,
             velocity,
+            angular_velocity,
+            moment_of_inertia,
+            torque: 0.0,
             affected_by_gravity,
+            gravity_scale,
+            linear_drag,
             is_static,
             elasticity,
+            friction,
             active_collision,
             collision_priority,
             collision_modes,
             triggers,
+            allow_sleep,
+            sleeping: false,
+            rest_time: 0.0,
+            script,
         }
     }
 
@@ -63,10 +121,30 @@ impl GenericEntity {
         self.velocity
     }
 
+    pub fn get_angular_velocity(&self) -> f32 {
+        self.angular_velocity
+    }
+
+    pub fn get_moment_of_inertia(&self) -> f32 {
+        self.moment_of_inertia
+    }
+
+    pub fn get_torque(&self) -> f32 {
+        self.torque
+    }
+
     pub fn is_affected_by_gravity(&self) -> bool {
         self.affected_by_gravity
     }
 
+    pub fn get_gravity_scale(&self) -> f32 {
+        self.gravity_scale
+    }
+
+    pub fn get_linear_drag(&self) -> f32 {
+        self.linear_drag
+    }
+
     pub fn is_static(&self) -> bool {
         self.is_static
     }
@@ -75,6 +153,10 @@ impl GenericEntity {
         self.elasticity
     }
 
+    pub fn get_friction(&self) -> f32 {
+        self.friction
+    }
+
     pub fn has_active_collision(&self) -> bool {
         self.active_collision
     }
@@ -91,6 +173,23 @@ impl GenericEntity {
         &self.triggers
     }
 
+    pub fn allows_sleep(&self) -> bool {
+        self.allow_sleep
+    }
+
+    pub fn get_script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    /// True if the entity is moving fast enough to wake up anything it touches
+    pub fn is_moving(&self) -> bool {
+        self.velocity.magnitude() > SLEEP_LINEAR_THRESHOLD || self.angular_velocity.abs() > SLEEP_ANGULAR_THRESHOLD
+    }
+
     // Setters
     pub fn set_name(&mut self, name: String) {
         self.name = name;
@@ -104,10 +203,26 @@ impl GenericEntity {
         self.velocity = velocity;
     }
 
+    pub fn set_angular_velocity(&mut self, angular_velocity: f32) {
+        self.angular_velocity = angular_velocity;
+    }
+
+    pub fn set_moment_of_inertia(&mut self, moment_of_inertia: f32) {
+        self.moment_of_inertia = moment_of_inertia;
+    }
+
     pub fn set_affected_by_gravity(&mut self, affected_by_gravity: bool) {
         self.affected_by_gravity = affected_by_gravity;
     }
 
+    pub fn set_gravity_scale(&mut self, gravity_scale: f32) {
+        self.gravity_scale = gravity_scale;
+    }
+
+    pub fn set_linear_drag(&mut self, linear_drag: f32) {
+        self.linear_drag = linear_drag;
+    }
+
     pub fn set_is_static(&mut self, is_static: bool) {
         self.is_static = is_static;
     }
@@ -116,6 +231,10 @@ impl GenericEntity {
         self.elasticity = elasticity;
     }
 
+    pub fn set_friction(&mut self, friction: f32) {
+        self.friction = friction;
+    }
+
     pub fn set_active_collision(&mut self, active_collision: bool) {
         self.active_collision = active_collision;
     }
@@ -132,12 +251,23 @@ impl GenericEntity {
         self.triggers = triggers;
     }
 
+    pub fn set_script(&mut self, script: Option<String>) {
+        self.script = script;
+    }
+
+    pub fn set_allow_sleep(&mut self, allow_sleep: bool) {
+        self.allow_sleep = allow_sleep;
+        if !allow_sleep {
+            self.wake();
+        }
+    }
+
     // Game Logic
 
     pub fn apply_gravity(&mut self, gravity: Vector2<f32>, terminal_velocity: Vector2<f32>, delta_time: f32) {
     if self.affected_by_gravity {
         // Apply gravity to the velocity
-        self.velocity += gravity * delta_time;
+        self.velocity += gravity * self.gravity_scale * delta_time;
         
         // Clamp the velocity to the terminal velocity
         self.velocity.x = self.velocity.x.clamp(-terminal_velocity.x, terminal_velocity.x);
@@ -145,6 +275,64 @@ impl GenericEntity {
     }
 }
 
+    /// Slows the entity down in proportion to its speed
+    pub fn apply_drag(&mut self, delta_time: f32) {
+        if self.linear_drag > 0.0 {
+            self.velocity *= (1.0 - self.linear_drag * delta_time).max(0.0);
+        }
+    }
+
+    /// Adds torque to be applied on the next call to integrate_torque
+    pub fn apply_torque(&mut self, torque: f32) {
+        self.torque += torque;
+    }
+
+    /// Instantly changes the angular velocity by impulse / moment of inertia
+    pub fn apply_angular_impulse(&mut self, impulse: f32) {
+        if !self.is_static && self.moment_of_inertia > 0.0 {
+            self.angular_velocity += impulse / self.moment_of_inertia;
+        }
+    }
+
+    /// Converts the accumulated torque into angular velocity and clears it
+    pub fn integrate_torque(&mut self, delta_time: f32) {
+        if !self.is_static && self.moment_of_inertia > 0.0 {
+            self.angular_velocity += (self.torque / self.moment_of_inertia) * delta_time;
+        }
+        self.torque = 0.0;
+    }
+
+    /// Wakes the entity so it takes part in integration and collision again
+    pub fn wake(&mut self) {
+        self.sleeping = false;
+        self.rest_time = 0.0;
+    }
+
+    /// Puts the entity to sleep. Sleeping entities skip integration and collision until something wakes them.
+    pub fn sleep(&mut self) {
+        self.sleeping = true;
+        self.rest_time = 0.0;
+        self.velocity = Vector2::new(0.0, 0.0);
+        self.angular_velocity = 0.0;
+        self.torque = 0.0;
+    }
+
+    /// Tracks how long the entity has been at rest and puts it to sleep once it has been still for long enough
+    pub fn update_sleep(&mut self, delta_time: f32) {
+        if !self.allow_sleep || self.sleeping {
+            return;
+        }
+
+        if self.is_moving() || self.torque != 0.0 {
+            self.rest_time = 0.0;
+            return;
+        }
+
+        self.rest_time += delta_time;
+        if self.rest_time >= TIME_UNTIL_SLEEP {
+            self.sleep();
+        }
+    }
 
     // Debug
 
@@ -153,12 +341,25 @@ impl GenericEntity {
         println!("Name: {}", self.name);
         println!("Weight: {}", self.weight);
         println!("Velocity: {}", self.velocity);
+        println!("Elasticity: {}", self.elasticity);
+        println!("Friction: {}", self.friction);
+        println!("Angular Velocity: {}", self.angular_velocity);
+        println!("Moment of Inertia: {}", self.moment_of_inertia);
         println!("Affected by Gravity: {}", self.affected_by_gravity);
+        println!("Gravity Scale: {}", self.gravity_scale);
+        println!("Linear Drag: {}", self.linear_drag);
         println!("Active Collision: {}", self.active_collision);
+        println!("Sleeping: {}", self.sleeping);
+        println!("Script: {:?}", self.script);
         println!("Collision mode(s): {:?}", self.collision_modes)
     }
 }
 
+/// Moment of inertia of a solid rectangle rotating around its center, used when a scene doesn't provide one
+pub fn rectangle_moment_of_inertia(weight: f32, width: f32, height: f32) -> f32 {
+    weight * (width * width + height * height) / 12.0
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CollisionMode {
     AABB,
@@ -0,0 +1,22 @@
+use std::{collections::HashMap, fs::File, io::Read};
+
+use serde::{Deserialize, Serialize};
+
+/// Physical properties shared by every entity made of the same stuff.
+/// Entities reference a material by name and can still override any of these values themselves.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhysicsMaterial {
+    pub restitution: f32, // Used as the entity elasticity
+    pub friction: f32, // Multiplier on the surface friction, 1.0 behaves like an entity without a material
+    pub density: f32, // Weight per unit of area, used when the entity doesn't give a weight
+}
+
+/// Loads a material file, a JSON object mapping material names to their properties
+pub fn load_materials_from_json(file_path: &str) -> Result<HashMap<String, PhysicsMaterial>, Box<dyn std::error::Error>> {
+    let mut file = File::open(file_path)?;
+    let mut data = String::new();
+    file.read_to_string(&mut data)?;
+
+    let materials: HashMap<String, PhysicsMaterial> = serde_json::from_str(&data)?;
+    Ok(materials)
+}
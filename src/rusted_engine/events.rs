@@ -1,5 +1,11 @@
+pub mod event_bus;
 pub mod event_handler;
 pub mod collision;
+pub mod conditions;
 pub mod piano_sequences;
+pub mod placeholders;
 pub mod player_movement;
+pub mod scheduler;
+pub mod sequences;
+pub mod trace_log;
 pub mod triggers;
\ No newline at end of file
@@ -1,11 +1,14 @@
 use std::sync::{Arc, RwLock};
 
-use nalgebra::Vector3;
+use nalgebra::{Vector2, Vector3};
 use rusted_open::framework::graphics::{internal_object::graphics_object::Generic2DGraphicsObject, util::master_graphics_list::MasterGraphicsList};
 use rusted_open::framework::events::movement;
 
 use crate::rusted_engine::entities::{generic_entity::{CollisionMode, GenericEntity}, util::master_entity_list::MasterEntityList};
 
+// How close two corners have to be along the collision normal to count as the same face, in world units
+const FACE_TOLERANCE: f32 = 0.001;
+
 #[derive(Debug, PartialEq)]
 pub struct CollisionEvent {
     pub object_name_1: String,
@@ -19,7 +22,8 @@ pub fn check_active_entity_collisions(master_entity_list: Arc<RwLock<MasterEntit
     let mut relevant_names = Vec::new();
     for entity in entities.values() {
         if let Ok(entity) = entity.read() {
-            if entity.has_active_collision() {
+            // Sleeping entities don't look for collisions themselves, but can still be hit by entities that are awake
+            if entity.has_active_collision() && !entity.is_sleeping() {
                 relevant_names.push(entity.get_name().to_owned());
             }
         }
@@ -109,10 +113,131 @@ fn is_colliding_circle(object_1_read: &Generic2DGraphicsObject,  object_2_read:
     return distance_squared < radius_sum * radius_sum;
 }
 
-fn is_colliding_obb() -> bool {
-    // Implement OBB collision logic here
-    unimplemented!("OBB collision not yet implemented");
-}  
+fn is_colliding_obb(object_1_read: &Generic2DGraphicsObject,  object_2_read: &Generic2DGraphicsObject) -> bool {
+    obb_penetration(&OrientedBox::from_object(object_1_read), &OrientedBox::from_object(object_2_read)).is_some()
+}
+
+/// A rotated rectangle in world space, what the OBB checks work on
+#[derive(Debug, Clone, Copy)]
+pub struct OrientedBox {
+    pub center: Vector2<f32>,
+    pub half_extents: Vector2<f32>,
+    pub rotation: f32,
+}
+
+impl OrientedBox {
+    pub fn from_object(object: &Generic2DGraphicsObject) -> Self {
+        let (width, height) = object.dimensions();
+        let position = object.get_position();
+        Self {
+            center: Vector2::new(position.x, position.y),
+            half_extents: Vector2::new(width / 2.0, height / 2.0),
+            rotation: object.get_rotation(),
+        }
+    }
+
+    // The four corners of the box
+    fn corners(&self) -> [Vector2<f32>; 4] {
+        let (axis_x, axis_y) = self.axes();
+        let half_x = axis_x * self.half_extents.x;
+        let half_y = axis_y * self.half_extents.y;
+
+        [
+            self.center + half_x + half_y,
+            self.center + half_x - half_y,
+            self.center - half_x - half_y,
+            self.center - half_x + half_y,
+        ]
+    }
+
+    // The local X and Y axes of the box after rotation
+    fn axes(&self) -> (Vector2<f32>, Vector2<f32>) {
+        let (sin, cos) = self.rotation.sin_cos();
+        (Vector2::new(cos, sin), Vector2::new(-sin, cos))
+    }
+
+    // Half the size of the world aligned box around the rotated one
+    fn bounding_half_extents(&self) -> Vector2<f32> {
+        let (sin, cos) = self.rotation.sin_cos();
+        Vector2::new(
+            cos.abs() * self.half_extents.x + sin.abs() * self.half_extents.y,
+            sin.abs() * self.half_extents.x + cos.abs() * self.half_extents.y,
+        )
+    }
+
+    /// Whether the world aligned boxes around the two come within margin of each other.
+    /// Resolved overlaps leave resting bodies exactly touching, so this is how contacts are found without a collision.
+    pub fn is_near(&self, other: &OrientedBox, margin: f32) -> bool {
+        let reach = self.bounding_half_extents() + other.bounding_half_extents();
+        let distance = self.center - other.center;
+        distance.x.abs() <= reach.x + margin && distance.y.abs() <= reach.y + margin
+    }
+}
+
+fn project_corners(corners: &[Vector2<f32>; 4], axis: Vector2<f32>) -> (f32, f32) {
+    let mut min = f32::MAX;
+    let mut max = f32::MIN;
+    for corner in corners {
+        let projection = corner.dot(&axis);
+        min = min.min(projection);
+        max = max.max(projection);
+    }
+    (min, max)
+}
+
+/// Separating axis test for two oriented boxes.
+/// Returns the axis of least penetration (pointing from object 2 towards object 1) and the penetration depth, or None if they don't overlap.
+/// Boxes that only touch along an edge don't overlap.
+pub fn obb_penetration(box_1: &OrientedBox, box_2: &OrientedBox) -> Option<(Vector2<f32>, f32)> {
+    let corners_1 = box_1.corners();
+    let corners_2 = box_2.corners();
+    let (axis_1_x, axis_1_y) = box_1.axes();
+    let (axis_2_x, axis_2_y) = box_2.axes();
+
+    let mut best_axis = Vector2::new(0.0, 0.0);
+    let mut best_depth = f32::MAX;
+
+    for axis in [axis_1_x, axis_1_y, axis_2_x, axis_2_y] {
+        let (min_1, max_1) = project_corners(&corners_1, axis);
+        let (min_2, max_2) = project_corners(&corners_2, axis);
+
+        let depth = max_1.min(max_2) - min_1.max(min_2);
+        if depth <= 0.0 {
+            return None; // Found a separating axis
+        }
+
+        if depth < best_depth {
+            best_depth = depth;
+            best_axis = axis;
+        }
+    }
+
+    // Make sure the axis points from object 2 towards object 1
+    let center_diff = box_1.center - box_2.center;
+    if center_diff.dot(&best_axis) < 0.0 {
+        best_axis = -best_axis;
+    }
+
+    Some((best_axis, best_depth))
+}
+
+// Approximate contact point of two overlapping OBBs, the midpoint between the deepest point of each box along the collision normal.
+// A box lying flat against the normal is deepest along a whole face, so the middle of that face is used.
+fn obb_contact_point(box_1: &OrientedBox, box_2: &OrientedBox, normal: Vector2<f32>) -> Vector2<f32> {
+    let deepest_1 = deepest_point(&box_1.corners(), -normal);
+    let deepest_2 = deepest_point(&box_2.corners(), normal);
+
+    (deepest_1 + deepest_2) / 2.0
+}
+
+// The average of the corners furthest along the direction, one corner or the middle of a face
+fn deepest_point(corners: &[Vector2<f32>; 4], direction: Vector2<f32>) -> Vector2<f32> {
+    let deepest = corners.iter().map(|corner| corner.dot(&direction)).fold(f32::MIN, f32::max);
+    let (sum, count) = corners.iter()
+        .filter(|corner| deepest - corner.dot(&direction) <= FACE_TOLERANCE)
+        .fold((Vector2::new(0.0, 0.0), 0.0), |(sum, count), corner| (sum + corner, count + 1.0));
+    sum / count
+}
 
 // Check for collision with another object
 // To have a collision, SELF and OTHER must SHARE the collision type. For example, Circle Collision objects cannot collide with AABB Collision Objects unless they both have AABB and Circle.
@@ -148,17 +273,27 @@ fn check_collision(object_1_read: &Generic2DGraphicsObject, object_2_read: &Gene
     match mode {
         CollisionMode::AABB => is_colliding_aabb(object_1_read, object_2_read),
         CollisionMode::Circle => is_colliding_circle(object_1_read, object_2_read),
-        CollisionMode::OBB => is_colliding_obb(),
+        CollisionMode::OBB => is_colliding_obb(object_1_read, object_2_read),
     }
 }
 
+/// Pushes entity 1 out of entity 2. OBB pairs move along the separating axis test's minimum translation,
+/// anything else along whichever world axis overlaps least.
 pub fn resolve_overlap(entity_1: &mut GenericEntity, entity_2: &mut GenericEntity, master_graphics_list: &MasterGraphicsList) {
     let object_1 = master_graphics_list.get_object(entity_1.get_name());
     let object_2 = master_graphics_list.get_object(entity_2.get_name());
 
     if let (Some(object_1), Some(object_2)) = (object_1, object_2) {
         let mut object_1 = object_1.write().unwrap();
-        let mut object_2 = object_2.write().unwrap();
+        let object_2 = object_2.read().unwrap();
+
+        if entity_1.get_collision_modes().contains(&CollisionMode::OBB) && entity_2.get_collision_modes().contains(&CollisionMode::OBB) {
+            if let Some((axis, depth)) = obb_penetration(&OrientedBox::from_object(&object_1), &OrientedBox::from_object(&object_2)) {
+                let pos_1 = object_1.get_position();
+                object_1.set_position(Vector3::new(pos_1.x + axis.x * depth, pos_1.y + axis.y * depth, pos_1.z));
+            }
+            return;
+        }
 
         let pos_1 = object_1.get_position();
         let pos_2 = object_2.get_position();
@@ -233,3 +368,217 @@ pub fn transfer_velocity_on_collision(entity_1: &mut GenericEntity, entity_2: &m
     }
 }
 
+// 2D cross product of two vectors, the z component of their 3D cross product
+fn cross(a: Vector2<f32>, b: Vector2<f32>) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+/// Applies one impulse at the contact point of two OBBs, changing both their velocities and their spin,
+/// so off-center hits make entities spin while momentum is kept. Returns false if the pair isn't OBB against OBB
+/// or the boxes don't overlap, in which case the velocities should be transferred with transfer_velocity_on_collision.
+/// This must run before the overlap is resolved, since that changes the contact.
+pub fn apply_obb_contact_impulse(entity_1: &mut GenericEntity, entity_2: &mut GenericEntity, master_graphics_list: &MasterGraphicsList) -> bool {
+    if !entity_1.get_collision_modes().contains(&CollisionMode::OBB) || !entity_2.get_collision_modes().contains(&CollisionMode::OBB) {
+        return false;
+    }
+
+    let (Some(object_1), Some(object_2)) = (master_graphics_list.get_object(entity_1.get_name()), master_graphics_list.get_object(entity_2.get_name())) else {
+        println!("One or both objects not found to apply the contact impulse");
+        return false;
+    };
+    let box_1 = OrientedBox::from_object(&object_1.read().unwrap());
+    let box_2 = OrientedBox::from_object(&object_2.read().unwrap());
+
+    apply_contact_impulse(entity_1, entity_2, &box_1, &box_2)
+}
+
+fn apply_contact_impulse(entity_1: &mut GenericEntity, entity_2: &mut GenericEntity, box_1: &OrientedBox, box_2: &OrientedBox) -> bool {
+    let Some((normal, _)) = obb_penetration(box_1, box_2) else { return false; };
+    let contact = obb_contact_point(box_1, box_2, normal);
+
+    let r_1 = contact - box_1.center;
+    let r_2 = contact - box_2.center;
+
+    let inverse_mass = |entity: &GenericEntity| if entity.is_static() || entity.get_weight() <= 0.0 { 0.0 } else { 1.0 / entity.get_weight() };
+    let inverse_inertia = |entity: &GenericEntity| if entity.is_static() || entity.get_moment_of_inertia() <= 0.0 { 0.0 } else { 1.0 / entity.get_moment_of_inertia() };
+
+    let inverse_mass_1 = inverse_mass(entity_1);
+    let inverse_mass_2 = inverse_mass(entity_2);
+    let inverse_inertia_1 = inverse_inertia(entity_1);
+    let inverse_inertia_2 = inverse_inertia(entity_2);
+
+    // Velocity of each body at the contact point, linear plus the tangential velocity from spinning
+    let angular_1 = entity_1.get_angular_velocity();
+    let angular_2 = entity_2.get_angular_velocity();
+    let contact_velocity_1 = entity_1.get_velocity() + Vector2::new(-angular_1 * r_1.y, angular_1 * r_1.x);
+    let contact_velocity_2 = entity_2.get_velocity() + Vector2::new(-angular_2 * r_2.y, angular_2 * r_2.x);
+
+    // The normal points from entity 2 to entity 1, so a negative value means they are moving into each other
+    let normal_velocity = (contact_velocity_1 - contact_velocity_2).dot(&normal);
+    if normal_velocity >= 0.0 {
+        return true;
+    }
+
+    let r_1_cross_n = cross(r_1, normal);
+    let r_2_cross_n = cross(r_2, normal);
+    let denominator = inverse_mass_1 + inverse_mass_2 + r_1_cross_n * r_1_cross_n * inverse_inertia_1 + r_2_cross_n * r_2_cross_n * inverse_inertia_2;
+    if denominator <= 0.0 {
+        return true;
+    }
+
+    let elasticity = (entity_1.get_elasticity() + entity_2.get_elasticity()) / 2.0;
+    let impulse = normal * (-(1.0 + elasticity) * normal_velocity / denominator);
+
+    // Equal and opposite, so whatever entity 1 gains entity 2 loses
+    entity_1.set_velocity(entity_1.get_velocity() + impulse * inverse_mass_1);
+    entity_2.set_velocity(entity_2.get_velocity() - impulse * inverse_mass_2);
+    entity_1.apply_angular_impulse(cross(r_1, impulse));
+    entity_2.apply_angular_impulse(-cross(r_2, impulse));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
+    use std::collections::HashSet;
+
+    use crate::rusted_engine::entities::generic_entity::{rectangle_moment_of_inertia, EntityConfig};
+
+    use super::*;
+
+    fn unit_box(x: f32, y: f32, rotation: f32) -> OrientedBox {
+        OrientedBox { center: Vector2::new(x, y), half_extents: Vector2::new(0.5, 0.5), rotation }
+    }
+
+    fn unit_entity(name: &str, velocity: Vector2<f32>, elasticity: f32) -> GenericEntity {
+        GenericEntity::new(EntityConfig {
+            name: name.to_owned(),
+            weight: 1.0,
+            velocity,
+            angular_velocity: 0.0,
+            moment_of_inertia: rectangle_moment_of_inertia(1.0, 1.0, 1.0),
+            affected_by_gravity: false,
+            gravity_scale: 1.0,
+            linear_drag: 0.0,
+            is_static: false,
+            elasticity,
+            friction: 0.0,
+            active_collision: true,
+            collision_priority: 0,
+            collision_modes: HashSet::from([CollisionMode::OBB]),
+            triggers: Vec::new(),
+            allow_sleep: false,
+            script: None,
+        })
+    }
+
+    // Angular momentum of an entity about the origin, its spin plus its movement
+    fn angular_momentum(entity: &GenericEntity, center: Vector2<f32>) -> f32 {
+        entity.get_moment_of_inertia() * entity.get_angular_velocity() + entity.get_weight() * cross(center, entity.get_velocity())
+    }
+
+    #[test]
+    fn rotated_boxes_overlap_along_the_shallowest_axis() {
+        // A diamond whose right corner pokes 0.2 into a square to its right
+        let diamond = unit_box(0.0, 0.0, FRAC_PI_4);
+        let square = unit_box(0.5_f32.sqrt() + 0.3, 0.0, 0.0);
+
+        let (axis, depth) = obb_penetration(&diamond, &square).expect("the corner overlaps the square");
+
+        // The square's face normal is the shallowest, pointing back towards the diamond
+        assert!((axis - Vector2::new(-1.0, 0.0)).norm() < 1e-5, "axis was {:?}", axis);
+        assert!((depth - 0.2).abs() < 1e-5, "depth was {}", depth);
+    }
+
+    #[test]
+    fn a_rotated_box_is_separated_on_its_face_normal_when_the_bounding_boxes_overlap() {
+        // The squares' bounding boxes overlap, but the diamond's face normal separates them
+        let diamond = unit_box(0.0, 0.0, FRAC_PI_4);
+        let square = unit_box(0.95, 0.95, 0.0);
+
+        assert!(obb_penetration(&diamond, &square).is_none());
+    }
+
+    #[test]
+    fn boxes_separated_on_a_face_normal_do_not_overlap() {
+        let left = unit_box(0.0, 0.0, 0.0);
+        let right = unit_box(1.5, 0.0, 0.0);
+
+        assert!(obb_penetration(&left, &right).is_none());
+    }
+
+    #[test]
+    fn touching_boxes_do_not_overlap() {
+        let left = unit_box(0.0, 0.0, 0.0);
+        let right = unit_box(1.0, 0.0, 0.0);
+
+        assert!(obb_penetration(&left, &right).is_none());
+    }
+
+    #[test]
+    fn the_penetration_axis_points_from_the_second_box_to_the_first() {
+        let below = unit_box(0.0, 0.0, 0.0);
+        let above = unit_box(0.0, 0.9, 0.0);
+
+        let (axis, depth) = obb_penetration(&above, &below).unwrap();
+        assert!((axis - Vector2::new(0.0, 1.0)).norm() < 1e-5);
+        assert!((depth - 0.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn an_off_center_hit_keeps_linear_and_angular_momentum() {
+        // A box turned 45 degrees comes down corner first onto the right side of a box at rest
+        let box_1 = unit_box(0.3, 1.15, FRAC_PI_4);
+        let box_2 = unit_box(0.0, 0.0, 0.0);
+        let mut entity_1 = unit_entity("falling", Vector2::new(0.0, -2.0), 0.5);
+        let mut entity_2 = unit_entity("resting", Vector2::new(0.0, 0.0), 0.5);
+
+        let momentum_before = entity_1.get_velocity() + entity_2.get_velocity();
+        let angular_momentum_before = angular_momentum(&entity_1, box_1.center) + angular_momentum(&entity_2, box_2.center);
+
+        assert!(apply_contact_impulse(&mut entity_1, &mut entity_2, &box_1, &box_2));
+
+        let momentum_after = entity_1.get_velocity() + entity_2.get_velocity();
+        let angular_momentum_after = angular_momentum(&entity_1, box_1.center) + angular_momentum(&entity_2, box_2.center);
+        assert!((momentum_after - momentum_before).norm() < 1e-4);
+        assert!((angular_momentum_after - angular_momentum_before).abs() < 1e-4);
+        // Hit off its center, the resting box both moves and spins
+        assert!(entity_2.get_velocity().y < 0.0);
+        assert!(entity_2.get_angular_velocity() != 0.0);
+    }
+
+    #[test]
+    fn a_head_on_elastic_hit_swaps_the_velocities_of_equal_weights() {
+        let box_1 = unit_box(0.95, 0.0, 0.0);
+        let box_2 = unit_box(0.0, 0.0, 0.0);
+        let mut entity_1 = unit_entity("moving", Vector2::new(-1.0, 0.0), 1.0);
+        let mut entity_2 = unit_entity("resting", Vector2::new(0.0, 0.0), 1.0);
+
+        assert!(apply_contact_impulse(&mut entity_1, &mut entity_2, &box_1, &box_2));
+
+        assert!(entity_1.get_velocity().norm() < 1e-4);
+        assert!((entity_2.get_velocity() - Vector2::new(-1.0, 0.0)).norm() < 1e-4);
+    }
+
+    #[test]
+    fn boxes_moving_apart_are_left_alone() {
+        let box_1 = unit_box(0.95, 0.0, 0.0);
+        let box_2 = unit_box(0.0, 0.0, 0.0);
+        let mut entity_1 = unit_entity("leaving", Vector2::new(1.0, 0.0), 1.0);
+        let mut entity_2 = unit_entity("resting", Vector2::new(0.0, 0.0), 1.0);
+
+        assert!(apply_contact_impulse(&mut entity_1, &mut entity_2, &box_1, &box_2));
+
+        assert_eq!(entity_1.get_velocity(), Vector2::new(1.0, 0.0));
+        assert_eq!(entity_2.get_velocity(), Vector2::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn boxes_that_do_not_overlap_fall_back_to_the_weight_based_transfer() {
+        let mut entity_1 = unit_entity("far", Vector2::new(-1.0, 0.0), 1.0);
+        let mut entity_2 = unit_entity("resting", Vector2::new(0.0, 0.0), 1.0);
+
+        assert!(!apply_contact_impulse(&mut entity_1, &mut entity_2, &unit_box(3.0, 0.0, 0.0), &unit_box(0.0, 0.0, 0.0)));
+    }
+}
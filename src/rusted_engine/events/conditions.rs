@@ -0,0 +1,343 @@
+use crate::rusted_engine::{entities::util::master_entity_list::MasterEntityList, game_state::{Comparison, GameState}, input::{action_map::ActionMap, gamepad_states::GamepadStates, key_states::KeyStates}, quests::quest::QuestStatus, util::{char_to_glfw_key::char_to_glfw_key, gamepad_names::{axis_name_to_glfw_axis, button_name_to_glfw_button}}};
+
+use super::triggers::{ActionCondition, EntityCondition, EntityState, GamepadAxisCondition, GamepadButtonCondition, KeyCondition, QuestCondition, QuestState, SpeedCondition, TimerCondition, TriggerConditions, VariableCondition};
+
+/// Everything a condition tree can look at while it is evaluated.
+/// The trigger decides how the leaves that depend on it are read.
+pub struct ConditionContext<'a> {
+    pub key_states: &'a KeyStates,
+    pub gamepad_states: &'a GamepadStates,
+    pub action_map: &'a ActionMap,
+    pub game_state: &'a GameState,
+    pub master_entity_list: &'a MasterEntityList,
+    pub collided_with: Option<&'a str>, // The other entity of a collision trigger, None for anything else
+    pub driver_fired: bool, // Whether the condition that drives the trigger (its timer or speed threshold) went off this frame
+    pub area: Option<&'a str>, // The area an EnteredArea or LeftArea trigger is firing for
+    pub keys_inverted: bool, // Whether key conditions pass when their keys aren't held, for KeyNotPressed triggers
+}
+
+/// Evaluates a condition tree. All with no children passes, Any with no children fails.
+pub fn evaluate_conditions(conditions: &TriggerConditions, context: &ConditionContext) -> bool {
+    match conditions {
+        TriggerConditions::All(children) => children.iter().all(|child| evaluate_conditions(child, context)),
+        TriggerConditions::Any(children) => children.iter().any(|child| evaluate_conditions(child, context)),
+        TriggerConditions::Not(child) => !evaluate_conditions(child, context),
+        TriggerConditions::CollisionConditions(cond) => context.collided_with == Some(cond.collided_with.as_str()),
+        TriggerConditions::KeyConditions(cond) => check_key_condition(cond, context.key_states) != context.keys_inverted,
+        TriggerConditions::ActionConditions(cond) => check_action_condition(cond, context.action_map, context.key_states, context.gamepad_states),
+        TriggerConditions::GamepadButtonConditions(cond) => check_gamepad_button_condition(cond, context.gamepad_states),
+        TriggerConditions::GamepadAxisConditions(cond) => check_gamepad_axis_condition(cond, context.gamepad_states),
+        TriggerConditions::TimerConditions(_) => context.driver_fired,
+        TriggerConditions::SpeedConditions(_) => context.driver_fired,
+        TriggerConditions::AreaConditions(cond) => context.area == Some(cond.area_name.as_str()),
+        TriggerConditions::VariableConditions(cond) => check_variable_condition(cond, context.game_state),
+        TriggerConditions::EntityConditions(cond) => check_entity_condition(cond, context.master_entity_list),
+        TriggerConditions::QuestConditions(cond) => check_quest_condition(cond, context.game_state),
+    }
+}
+
+/// Finds the timer that drives a Timer trigger, the first one in the tree.
+/// A negated timer can't drive anything, the trigger only runs on the frames its timer goes off.
+pub fn find_timer_condition(conditions: &TriggerConditions) -> Option<&TimerCondition> {
+    match conditions {
+        TriggerConditions::TimerConditions(cond) => Some(cond),
+        TriggerConditions::All(children) | TriggerConditions::Any(children) => children.iter().find_map(find_timer_condition),
+        _ => None,
+    }
+}
+
+/// Finds the threshold of a SpeedAbove or SpeedBelow trigger, the first one in the tree outside a Not, same as timers
+pub fn find_speed_condition(conditions: &TriggerConditions) -> Option<&SpeedCondition> {
+    match conditions {
+        TriggerConditions::SpeedConditions(cond) => Some(cond),
+        TriggerConditions::All(children) | TriggerConditions::Any(children) => children.iter().find_map(find_speed_condition),
+        _ => None,
+    }
+}
+
+/// True when any of the keys is held down, or all of them if require_all is set.
+/// A character no key types is never held, so it can't make require_all pass on its own.
+pub fn check_key_condition(trigger_condition: &KeyCondition, key_states: &KeyStates) -> bool {
+    let is_held = |key: &char| char_to_glfw_key(*key).is_some_and(|key| key_states.is_key_pressed_raw(key));
+
+    if trigger_condition.require_all {
+        trigger_condition.keys.iter().all(is_held)
+    } else {
+        trigger_condition.keys.iter().any(is_held)
+    }
+}
+
+/// True when any of the actions is held down, or all of them if require_all is set
+pub fn check_action_condition(trigger_condition: &ActionCondition, action_map: &ActionMap, key_states: &KeyStates, gamepad_states: &GamepadStates) -> bool {
+    let mut actions = trigger_condition.actions.iter();
+
+    if trigger_condition.require_all {
+        actions.all(|action_name| action_map.is_action_held(action_name, key_states, gamepad_states))
+    } else {
+        actions.any(|action_name| action_map.is_action_held(action_name, key_states, gamepad_states))
+    }
+}
+
+/// True when any of the buttons is held down, or all of them if require_all is set.
+/// A button name that doesn't exist is never held, same as keys.
+pub fn check_gamepad_button_condition(trigger_condition: &GamepadButtonCondition, gamepad_states: &GamepadStates) -> bool {
+    let is_held = |button: &String| button_name_to_glfw_button(button).is_some_and(|button| gamepad_states.is_button_pressed_raw(button));
+
+    if trigger_condition.require_all {
+        trigger_condition.buttons.iter().all(is_held)
+    } else {
+        trigger_condition.buttons.iter().any(is_held)
+    }
+}
+
+pub fn check_gamepad_axis_condition(trigger_condition: &GamepadAxisCondition, gamepad_states: &GamepadStates) -> bool {
+    let axis = match axis_name_to_glfw_axis(&trigger_condition.axis) {
+        Some(axis) => axis,
+        None => return false,
+    };
+    let value = gamepad_states.get_axis(axis);
+    match trigger_condition.comparison {
+        Comparison::Equal => value == trigger_condition.value,
+        Comparison::NotEqual => value != trigger_condition.value,
+        Comparison::Greater => value > trigger_condition.value,
+        Comparison::GreaterOrEqual => value >= trigger_condition.value,
+        Comparison::Less => value < trigger_condition.value,
+        Comparison::LessOrEqual => value <= trigger_condition.value,
+    }
+}
+
+pub fn check_variable_condition(trigger_condition: &VariableCondition, game_state: &GameState) -> bool {
+    match game_state.get_variable(&trigger_condition.variable_name) {
+        Some(variable) => variable.compare(&trigger_condition.comparison, &trigger_condition.value),
+        None => false,
+    }
+}
+
+pub fn check_entity_condition(trigger_condition: &EntityCondition, master_entity_list: &MasterEntityList) -> bool {
+    let entity = match master_entity_list.get_entity(&trigger_condition.entity_name) {
+        Some(entity) => entity,
+        None => return matches!(trigger_condition.state, EntityState::Missing),
+    };
+
+    // Someone else holding the entity counts as the condition failing rather than waiting on it
+    let entity = match entity.try_read() {
+        Ok(entity) => entity,
+        Err(_) => {
+            println!("Entity {} is locked, its condition can't be checked this frame.", trigger_condition.entity_name);
+            return false;
+        }
+    };
+
+    match trigger_condition.state {
+        EntityState::Exists => true,
+        EntityState::Missing => false,
+        EntityState::Sleeping => entity.is_sleeping(),
+        EntityState::Awake => !entity.is_sleeping(),
+        EntityState::Moving => entity.is_moving(),
+        EntityState::Stationary => !entity.is_moving(),
+        EntityState::AffectedByGravity => entity.is_affected_by_gravity(),
+        EntityState::Weightless => !entity.is_affected_by_gravity(),
+    }
+}
+
+pub fn check_quest_condition(trigger_condition: &QuestCondition, game_state: &GameState) -> bool {
+    let status = game_state.get_quest_progress(&trigger_condition.quest_name).map(|progress| &progress.status);
+    match trigger_condition.state {
+        QuestState::NotStarted => status.is_none(),
+        QuestState::Active => status == Some(&QuestStatus::Active),
+        QuestState::Completed => status == Some(&QuestStatus::Completed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glfw::{Action, Key, Modifiers, WindowEvent};
+
+    use crate::rusted_engine::{events::triggers::{AreaCondition, CollisionCondition}, game_state::GameVariable};
+
+    use super::*;
+
+    struct Fixture {
+        key_states: KeyStates,
+        gamepad_states: GamepadStates,
+        action_map: ActionMap,
+        game_state: GameState,
+        master_entity_list: MasterEntityList,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            Self {
+                key_states: KeyStates::new(),
+                gamepad_states: GamepadStates::new(),
+                action_map: ActionMap::new(),
+                game_state: GameState::new(),
+                master_entity_list: MasterEntityList::new(),
+            }
+        }
+
+        fn press(&mut self, key: Key) {
+            self.key_states.handle_key_event(WindowEvent::Key(key, 0, Action::Press, Modifiers::empty()));
+        }
+
+        fn evaluate(&self, conditions: &TriggerConditions, driver_fired: bool) -> bool {
+            self.evaluate_with_keys(conditions, driver_fired, false)
+        }
+
+        fn evaluate_with_keys(&self, conditions: &TriggerConditions, driver_fired: bool, keys_inverted: bool) -> bool {
+            let context = ConditionContext {
+                key_states: &self.key_states,
+                gamepad_states: &self.gamepad_states,
+                action_map: &self.action_map,
+                game_state: &self.game_state,
+                master_entity_list: &self.master_entity_list,
+                collided_with: Some("coin"),
+                driver_fired,
+                area: Some("pond"),
+                keys_inverted,
+            };
+            evaluate_conditions(conditions, &context)
+        }
+    }
+
+    fn variable(variable_name: &str, comparison: Comparison, value: i64) -> TriggerConditions {
+        TriggerConditions::VariableConditions(VariableCondition { variable_name: variable_name.to_owned(), comparison, value: GameVariable::Int(value) })
+    }
+
+    fn keys(keys: &[char], require_all: bool) -> TriggerConditions {
+        TriggerConditions::KeyConditions(KeyCondition { keys: keys.to_vec(), require_all })
+    }
+
+    fn timer() -> TriggerConditions {
+        TriggerConditions::TimerConditions(TimerCondition { time_in_seconds: 1.0, repeating: false, interval_in_seconds: None })
+    }
+
+    #[test]
+    fn empty_all_passes_and_empty_any_fails() {
+        let fixture = Fixture::new();
+
+        assert!(fixture.evaluate(&TriggerConditions::All(Vec::new()), false));
+        assert!(!fixture.evaluate(&TriggerConditions::Any(Vec::new()), false));
+        assert!(!fixture.evaluate(&TriggerConditions::Not(Box::new(TriggerConditions::All(Vec::new()))), false));
+    }
+
+    #[test]
+    fn variables_combine_through_all_any_and_not() {
+        let mut fixture = Fixture::new();
+        fixture.game_state.set_variable("coins".to_owned(), GameVariable::Int(5));
+
+        let enough = variable("coins", Comparison::GreaterOrEqual, 5);
+        let too_many = variable("coins", Comparison::Greater, 10);
+        let missing = variable("gems", Comparison::Equal, 0);
+
+        assert!(fixture.evaluate(&TriggerConditions::All(vec![enough.clone(), TriggerConditions::Not(Box::new(too_many.clone()))]), false));
+        assert!(!fixture.evaluate(&TriggerConditions::All(vec![enough.clone(), too_many.clone()]), false));
+        assert!(fixture.evaluate(&TriggerConditions::Any(vec![too_many, enough]), false));
+        // A missing variable never passes, whatever it is compared against
+        assert!(!fixture.evaluate(&missing, false));
+        assert!(fixture.evaluate(&TriggerConditions::Not(Box::new(missing)), false));
+    }
+
+    #[test]
+    fn key_conditions_need_any_key_or_all_of_them() {
+        let mut fixture = Fixture::new();
+        fixture.press(Key::A);
+
+        assert!(fixture.evaluate(&keys(&['a', 'd'], false), false));
+        assert!(!fixture.evaluate(&keys(&['a', 'd'], true), false));
+        fixture.press(Key::D);
+        assert!(fixture.evaluate(&keys(&['a', 'd'], true), false));
+        assert!(!fixture.evaluate(&TriggerConditions::Not(Box::new(keys(&['a'], false))), false));
+    }
+
+    #[test]
+    fn inverted_keys_leave_the_other_conditions_alone() {
+        let mut fixture = Fixture::new();
+        let has_key = variable("has_key", Comparison::Equal, 1);
+        let w_not_pressed_with_key = TriggerConditions::All(vec![keys(&['w'], false), has_key]);
+
+        // Without the key nothing fires, whether w is held or not
+        assert!(!fixture.evaluate_with_keys(&w_not_pressed_with_key, false, true));
+        fixture.game_state.set_variable("has_key".to_owned(), GameVariable::Int(1));
+        assert!(fixture.evaluate_with_keys(&w_not_pressed_with_key, false, true));
+        fixture.press(Key::W);
+        assert!(!fixture.evaluate_with_keys(&w_not_pressed_with_key, false, true));
+    }
+
+    #[test]
+    fn inverted_keys_compose_through_not_and_any() {
+        let mut fixture = Fixture::new();
+        fixture.press(Key::A);
+
+        // Not pressed a, or not pressed d
+        assert!(fixture.evaluate_with_keys(&TriggerConditions::Any(vec![keys(&['a'], false), keys(&['d'], false)]), false, true));
+        // Neither a nor d pressed
+        assert!(!fixture.evaluate_with_keys(&keys(&['a', 'd'], false), false, true));
+        // Not all of a and d pressed
+        assert!(fixture.evaluate_with_keys(&keys(&['a', 'd'], true), false, true));
+        // Not inside the tree still flips the leaf back
+        assert!(fixture.evaluate_with_keys(&TriggerConditions::Not(Box::new(keys(&['a'], false))), false, true));
+    }
+
+    #[test]
+    fn characters_no_key_types_are_never_held() {
+        let mut fixture = Fixture::new();
+        fixture.press(Key::A);
+
+        // 'é' isn't on the keyboard
+        assert!(!fixture.evaluate(&keys(&['é'], true), false));
+        assert!(!fixture.evaluate(&keys(&['a', 'é'], true), false));
+        assert!(fixture.evaluate(&keys(&['a', 'é'], false), false));
+    }
+
+    #[test]
+    fn unknown_gamepad_buttons_are_never_held() {
+        let fixture = Fixture::new();
+        let buttons = |buttons: &[&str], require_all| TriggerConditions::GamepadButtonConditions(GamepadButtonCondition {
+            buttons: buttons.iter().map(|button| button.to_string()).collect(),
+            require_all,
+        });
+
+        assert!(!fixture.evaluate(&buttons(&["Z"], true), false));
+        assert!(!fixture.evaluate(&buttons(&["Z", "Turbo"], true), false));
+        assert!(!fixture.evaluate(&buttons(&["Z"], false), false));
+        assert!(fixture.evaluate(&TriggerConditions::Not(Box::new(buttons(&["Z"], true))), false));
+    }
+
+    #[test]
+    fn missing_entities_only_pass_the_missing_state() {
+        let fixture = Fixture::new();
+        let entity = |state| TriggerConditions::EntityConditions(EntityCondition { entity_name: "ghost".to_owned(), state });
+
+        assert!(fixture.evaluate(&entity(EntityState::Missing), false));
+        assert!(!fixture.evaluate(&entity(EntityState::Exists), false));
+        assert!(!fixture.evaluate(&entity(EntityState::Awake), false));
+    }
+
+    #[test]
+    fn collision_and_area_conditions_read_the_trigger_context() {
+        let fixture = Fixture::new();
+
+        assert!(fixture.evaluate(&TriggerConditions::CollisionConditions(CollisionCondition { collided_with: "coin".to_owned() }), false));
+        assert!(!fixture.evaluate(&TriggerConditions::CollisionConditions(CollisionCondition { collided_with: "spike".to_owned() }), false));
+        assert!(fixture.evaluate(&TriggerConditions::AreaConditions(AreaCondition { area_name: "pond".to_owned() }), false));
+        assert!(!fixture.evaluate(&TriggerConditions::AreaConditions(AreaCondition { area_name: "lava".to_owned() }), false));
+    }
+
+    #[test]
+    fn timers_pass_only_when_they_went_off() {
+        let fixture = Fixture::new();
+
+        assert!(fixture.evaluate(&timer(), true));
+        assert!(!fixture.evaluate(&timer(), false));
+    }
+
+    #[test]
+    fn a_negated_timer_does_not_drive_the_trigger() {
+        let negated = TriggerConditions::Not(Box::new(timer()));
+        assert!(find_timer_condition(&negated).is_none());
+
+        let driven = TriggerConditions::All(vec![negated, timer()]);
+        assert!(find_timer_condition(&driven).is_some());
+    }
+}
@@ -0,0 +1,132 @@
+use std::fmt;
+
+use crate::rusted_engine::audio::audio_manager::AudioType;
+
+use super::triggers::Outcome;
+
+/// Things that happened inside the engine that game code may want to react to
+#[derive(Debug, Clone)]
+pub enum EngineEvent {
+    CollisionStarted { entity_1: String, entity_2: String },
+    CollisionEnded { entity_1: String, entity_2: String },
+    EntityCreated { entity_name: String },
+    EntityDestroyed { entity_name: String },
+    SceneUnloaded { scene_name: String },
+    SceneLoaded { scene_name: String },
+    AudioStarted { audio_name: String, audio_type: AudioType },
+    PianoSequencePlayed { sequence_name: String },
+    OutcomeApplied(Box<Outcome>), // Boxed, outcomes are far bigger than every other event
+}
+
+/// A one line description, for logging events
+impl fmt::Display for EngineEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EngineEvent::CollisionStarted { entity_1, entity_2 } => write!(f, "{} started touching {}", entity_1, entity_2),
+            EngineEvent::CollisionEnded { entity_1, entity_2 } => write!(f, "{} stopped touching {}", entity_1, entity_2),
+            EngineEvent::EntityCreated { entity_name } => write!(f, "{} was created", entity_name),
+            EngineEvent::EntityDestroyed { entity_name } => write!(f, "{} was destroyed", entity_name),
+            EngineEvent::SceneUnloaded { scene_name } => write!(f, "Left scene {}", scene_name),
+            EngineEvent::SceneLoaded { scene_name } => write!(f, "Entered scene {}", scene_name),
+            EngineEvent::AudioStarted { audio_name, audio_type } => write!(f, "Started {:?} audio {}", audio_type, audio_name),
+            EngineEvent::PianoSequencePlayed { sequence_name } => write!(f, "Played piano sequence {}", sequence_name),
+            EngineEvent::OutcomeApplied(outcome) => write!(f, "Applied {:?}", outcome),
+        }
+    }
+}
+
+type Subscriber = Box<dyn FnMut(&EngineEvent) + Send + Sync>;
+
+/// Collects engine events over a frame and hands them to every subscriber when dispatched.
+/// The engine dispatches once per frame at the end of main_loop, so subscribers see a frame's events in the order they happened,
+/// after its outcomes have been applied and everything has moved.
+pub struct EventBus {
+    pending: Vec<EngineEvent>,
+    subscribers: Vec<Subscriber>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Subscribers are called while the bus is locked, so they must not subscribe or publish themselves.
+    /// Anything they need from the engine should be captured as an Arc when subscribing. They stay subscribed for the life of the bus.
+    pub fn subscribe(&mut self, subscriber: impl FnMut(&EngineEvent) + Send + Sync + 'static) {
+        self.subscribers.push(Box::new(subscriber));
+    }
+
+    /// Queues an event for the next dispatch. Whoever has subscribed by then gets it.
+    pub fn publish(&mut self, event: EngineEvent) {
+        self.pending.push(event);
+    }
+
+    /// Delivers every pending event to every subscriber, in the order they were published
+    pub fn dispatch(&mut self) {
+        let events = std::mem::take(&mut self.pending);
+        for event in events.iter() {
+            for subscriber in self.subscribers.iter_mut() {
+                subscriber(event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    fn recording_subscriber(event_bus: &mut EventBus) -> Arc<Mutex<Vec<String>>> {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = received.clone();
+        event_bus.subscribe(move |event| received_clone.lock().unwrap().push(event.to_string()));
+        received
+    }
+
+    #[test]
+    fn subscribers_get_events_in_the_order_they_were_published() {
+        let mut event_bus = EventBus::new();
+        let first = recording_subscriber(&mut event_bus);
+        let second = recording_subscriber(&mut event_bus);
+
+        event_bus.publish(EngineEvent::SceneLoaded { scene_name: "testscene".to_owned() });
+        event_bus.publish(EngineEvent::EntityCreated { entity_name: "coin".to_owned() });
+        event_bus.publish(EngineEvent::EntityDestroyed { entity_name: "coin".to_owned() });
+
+        // Nothing is delivered before the dispatch
+        assert!(first.lock().unwrap().is_empty());
+        event_bus.dispatch();
+
+        let expected = vec!["Entered scene testscene", "coin was created", "coin was destroyed"];
+        assert_eq!(*first.lock().unwrap(), expected);
+        assert_eq!(*second.lock().unwrap(), expected);
+    }
+
+    #[test]
+    fn events_are_delivered_once() {
+        let mut event_bus = EventBus::new();
+        let received = recording_subscriber(&mut event_bus);
+
+        event_bus.publish(EngineEvent::SceneUnloaded { scene_name: "testscene".to_owned() });
+        event_bus.dispatch();
+        event_bus.dispatch();
+
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn events_published_before_anyone_subscribed_are_kept_for_the_dispatch() {
+        let mut event_bus = EventBus::new();
+
+        event_bus.publish(EngineEvent::SceneLoaded { scene_name: "testscene".to_owned() });
+        let received = recording_subscriber(&mut event_bus);
+        event_bus.dispatch();
+
+        assert_eq!(*received.lock().unwrap(), vec!["Entered scene testscene"]);
+    }
+}
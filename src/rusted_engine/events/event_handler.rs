@@ -1,11 +1,18 @@
-use std::{collections::HashSet, sync::{Arc, RwLock}};
+use std::{collections::{HashMap, HashSet}, fs, sync::{Arc, RwLock}};
 
 use nalgebra::{Vector2, Vector3};
+use rhai::Dynamic;
 use rusted_open::framework::graphics::{camera::Camera, internal_object::{animation_config::AnimationConfig, atlas_config::AtlasConfig, custom_shader::CustomShader, graphics_object::Generic2DGraphicsObject}, texture_manager::TextureManager, util::master_graphics_list::MasterGraphicsList};
 
-use crate::rusted_engine::{audio::audio_manager::{AudioManager, AudioType}, entities::{generic_entity::{CollisionMode, GenericEntity}, util::master_entity_list::MasterEntityList}, game_state::GameState, input::key_states::KeyStates, scenes::scene_manager::{ObjectData, SceneManager}, util::char_to_glfw_key::char_to_glfw_key};
+use crate::rusted_engine::{audio::audio_manager::{AudioManager, AudioType}, cutscenes::cutscene_player::CutscenePlayer, dialogue::dialogue_runner::DialogueRunner, entities::{generic_entity::{rectangle_moment_of_inertia, CollisionMode, EntityConfig, GenericEntity}, util::master_entity_list::MasterEntityList}, game_state::GameState, input::{action_map::ActionMap, gamepad_states::GamepadStates, key_states::KeyStates}, quests::quest_tracker::QuestTracker, scenes::scene_manager::{ObjectData, SceneManager}, scripting::script_engine::ScriptEngine, util::random::Random};
 
-use super::{collision::{self, resolve_overlap, transfer_velocity_on_collision, CollisionEvent}, triggers::{KeyCondition, Outcome, SceneTriggerType, Trigger, TriggerConditions, TriggerType}};
+use super::{event_bus::{EngineEvent, EventBus}, trace_log::{TraceKind, TraceLog}, conditions::{evaluate_conditions, find_speed_condition, find_timer_condition, ConditionContext}, piano_sequences::PianoSequence, placeholders::resolve_placeholders, scheduler::Scheduler, sequences::SequenceRunner, collision::{self, apply_obb_contact_impulse, resolve_overlap, OrientedBox, transfer_velocity_on_collision, CollisionEvent}, triggers::{DumpTraceLogArgs, EntityProperty, Outcome, RandomChoiceArgs, SceneTriggerType, SwapSceneArgs, TimerCondition, Trigger, TriggerConditions, TriggerType}};
+
+// How close two bodies have to be to count as resting on each other, in world units
+const CONTACT_MARGIN: f32 = 0.005;
+
+// Whether an outcome did anything, the error says why it was ignored
+type OutcomeResult = Result<(), String>;
 
 pub struct EventHandler {
     camera: Arc<RwLock<Camera>>,
@@ -17,19 +24,66 @@ pub struct EventHandler {
     game_state: Arc<RwLock<GameState>>,
     event_outcomes: Vec<Outcome>,
     key_states: Arc<RwLock<KeyStates>>,
+    event_bus: Arc<RwLock<EventBus>>,
+    trace_log: Arc<RwLock<TraceLog>>,
+    dialogue_runner: Arc<RwLock<DialogueRunner>>,
+    cutscene_player: Arc<RwLock<CutscenePlayer>>,
+    quest_tracker: Arc<RwLock<QuestTracker>>,
+    action_map: Arc<RwLock<ActionMap>>,
+    gamepad_states: Arc<RwLock<GamepadStates>>,
+    colliding_pairs: HashSet<(String, String)>, // Pairs of entity names that collided last frame, in name order
+    submerged_entities: HashSet<(String, String)>, // (entity name, fluid volume name) pairs from the last frame
+    submerged_scene_name: String,
+    scene_timers: HashMap<usize, TimerState>, // Keyed by the index of the Timer trigger in the current scene
+    variable_trigger_states: HashMap<usize, bool>, // Whether each Variable trigger's condition held last frame, keyed like the timers
+    trigger_states: HashMap<String, TriggerState>, // Keyed by "owner:id", or "owner#index" for triggers without an id
+    trigger_clock: f32, // Game time used for trigger cooldowns
+    entities_in_areas: HashSet<(String, String)>, // (entity name, area name) pairs from the last frame, only for entities with area triggers
+    speed_trigger_states: HashMap<String, bool>, // Whether each speed trigger's threshold was crossed last frame, keyed like trigger_states
+    area_watchers: HashSet<String>, // Entities whose area triggers were checked last frame
+    exiting_to: Option<String>, // The scene a swap is waiting on while the SceneExit outcomes run
+    sequence_runner: SequenceRunner,
+    scheduler: Scheduler,
+    random: Random,
+    script_engine: ScriptEngine,
+}
+
+/// The parts of the engine the event handler shares with the engine controller
+pub struct EventHandlerResources {
+    pub camera: Arc<RwLock<Camera>>,
+    pub master_entity_list: Arc<RwLock<MasterEntityList>>,
+    pub master_graphics_list: Arc<RwLock<MasterGraphicsList>>,
+    pub texture_manager: Arc<RwLock<TextureManager>>,
+    pub audio_manager: Arc<RwLock<AudioManager>>,
+    pub scene_manager: Arc<RwLock<SceneManager>>,
+    pub game_state: Arc<RwLock<GameState>>,
+    pub key_states: Arc<RwLock<KeyStates>>,
+    pub event_bus: Arc<RwLock<EventBus>>,
+    pub trace_log: Arc<RwLock<TraceLog>>,
+    pub dialogue_runner: Arc<RwLock<DialogueRunner>>,
+    pub cutscene_player: Arc<RwLock<CutscenePlayer>>,
+    pub quest_tracker: Arc<RwLock<QuestTracker>>,
+    pub action_map: Arc<RwLock<ActionMap>>,
+    pub gamepad_states: Arc<RwLock<GamepadStates>>,
+}
+
+struct TimerState {
+    elapsed: f32,
+    fired: bool,
+}
+
+/// What a trigger remembers between fires. Kept across scene swaps, so a trigger that fires once stays spent.
+struct TriggerState {
+    last_fired: Option<f32>,
+    fire_count: u32,
+    enabled: Option<bool>, // Set by EnableTrigger and DisableTrigger, overrides the trigger's own enabled flag
 }
 
 impl EventHandler {
-    pub fn new(
-        camera: Arc<RwLock<Camera>>,
-        master_entity_list: Arc<RwLock<MasterEntityList>>,
-        master_graphics_list: Arc<RwLock<MasterGraphicsList>>,
-        texture_manager: Arc<RwLock<TextureManager>>,
-        audio_manager: Arc<RwLock<AudioManager>>,
-        scene_manager: Arc<RwLock<SceneManager>>,
-        game_state: Arc<RwLock<GameState>>,
-        key_states: Arc<RwLock<KeyStates>>,
-    ) -> Self {
+    pub fn new(resources: EventHandlerResources) -> Self {
+        let EventHandlerResources { camera, master_entity_list, master_graphics_list, texture_manager, audio_manager, scene_manager, game_state, key_states, event_bus, trace_log, dialogue_runner, cutscene_player, quest_tracker, action_map, gamepad_states } = resources;
+        let script_engine = ScriptEngine::new(master_entity_list.clone(), master_graphics_list.clone(), game_state.clone(), audio_manager.clone(), camera.clone());
+
         Self {
             camera,
             master_entity_list,
@@ -39,95 +93,427 @@ impl EventHandler {
             scene_manager,
             game_state,
             key_states,
+            event_bus,
+            trace_log,
+            dialogue_runner,
+            cutscene_player,
+            quest_tracker,
+            action_map,
+            gamepad_states,
+            colliding_pairs: HashSet::new(),
             event_outcomes: Vec::new(),
+            submerged_entities: HashSet::new(),
+            submerged_scene_name: String::new(),
+            scene_timers: HashMap::new(),
+            variable_trigger_states: HashMap::new(),
+            trigger_states: HashMap::new(),
+            trigger_clock: 0.0,
+            entities_in_areas: HashSet::new(),
+            speed_trigger_states: HashMap::new(),
+            area_watchers: HashSet::new(),
+            exiting_to: None,
+            sequence_runner: SequenceRunner::new(),
+            scheduler: Scheduler::new(),
+            random: Random::new(),
+            script_engine,
         }
     }
 
     pub fn process_event_outcomes(&mut self, delta_time: f32) {
+        // Running sequences hand over whatever steps are due this frame
+        let due_outcomes = self.sequence_runner.update(delta_time);
+        self.event_outcomes.extend(due_outcomes);
+        // Followed by anything delayed that has come due
+        let due_outcomes = self.scheduler.update(delta_time);
+        self.event_outcomes.extend(due_outcomes);
+        // Then the cutscene, if one is playing
+        let cutscene_outcomes = self.cutscene_player.write().unwrap().update(delta_time);
+        self.event_outcomes.extend(cutscene_outcomes);
+        self.sync_input_lock();
+        self.hold_cutscene_tracked_entities();
+        // And whatever the scripts queued since the last frame
+        let script_outcomes = self.script_engine.take_outcomes();
+        self.event_outcomes.extend(script_outcomes);
+
         let mut index = 0;
     
         while index < self.event_outcomes.len() {
-            let event_outcome = &self.event_outcomes[index];
-            
-            match event_outcome {
-                Outcome::Sequence(sequence_args) => {
-                    match sequence_args.sequence_name.as_str() {
-                        "homebringer_sequence" => {
-                            self.homebringer_sequence();
-                        }
-                        "gorbino_sequence" => {
-                            self.gorbino_sequence();
-                        }
-                        "explosion_sequence" => {
-                            self.explosion_sequence();
-                        }
-                        "gravity_sequence" => {
-                            self.gravity_sequence();
-                        }
-                        _ => {
-                            println!("No sequence found for sequence name: {}", sequence_args.sequence_name);
-                        }
-                    }
-                }
-                Outcome::AccelerateObject(accelerate_object_args) => {
-                    super::player_movement::accelerate_object(accelerate_object_args.clone(), &self.master_entity_list.write().unwrap(), delta_time)
-                }
-                Outcome::SwapScene(swap_scene_args) => {
-                    if !swap_scene_args.scene_name.is_empty() {
-                        self.swap_scene(swap_scene_args.scene_name.clone());
-                    }
-                }
-                Outcome::CreateObject(create_object_args) => {
-                    if self.master_graphics_list.read().unwrap().get_object(&create_object_args.graphics.name).is_some() {
-                        println!("Call to create object was made when object of same ID \"{}\" already exists. Ignoring.", create_object_args.graphics.name)
-                    } else {
-                        self.create_object(create_object_args.clone());
-                    }
-                }
-                Outcome::DestroyObject(destroy_args) => {
-                    if !destroy_args.object_name.is_empty() {
-                        let chained_outcomes = self.destroy_object(destroy_args.object_name.clone());
-                        self.event_outcomes.extend(chained_outcomes);
-                    }
-                }
-                Outcome::TeleportObject(teleport_args) => {
-                    if !teleport_args.object_name.is_empty() {
-                        self.teleport_object(teleport_args.object_name.clone(), teleport_args.new_position.clone());
-                    }
-                }
-                Outcome::EnqueueAudio(audio_args) => {
-                    if !audio_args.audio_name.is_empty() {
-                        self.enqueue_audio(audio_args.audio_name.clone(), audio_args.audio_type.clone(), audio_args.volume);
-                    }
+            let applied_outcome = self.event_outcomes[index].clone();
+
+            let result = match self.outcome_ignored_reason(&applied_outcome) {
+                Some(reason) => Err(reason.to_owned()),
+                None => self.apply_outcome(index, delta_time),
+            };
+
+            match result {
+                Ok(()) => {
+                    self.trace_log.write().unwrap().record_outcome(TraceKind::OutcomeApplied, "", &applied_outcome);
+                    self.publish_event(EngineEvent::OutcomeApplied(Box::new(applied_outcome)));
                 }
-                Outcome::SetAtlasConfig(set_atlas_config_args) => {
-                    if !set_atlas_config_args.object_name.is_empty() {
-                        self.set_atlas_config(set_atlas_config_args.object_name.clone(), set_atlas_config_args.atlas_config.clone());
-                    }
+                Err(reason) => {
+                    println!("Ignored an outcome, {}", reason);
+                    self.trace(TraceKind::OutcomeIgnored, "", format!("{}: {}", reason, serde_json::to_string(&applied_outcome).unwrap_or_else(|_| format!("{:?}", applied_outcome))));
                 }
-                Outcome::SetAnimationConfig(set_animation_config_args) => {
-                    if !set_animation_config_args.object_name.is_empty() {
-                        self.set_animation_config(set_animation_config_args.object_name.clone(), set_animation_config_args.animation_config.clone());
-                    }
+            }
+
+            index += 1;
+        }
+
+        self.event_outcomes.clear();
+        // A dialogue node entered this frame is shown with the choices that hold now its outcomes are applied
+        self.dialogue_runner.write().unwrap().present_pending_line(&self.game_state.read().unwrap());
+    }
+
+    /// Applies the outcome at index in event_outcomes, any outcomes it leads to are queued after it.
+    /// The error says why the outcome was ignored.
+    fn apply_outcome(&mut self, index: usize, delta_time: f32) -> OutcomeResult {
+        let event_outcome = &self.event_outcomes[index];
+        match event_outcome {
+            Outcome::Sequence(sequence_args) => {
+                let current_scene_name = self.game_state.read().unwrap().get_current_scene_name();
+                let sequence = self.scene_manager.read().unwrap().get_sequence(&current_scene_name, &sequence_args.sequence_name);
+
+                let Some(sequence) = sequence else {
+                    return Err(format!("no sequence found for sequence name: {}", sequence_args.sequence_name));
+                };
+                self.sequence_runner.start(&sequence_args.sequence_name, sequence);
+                // Steps without a wait run this frame, right after the outcome that started them
+                let due_outcomes = self.sequence_runner.update(0.0);
+                self.event_outcomes.extend(due_outcomes);
+                Ok(())
+            }
+            Outcome::StopSequence(sequence_args) => {
+                self.sequence_runner.stop(&sequence_args.sequence_name);
+                Ok(())
+            }
+            Outcome::Delay(delay_args) => {
+                self.scheduler.schedule(delay_args.clone());
+                Ok(())
+            }
+            Outcome::CancelScheduled(cancel_scheduled_args) => {
+                self.scheduler.cancel(&cancel_scheduled_args.tag);
+                Ok(())
+            }
+            Outcome::AccelerateObject(accelerate_object_args) => {
+                super::player_movement::accelerate_object(accelerate_object_args.clone(), &self.master_entity_list.write().unwrap(), delta_time)
+            }
+            Outcome::ApplyTorque(apply_torque_args) => {
+                super::player_movement::apply_torque(apply_torque_args.clone(), &self.master_entity_list.read().unwrap())
+            }
+            Outcome::WakeObject(wake_object_args) => {
+                self.wake_object(wake_object_args.object_name.clone())
+            }
+            Outcome::SwapScene(swap_scene_args) => {
+                let scene_name = swap_scene_args.scene_name.clone();
+                let exit_outcomes = self.take_scene_exit_outcomes(&scene_name);
+                if exit_outcomes.is_empty() {
+                    self.swap_scene(scene_name);
                 }
-                Outcome::SetCameraZoom(set_camera_zoom_args) => {
-                    self.set_camera_zoom(set_camera_zoom_args.zoom);
+                else {
+                    // Run the exit outcomes while the old scene is still loaded, then come back to this swap
+                    let mut deferred = exit_outcomes;
+                    deferred.push(Outcome::SwapScene(SwapSceneArgs { scene_name }));
+                    self.event_outcomes.splice(index + 1..index + 1, deferred);
                 }
-                Outcome::SetCameraTrackingTarget(set_camera_tracking_target_args) => {
-                    self.set_camera_tracking_target(set_camera_tracking_target_args.tracking_target.clone());
+                Ok(())
+            }
+            Outcome::CreateObject(create_object_args) => {
+                self.create_object(create_object_args.clone());
+                Ok(())
+            }
+            Outcome::DestroyObject(destroy_args) => {
+                let chained_outcomes = self.destroy_object(destroy_args.object_name.clone())?;
+                self.event_outcomes.extend(chained_outcomes);
+                Ok(())
+            }
+            Outcome::TeleportObject(teleport_args) => {
+                self.teleport_object(teleport_args.object_name.clone(), teleport_args.new_position.clone(), teleport_args.relative_to.clone())
+            }
+            Outcome::EnqueueAudio(audio_args) => {
+                self.enqueue_audio(audio_args.audio_name.clone(), audio_args.audio_type.clone(), audio_args.volume);
+                Ok(())
+            }
+            Outcome::SetAtlasConfig(set_atlas_config_args) => {
+                self.set_atlas_config(set_atlas_config_args.object_name.clone(), set_atlas_config_args.atlas_config.clone())
+            }
+            Outcome::SetAnimationConfig(set_animation_config_args) => {
+                self.set_animation_config(set_animation_config_args.object_name.clone(), set_animation_config_args.animation_config.clone())
+            }
+            Outcome::SetCameraZoom(set_camera_zoom_args) => {
+                self.set_camera_zoom(set_camera_zoom_args.zoom);
+                Ok(())
+            }
+            Outcome::SetCameraTrackingTarget(set_camera_tracking_target_args) => {
+                self.set_camera_tracking_target(set_camera_tracking_target_args.tracking_target.clone());
+                Ok(())
+            }
+            Outcome::SetVariable(set_variable_args) => {
+                self.game_state.write().unwrap().set_variable(set_variable_args.variable_name.clone(), set_variable_args.value.clone());
+                Ok(())
+            }
+            Outcome::IncrementVariable(increment_variable_args) => {
+                self.game_state.write().unwrap().increment_variable(&increment_variable_args.variable_name, increment_variable_args.amount.clone());
+                Ok(())
+            }
+            Outcome::ToggleFlag(toggle_flag_args) => {
+                self.game_state.write().unwrap().toggle_flag(&toggle_flag_args.flag_name);
+                Ok(())
+            }
+            Outcome::SaveGame(save_game_args) => {
+                self.save_game(&save_game_args.save_name)
+            }
+            Outcome::LoadGame(load_game_args) => {
+                let save_name = load_game_args.save_name.clone();
+                self.load_game(&save_name)
+            }
+            Outcome::If(if_args) => {
+                let master_entity_list = self.master_entity_list.clone();
+                let branch = if self.check_conditions(&if_args.condition, &master_entity_list.read().unwrap(), None, false, None, false) {
+                    if_args.then.clone()
+                } else {
+                    if_args.otherwise.clone()
+                };
+                run_next(&mut self.event_outcomes, index, branch);
+                Ok(())
+            }
+            Outcome::RandomChoice(random_choice_args) => {
+                let random_choice_args = random_choice_args.clone();
+                let choice = self.choose_weighted(&random_choice_args)?;
+                run_next(&mut self.event_outcomes, index, choice);
+                Ok(())
+            }
+            Outcome::Repeat(repeat_args) => {
+                let due_outcomes = self.scheduler.schedule_repeats(repeat_args.clone());
+                run_next(&mut self.event_outcomes, index, due_outcomes);
+                Ok(())
+            }
+            Outcome::SetVelocity(set_velocity_args) => {
+                super::player_movement::set_velocity(set_velocity_args.clone(), &self.master_entity_list.read().unwrap())
+            }
+            Outcome::ApplyImpulse(apply_impulse_args) => {
+                super::player_movement::apply_impulse(apply_impulse_args.clone(), &self.master_entity_list.read().unwrap())
+            }
+            Outcome::SetEntityProperty(set_entity_property_args) => {
+                self.set_entity_property(set_entity_property_args.object_name.clone(), set_entity_property_args.property.clone())
+            }
+            Outcome::SetSceneGravity(set_scene_gravity_args) => {
+                self.set_scene_gravity(&set_scene_gravity_args.gravity)
+            }
+            Outcome::SetTerminalVelocity(set_terminal_velocity_args) => {
+                self.set_terminal_velocity(&set_terminal_velocity_args.terminal_velocity)
+            }
+            Outcome::StopAudio(stop_audio_args) => {
+                self.stop_audio(stop_audio_args.audio_type.clone());
+                Ok(())
+            }
+            Outcome::SetRotation(set_rotation_args) => {
+                self.set_rotation(set_rotation_args.object_name.clone(), set_rotation_args.rotation)
+            }
+            Outcome::SetScale(set_scale_args) => {
+                self.set_scale(set_scale_args.object_name.clone(), set_scale_args.scale)
+            }
+            Outcome::EnableTrigger(trigger_id_args) => {
+                let trigger_id_args = trigger_id_args.clone();
+                for owner in trigger_id_args.owners.iter() {
+                    self.set_trigger_enabled(owner, &trigger_id_args.trigger_id, true);
                 }
-                _ => {
-                    println!("Unhandled outcome: {:?}", event_outcome);
+                Ok(())
+            }
+            Outcome::DisableTrigger(trigger_id_args) => {
+                let trigger_id_args = trigger_id_args.clone();
+                for owner in trigger_id_args.owners.iter() {
+                    self.set_trigger_enabled(owner, &trigger_id_args.trigger_id, false);
                 }
+                Ok(())
+            }
+            Outcome::Script(script_args) => {
+                let args = script_args.args.iter().map(|arg| Dynamic::from(arg.clone())).collect();
+                self.script_engine.run_script(&script_args.script_name, script_args.function.as_deref(), args);
+                // Anything the script queued runs this frame, right after the outcomes already waiting
+                let script_outcomes = self.script_engine.take_outcomes();
+                self.event_outcomes.extend(script_outcomes);
+                Ok(())
+            }
+            Outcome::DumpTraceLog(dump_trace_log_args) => {
+                self.dump_trace_log(&dump_trace_log_args);
+                Ok(())
+            }
+            Outcome::RebindAction(rebind_action_args) => {
+                self.action_map.write().unwrap().start_rebind(&rebind_action_args.action_name);
+                Ok(())
+            }
+            Outcome::StartQuest(quest_args) => {
+                self.quest_tracker.read().unwrap().start_quest(&quest_args.quest_name, &mut self.game_state.write().unwrap());
+                Ok(())
+            }
+            Outcome::AdvanceObjective(advance_objective_args) => {
+                let quest_outcomes = self.quest_tracker.read().unwrap().advance_objective(&advance_objective_args.quest_name, &advance_objective_args.objective_id, advance_objective_args.amount, &mut self.game_state.write().unwrap());
+                self.event_outcomes.extend(quest_outcomes);
+                Ok(())
+            }
+            Outcome::PlayCutscene(cutscene_args) => {
+                let cutscene_outcomes = self.cutscene_player.write().unwrap().play(&cutscene_args.cutscene_name);
+                self.event_outcomes.extend(cutscene_outcomes);
+                self.sync_input_lock();
+                Ok(())
+            }
+            Outcome::PauseCutscene(cutscene_args) => {
+                self.cutscene_player.write().unwrap().pause(&cutscene_args.cutscene_name);
+                Ok(())
+            }
+            Outcome::ResumeCutscene(cutscene_args) => {
+                self.cutscene_player.write().unwrap().resume(&cutscene_args.cutscene_name);
+                Ok(())
+            }
+            Outcome::SkipCutscene(cutscene_args) => {
+                let cutscene_outcomes = self.cutscene_player.write().unwrap().skip(&cutscene_args.cutscene_name);
+                self.event_outcomes.extend(cutscene_outcomes);
+                self.sync_input_lock();
+                Ok(())
+            }
+            Outcome::StartDialogue(start_dialogue_args) => {
+                let node_outcomes = self.dialogue_runner.write().unwrap().start(&start_dialogue_args.dialogue_name);
+                self.event_outcomes.extend(node_outcomes);
+                Ok(())
             }
-    
-            index += 1;
         }
-    
-        self.event_outcomes.clear();
     }
     
 
+    /// Adds an outcome to be processed with the rest of this frame's outcomes
+    pub fn queue_outcome(&mut self, outcome: Outcome) {
+        self.event_outcomes.push(outcome);
+    }
+
+    /// The piano sequences that can be played in the current scene
+    pub fn get_current_piano_sequences(&self) -> Vec<PianoSequence> {
+        let current_scene_name = self.game_state.read().unwrap().get_current_scene_name();
+        self.scene_manager.read().unwrap().get_piano_sequences(&current_scene_name)
+    }
+
+    /// Lets the player skip the cutscene that is playing, if it can be skipped
+    pub fn process_cutscene_input(&mut self) {
+        let cutscene_outcomes = self.cutscene_player.write().unwrap().process_input(&self.key_states.read().unwrap());
+        self.event_outcomes.extend(cutscene_outcomes);
+        self.sync_input_lock();
+    }
+
+    /// Gameplay input stays locked for as long as a cutscene that asks for it is playing
+    fn sync_input_lock(&self) {
+        let input_locked = self.cutscene_player.read().unwrap().is_locking_input();
+        self.game_state.write().unwrap().set_input_locked(input_locked);
+    }
+
+    /// Entities on a cutscene's position track only move along it. Without this, gravity and collisions would build up velocity
+    /// between the teleports of the track, and the entity would shoot off when the cutscene lets go of it.
+    fn hold_cutscene_tracked_entities(&self) {
+        let tracked_object_names = self.cutscene_player.read().unwrap().get_tracked_object_names();
+        let master_entity_list = self.master_entity_list.read().unwrap();
+        for object_name in tracked_object_names {
+            if let Some(entity) = master_entity_list.get_entity(&object_name) {
+                let mut entity = entity.write().unwrap();
+                entity.set_velocity(Vector2::new(0.0, 0.0));
+                entity.set_angular_velocity(0.0);
+            }
+        }
+    }
+
+    /// Lets the player answer the dialogue being shown, if there is one. Not while a cutscene has the controls.
+    pub fn process_dialogue_input(&mut self) {
+        if self.game_state.read().unwrap().is_input_locked() {
+            return;
+        }
+        let dialogue_outcomes = self.dialogue_runner.write().unwrap().process_input(&mut self.key_states.write().unwrap(), &self.game_state.read().unwrap());
+        self.event_outcomes.extend(dialogue_outcomes);
+    }
+
+    /// Compiles the scripts entities and Script outcomes can refer to
+    pub fn load_scripts_from_directory(&mut self, dir_path: &str) {
+        if let Err(e) = self.script_engine.load_scripts_from_directory(dir_path) {
+            println!("Error loading scripts from {}: {}", dir_path, e);
+        }
+    }
+
+    /// Calls on_update(self, delta_time) on every entity whose script has one
+    pub fn run_script_updates(&mut self, delta_time: f32) {
+        // Collected first so the scripts are free to lock any entity
+        let entities: Vec<Arc<RwLock<GenericEntity>>> = self.master_entity_list.read().unwrap().get_entities().read().unwrap().values().cloned().collect();
+        let scripted_entities: Vec<(String, String)> = entities.iter()
+            .filter_map(|entity| {
+                let entity = entity.read().unwrap();
+                entity.get_script().map(|script| (entity.get_name().to_owned(), script.to_owned()))
+            })
+            .collect();
+
+        for (entity_name, script_name) in scripted_entities {
+            if self.script_engine.has_function(&script_name, "on_update") {
+                self.script_engine.run_script(&script_name, Some("on_update"), vec![Dynamic::from(entity_name), Dynamic::from_float(delta_time as f64)]);
+            }
+        }
+    }
+
+    /// Why process_event_outcomes will skip an outcome, None if it will be applied
+    fn outcome_ignored_reason(&self, outcome: &Outcome) -> Option<&'static str> {
+        let empty_name = match outcome {
+            Outcome::SwapScene(args) => args.scene_name.is_empty(),
+            Outcome::DestroyObject(args) => args.object_name.is_empty(),
+            Outcome::TeleportObject(args) => args.object_name.is_empty(),
+            Outcome::EnqueueAudio(args) => args.audio_name.is_empty(),
+            Outcome::StartDialogue(args) => args.dialogue_name.is_empty(),
+            Outcome::PlayCutscene(args) => args.cutscene_name.is_empty(),
+            Outcome::RebindAction(args) => args.action_name.is_empty(),
+            Outcome::StartQuest(args) => args.quest_name.is_empty(),
+            Outcome::AdvanceObjective(args) => args.quest_name.is_empty() || args.objective_id.is_empty(),
+            Outcome::SetAtlasConfig(args) => args.object_name.is_empty(),
+            Outcome::SetAnimationConfig(args) => args.object_name.is_empty(),
+            Outcome::SetEntityProperty(args) => args.object_name.is_empty(),
+            Outcome::SetRotation(args) => args.object_name.is_empty(),
+            Outcome::SetScale(args) => args.object_name.is_empty(),
+            Outcome::EnableTrigger(args) | Outcome::DisableTrigger(args) => args.trigger_id.is_empty() || args.owners.is_empty(),
+            _ => false,
+        };
+        if empty_name {
+            return Some("no name given");
+        }
+
+        if let Outcome::CreateObject(create_object_args) = outcome {
+            if self.master_graphics_list.read().unwrap().get_object(&create_object_args.graphics.name).is_some() {
+                return Some("an object with that name already exists");
+            }
+        }
+        None
+    }
+
+    fn trace(&self, kind: TraceKind, owner: &str, detail: String) {
+        self.trace_log.write().unwrap().record(kind, owner, detail);
+    }
+
+    /// Writes the trace log, narrowed to the args' owner and frames, to src\resources\traces\<file_name>.json
+    pub fn dump_trace_log(&self, args: &DumpTraceLogArgs) {
+        if let Err(e) = fs::create_dir_all("src\\resources\\traces") {
+            println!("Failed to create the traces directory: {}", e);
+            return;
+        }
+
+        let file_path = format!("src\\resources\\traces\\{}.json", args.file_name);
+        let mut trace_log = self.trace_log.write().unwrap();
+        match trace_log.dump_to_json(&file_path, args.owner.as_deref(), args.last_frames) {
+            Ok(()) => println!("Dumped the trace log to {}", file_path),
+            Err(e) => println!("Failed to dump the trace log to {}: {}", file_path, e),
+        }
+        if args.clear {
+            trace_log.clear();
+        }
+    }
+
+    /// Queues an event for the subscribers of the event bus, they get it when the frame's events are dispatched.
+    /// Quests hear about it straight away, so anything an objective completes runs this frame.
+    pub fn publish_event(&mut self, event: EngineEvent) {
+        let quest_outcomes = self.quest_tracker.read().unwrap().handle_event(&event, &mut self.game_state.write().unwrap());
+        self.event_outcomes.extend(quest_outcomes);
+        self.event_bus.write().unwrap().publish(event);
+    }
+
     pub fn process_collisions(&mut self) {
         let collision_events = collision::check_active_entity_collisions(self.master_entity_list.clone(), self.master_graphics_list.clone());
         let mut event_outcomes = self.handle_collision_events(collision_events);
@@ -136,137 +522,662 @@ impl EventHandler {
 
     pub fn handle_collision_events(&mut self, collision_events: Vec<CollisionEvent>) -> Vec<Outcome> {
         let mut event_outcomes: Vec<Outcome> = Vec::new();
+        let mut script_collisions: Vec<(String, String, String)> = Vec::new(); // (script name, entity name, other entity name)
+        self.publish_collision_changes(&collision_events);
 
-        let master_entity_list = self.master_entity_list.read().unwrap();
-        let master_graphics_list = self.master_graphics_list.read().unwrap();
+        let master_entity_list = self.master_entity_list.clone();
+        let master_entity_list = master_entity_list.read().unwrap();
+        let master_graphics_list = self.master_graphics_list.clone();
+        let master_graphics_list = master_graphics_list.read().unwrap();
         for collision_event in collision_events {
-            if let Some(entity_1) = master_entity_list.get_entity(&collision_event.object_name_1) {
+            if let (Some(entity_1), Some(entity_2)) = (master_entity_list.get_entity(&collision_event.object_name_1), master_entity_list.get_entity(&collision_event.object_name_2)) {
+                // Check collision triggers before we might destroy the object.
+                // Nothing is locked while they are evaluated, so conditions can look at any entity, these two included.
+                let triggers_1 = entity_1.read().unwrap().get_triggers().clone();
+                let triggers_2 = entity_2.read().unwrap().get_triggers().clone();
+                if let Some(script_name) = entity_1.read().unwrap().get_script() {
+                    script_collisions.push((script_name.to_owned(), collision_event.object_name_1.clone(), collision_event.object_name_2.clone()));
+                }
+                if let Some(script_name) = entity_2.read().unwrap().get_script() {
+                    script_collisions.push((script_name.to_owned(), collision_event.object_name_2.clone(), collision_event.object_name_1.clone()));
+                }
+                self.check_collision_triggers(&triggers_1, &collision_event.object_name_1, &collision_event.object_name_2, &master_entity_list, &mut event_outcomes);
+                self.check_collision_triggers(&triggers_2, &collision_event.object_name_2, &collision_event.object_name_1, &master_entity_list, &mut event_outcomes);
+
                 if let Ok(mut entity_1) = entity_1.write() {
-                    if let Some(entity_2) = master_entity_list.get_entity(&collision_event.object_name_2) {
-                        if let Ok(mut entity_2) = entity_2.write() {
-                            // Check collision triggers before we might destroy the object.
-                            self.check_collision_triggers(entity_1.get_triggers(), entity_2.get_name().to_owned(), &mut event_outcomes);
-                            self.check_collision_triggers(entity_2.get_triggers(), entity_1.get_name().to_owned(), &mut event_outcomes);
-    
-                            // Resolve overlap first
-                            resolve_overlap(&mut entity_1, &mut entity_2, &master_graphics_list);
-    
-                            // Transfer velocities based on the collision and weights
+                    if let Ok(mut entity_2) = entity_2.write() {
+                        // Being hit by something moving wakes a sleeping entity, and whatever it is touching will be woken in turn
+                        if entity_2.is_sleeping() && entity_1.is_moving() {
+                            entity_2.wake();
+                        }
+
+                        // The contact impulse has to be worked out while the objects still overlap
+                        let impulse_applied = apply_obb_contact_impulse(&mut entity_1, &mut entity_2, &master_graphics_list);
+
+                        // Resolve overlap first
+                        resolve_overlap(&mut entity_1, &mut entity_2, &master_graphics_list);
+
+                        // Anything the contact impulse didn't cover transfers velocities based on the weights
+                        if !impulse_applied {
                             transfer_velocity_on_collision(&mut entity_1, &mut entity_2);
                         }
                     }
                 }
             }
         }
+
+        // Scripts run once the collisions are resolved and the lists are let go, so they can lock whatever they need
+        drop(master_entity_list);
+        drop(master_graphics_list);
+        for (script_name, entity_name, other_name) in script_collisions {
+            if self.script_engine.has_function(&script_name, "on_collision") {
+                self.script_engine.run_script(&script_name, Some("on_collision"), vec![Dynamic::from(entity_name), Dynamic::from(other_name)]);
+            }
+        }
         return event_outcomes
     }
 
-    fn check_collision_triggers(&self, triggers: &Vec<Trigger>, entity_2_name: String, event_outcomes: &mut Vec<Outcome>) {
-        for trigger in triggers {
+    /// Compares this frame's collisions with the last frame's and publishes the pairs that started or stopped touching
+    fn publish_collision_changes(&mut self, collision_events: &[CollisionEvent]) {
+        let mut colliding_pairs: HashSet<(String, String)> = collision_events.iter()
+            .map(|collision_event| ordered_pair(&collision_event.object_name_1, &collision_event.object_name_2))
+            .collect();
+
+        // Two sleeping bodies aren't checked against each other, but they are still touching
+        {
+            let master_entity_list = self.master_entity_list.read().unwrap();
+            let is_sleeping = |entity_name: &str| master_entity_list.get_entity(entity_name).is_some_and(|entity| entity.read().unwrap().is_sleeping());
+            for (entity_1, entity_2) in self.colliding_pairs.iter() {
+                if is_sleeping(entity_1) && is_sleeping(entity_2) {
+                    colliding_pairs.insert((entity_1.clone(), entity_2.clone()));
+                }
+            }
+        }
+
+        let previous_pairs = std::mem::replace(&mut self.colliding_pairs, colliding_pairs.clone());
+        for (entity_1, entity_2) in colliding_pairs.difference(&previous_pairs) {
+            self.publish_event(EngineEvent::CollisionStarted { entity_1: entity_1.clone(), entity_2: entity_2.clone() });
+        }
+        for (entity_1, entity_2) in previous_pairs.difference(&colliding_pairs) {
+            self.publish_event(EngineEvent::CollisionEnded { entity_1: entity_1.clone(), entity_2: entity_2.clone() });
+        }
+    }
+
+    fn check_collision_triggers(&mut self, triggers: &[Trigger], entity_1_name: &str, entity_2_name: &str, master_entity_list: &MasterEntityList, event_outcomes: &mut Vec<Outcome>) {
+        for (trigger_index, trigger) in triggers.iter().enumerate() {
             if let TriggerType::Collision = trigger.trigger_type {
-                if let Some(TriggerConditions::CollisionConditions(cond)) = &trigger.conditions {
-                    if cond.collided_with == entity_2_name {
-                        event_outcomes.push(trigger.outcome.clone());
+                let trigger_key = trigger_key(&trigger.id, entity_1_name, trigger_index);
+                // Resting contacts check the trigger every frame, so only changes are traced
+                let repeat_key = format!("{} against {}", trigger_key, entity_2_name);
+                if let Some(reason) = self.trigger_blocked_reason(&trigger_key, trigger.enabled, trigger.cooldown_seconds, trigger.max_fires) {
+                    self.trace_log.write().unwrap().record_repeating(&repeat_key, TraceKind::TriggerBlocked, entity_1_name, format!("{} is {}", trigger_key, reason));
+                    continue;
+                }
+
+                let fires = match &trigger.conditions {
+                    Some(conditions) => self.check_conditions(conditions, master_entity_list, Some(entity_2_name), false, None, false),
+                    None => true,
+                };
+                if fires {
+                    self.record_trigger_fired(trigger_key, entity_1_name, &trigger.outcome);
+                    for outcome in trigger.outcome.iter() {
+                        event_outcomes.push(resolve_placeholders(outcome, entity_1_name, Some(entity_2_name)));
                     }
-                } else {
-                    event_outcomes.push(trigger.outcome.clone());
+                }
+                else {
+                    self.trace_log.write().unwrap().record_repeating(&repeat_key, TraceKind::ConditionsFailed, entity_1_name, repeat_key.clone());
                 }
             }
         }
     }
 
-    fn check_destruction_triggers(&self, triggers: &Vec<Trigger>, event_outcomes: &mut Vec<Outcome>) {
-        for trigger in triggers {
+    fn check_destruction_triggers(&mut self, triggers: &[Trigger], entity_name: &str, master_entity_list: &MasterEntityList, event_outcomes: &mut Vec<Outcome>) {
+        for (trigger_index, trigger) in triggers.iter().enumerate() {
             if let TriggerType::Destruction = trigger.trigger_type {
-                event_outcomes.push(trigger.outcome.clone());
+                let trigger_key = trigger_key(&trigger.id, entity_name, trigger_index);
+                if !self.is_trigger_ready_traced(&trigger_key, entity_name, trigger.enabled, trigger.cooldown_seconds, trigger.max_fires) {
+                    continue;
+                }
+
+                let fires = match &trigger.conditions {
+                    Some(conditions) => self.check_conditions(conditions, master_entity_list, None, false, None, false),
+                    None => true,
+                };
+                if fires {
+                    self.record_trigger_fired(trigger_key, entity_name, &trigger.outcome);
+                    for outcome in trigger.outcome.iter() {
+                        event_outcomes.push(resolve_placeholders(outcome, entity_name, None));
+                    }
+                }
+                else {
+                    self.trace(TraceKind::ConditionsFailed, entity_name, trigger_key);
+                }
+            }
+        }
+    }
+
+    /// Whether a trigger is allowed to fire right now: enabled, off cooldown and below its fire limit
+    fn is_trigger_ready(&self, trigger_key: &str, enabled: bool, cooldown_seconds: Option<f32>, max_fires: Option<u32>) -> bool {
+        self.trigger_blocked_reason(trigger_key, enabled, cooldown_seconds, max_fires).is_none()
+    }
+
+    /// Like is_trigger_ready, but leaves a trace of why the trigger was held back.
+    /// Only for triggers that are checked when something happens, polled ones would fill the trace every frame.
+    fn is_trigger_ready_traced(&self, trigger_key: &str, owner: &str, enabled: bool, cooldown_seconds: Option<f32>, max_fires: Option<u32>) -> bool {
+        match self.trigger_blocked_reason(trigger_key, enabled, cooldown_seconds, max_fires) {
+            Some(reason) => {
+                self.trace(TraceKind::TriggerBlocked, owner, format!("{} is {}", trigger_key, reason));
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn trigger_blocked_reason(&self, trigger_key: &str, enabled: bool, cooldown_seconds: Option<f32>, max_fires: Option<u32>) -> Option<&'static str> {
+        let trigger_state = match self.trigger_states.get(trigger_key) {
+            Some(trigger_state) => trigger_state,
+            None => return if enabled { None } else { Some("disabled") },
+        };
+
+        if !trigger_state.enabled.unwrap_or(enabled) {
+            return Some("disabled");
+        }
+        if max_fires.is_some_and(|max_fires| trigger_state.fire_count >= max_fires) {
+            return Some("out of fires");
+        }
+        match (cooldown_seconds, trigger_state.last_fired) {
+            (Some(cooldown_seconds), Some(last_fired)) if self.trigger_clock - last_fired < cooldown_seconds => Some("cooling down"),
+            _ => None,
+        }
+    }
+
+    /// Outcomes are traced as the trigger holds them, before $self and $other are filled in
+    fn record_trigger_fired(&mut self, trigger_key: String, owner: &str, outcomes: &[Outcome]) {
+        {
+            let mut trace_log = self.trace_log.write().unwrap();
+            trace_log.record(TraceKind::TriggerFired, owner, trigger_key.clone());
+            for outcome in outcomes.iter() {
+                trace_log.record_outcome(TraceKind::OutcomeQueued, owner, outcome);
             }
         }
+
+        let trigger_clock = self.trigger_clock;
+        let trigger_state = self.trigger_states.entry(trigger_key).or_insert(TriggerState { last_fired: None, fire_count: 0, enabled: None });
+        trigger_state.last_fired = Some(trigger_clock);
+        trigger_state.fire_count += 1;
+    }
+
+    /// Picks one of the choices at random, in proportion to their weights
+    fn choose_weighted(&mut self, random_choice_args: &RandomChoiceArgs) -> Result<Vec<Outcome>, String> {
+        let weights: Vec<f32> = random_choice_args.choices.iter().map(|choice| choice.weight).collect();
+        match self.random.choose_weighted(&weights) {
+            Some(index) => Ok(random_choice_args.choices[index].outcome.clone()),
+            None => Err("RandomChoice has no choices with a positive weight".to_owned()),
+        }
+    }
+
+    /// The trigger_id is the trigger's id, or "#index" for a trigger without one
+    pub fn set_trigger_enabled(&mut self, owner: &str, trigger_id: &str, enabled: bool) {
+        let trigger_key = if trigger_id.starts_with('#') {
+            format!("{}{}", owner, trigger_id)
+        } else {
+            format!("{}:{}", owner, trigger_id)
+        };
+        let trigger_state = self.trigger_states.entry(trigger_key).or_insert(TriggerState { last_fired: None, fire_count: 0, enabled: None });
+        trigger_state.enabled = Some(enabled);
+    }
+
+    /// Evaluates a trigger's condition tree against the current input, variables and entities
+    fn check_conditions(&self, conditions: &TriggerConditions, master_entity_list: &MasterEntityList, collided_with: Option<&str>, driver_fired: bool, area: Option<&str>, keys_inverted: bool) -> bool {
+        let key_states = self.key_states.read().unwrap();
+        let gamepad_states = self.gamepad_states.read().unwrap();
+        let action_map = self.action_map.read().unwrap();
+        let game_state = self.game_state.read().unwrap();
+        // While a cutscene has the controls, every key, button and action reads as not pressed
+        let (no_keys, no_buttons) = (KeyStates::new(), GamepadStates::new());
+        let (key_states, gamepad_states) = if game_state.is_input_locked() { (&no_keys, &no_buttons) } else { (&*key_states, &*gamepad_states) };
+        let context = ConditionContext {
+            key_states,
+            gamepad_states,
+            action_map: &action_map,
+            game_state: &game_state,
+            master_entity_list,
+            collided_with,
+            driver_fired,
+            area,
+            keys_inverted,
+        };
+        evaluate_conditions(conditions, &context)
     }
 
-    pub fn check_scene_triggers(&mut self) {
+    /// Queues the enter and exit outcomes of fluid volumes, given everything that is submerged this frame.
+    pub fn process_fluid_transitions(&mut self, submerged_entities: HashSet<(String, String)>) {
+        let game_state = self.game_state.read().unwrap();
+
+        // A new scene starts with a clean slate, nothing left the water just because the scene changed
+        let current_scene_name = game_state.get_current_scene_name();
+        if current_scene_name != self.submerged_scene_name {
+            self.submerged_scene_name = current_scene_name;
+            self.submerged_entities.clear();
+        }
+
+        for fluid_volume in game_state.get_fluid_volumes() {
+            for (entity_name, fluid_name) in submerged_entities.difference(&self.submerged_entities) {
+                if fluid_name == &fluid_volume.name {
+                    for outcome in fluid_volume.on_enter.iter() {
+                        self.event_outcomes.push(resolve_placeholders(outcome, entity_name, Some(fluid_name)));
+                    }
+                }
+            }
+            for (entity_name, fluid_name) in self.submerged_entities.difference(&submerged_entities) {
+                if fluid_name == &fluid_volume.name {
+                    for outcome in fluid_volume.on_exit.iter() {
+                        self.event_outcomes.push(resolve_placeholders(outcome, entity_name, Some(fluid_name)));
+                    }
+                }
+            }
+        }
+
+        self.submerged_entities = submerged_entities;
+    }
+
+    pub fn check_scene_triggers(&mut self, delta_time: f32) {
+        // Scene triggers are checked first every frame, so this is where the cooldown clock moves on
+        self.trigger_clock += delta_time;
+
         let current_scene_name = self.game_state.read().unwrap().get_current_scene_name();
+        let input_locked = self.game_state.read().unwrap().is_input_locked();
 
         // This if statement should never fail
-        if let Some(current_scene) = self.scene_manager.read().unwrap().get_scene(&current_scene_name) {
+        let current_scene = self.scene_manager.read().unwrap().get_scene(&current_scene_name);
+        if let Some(current_scene) = current_scene {
             let current_scene_triggers = current_scene.read().unwrap().get_triggers();
+            let master_entity_list = self.master_entity_list.clone();
+            let master_entity_list = master_entity_list.read().unwrap();
 
-            for scene_trigger in current_scene_triggers {
-                match scene_trigger.scene_trigger_type {
+            for (trigger_index, scene_trigger) in current_scene_triggers.into_iter().enumerate() {
+                // These only fire around scene swaps
+                if let SceneTriggerType::SceneEnter | SceneTriggerType::SceneExit = scene_trigger.scene_trigger_type {
+                    continue;
+                }
+                // Nothing the player presses reaches the scene while a cutscene has the controls
+                if input_locked && matches!(scene_trigger.scene_trigger_type, SceneTriggerType::KeyPressed | SceneTriggerType::KeyNotPressed) {
+                    continue;
+                }
+
+                let trigger_key = trigger_key(&scene_trigger.id, &current_scene_name, trigger_index);
+                if !self.is_trigger_ready(&trigger_key, scene_trigger.enabled, scene_trigger.cooldown_seconds, scene_trigger.max_fires) {
+                    continue;
+                }
+
+                let conditions = match &scene_trigger.conditions {
+                    Some(conditions) => conditions,
+                    None => {
+                        println!("A {:?} trigger was processed, but no condition could be found. Ignoring.", scene_trigger.scene_trigger_type);
+                        continue;
+                    }
+                };
+
+                let fires = match scene_trigger.scene_trigger_type {
                     SceneTriggerType::KeyPressed => {
-                        if let Some(TriggerConditions::KeyConditions(cond)) = &scene_trigger.conditions {
-                            if self.check_key_pressed_trigger(cond.clone()) {
-                                for outcome in scene_trigger.outcome {
-                                    self.event_outcomes.push(outcome)
-                                }
-                            }
-                        } else {
-                            println!("A KeyPressed trigger was processed, but no condition could be found. Ignoring.");
-                        }
+                        self.check_conditions(conditions, &master_entity_list, None, false, None, false)
                     }
                     SceneTriggerType::KeyNotPressed => {
-                        if let Some(TriggerConditions::KeyConditions(cond)) = &scene_trigger.conditions {
-                            if self.check_key_not_pressed_trigger(cond.clone()) {
-                                for outcome in scene_trigger.outcome {
-                                    self.event_outcomes.push(outcome)
-                                }
+                        // Only the key leaves are read the other way round, the rest of the tree means the same as in a KeyPressed trigger
+                        self.check_conditions(conditions, &master_entity_list, None, false, None, true)
+                    }
+                    SceneTriggerType::Timer => {
+                        // The first timer in the tree drives the trigger, the rest of the conditions are checked when it goes off
+                        match find_timer_condition(conditions).cloned() {
+                            Some(timer_condition) => {
+                                self.check_timer_trigger(trigger_index, timer_condition, delta_time)
+                                    && self.check_conditions(conditions, &master_entity_list, None, true, None, false)
+                            }
+                            None => {
+                                println!("A Timer trigger was processed, but no timer condition could be found. Ignoring.");
+                                false
                             }
-                        } else {
-                            println!("A KeyNotPressed trigger was processed, but no condition could be found. Ignoring.");
                         }
                     }
-                    SceneTriggerType::Timer => {
-                        self.check_timer_trigger();
+                    SceneTriggerType::Variable => {
+                        let holds = self.check_conditions(conditions, &master_entity_list, None, false, None, false);
+                        self.check_variable_trigger(trigger_index, holds)
                     }
-                    _ => {
-                        println!("The scene trigger was empty.")
+                    SceneTriggerType::SceneEnter | SceneTriggerType::SceneExit => false,
+                };
+
+                if fires {
+                    self.record_trigger_fired(trigger_key, &current_scene_name, &scene_trigger.outcome);
+                    for outcome in scene_trigger.outcome {
+                        self.event_outcomes.push(outcome)
                     }
                 }
             }
         }
     }
 
-    pub fn check_key_pressed_trigger(&self, trigger_condition: KeyCondition) -> bool {
-        for key in trigger_condition.keys.iter() {
-            if let Some(key) = char_to_glfw_key(*key) {
-                if self.key_states.read().unwrap().is_key_pressed_raw(key) {
-                    return true;
+    /// Advances the timer belonging to a scene trigger and returns true on the frames it goes off.
+    /// One-shot timers fire once after time_in_seconds, repeating timers keep firing every interval after that.
+    pub fn check_timer_trigger(&mut self, trigger_index: usize, trigger_condition: TimerCondition, delta_time: f32) -> bool {
+        let timer = self.scene_timers.entry(trigger_index).or_insert(TimerState { elapsed: 0.0, fired: false });
+
+        if timer.fired && !trigger_condition.repeating {
+            return false;
+        }
+
+        timer.elapsed += delta_time;
+
+        let due_time = if timer.fired {
+            trigger_condition.interval_in_seconds.unwrap_or(trigger_condition.time_in_seconds)
+        } else {
+            trigger_condition.time_in_seconds
+        };
+
+        if timer.elapsed >= due_time {
+            // Keep the leftover time so repeating timers don't drift
+            timer.elapsed = (timer.elapsed - due_time).max(0.0);
+            timer.fired = true;
+            return true;
+        }
+        return false;
+    }
+
+    /// Returns true only on the frame the trigger's conditions start to hold, so a Variable trigger doesn't fire every frame
+    pub fn check_variable_trigger(&mut self, trigger_index: usize, holds: bool) -> bool {
+        let held_before = self.variable_trigger_states.insert(trigger_index, holds).unwrap_or(false);
+        holds && !held_before
+    }
+
+    /// Timers, Variable triggers, areas and speed thresholds start over whenever a scene is (re)loaded
+    pub fn reset_scene_timers(&mut self) {
+        self.scene_timers.clear();
+        self.variable_trigger_states.clear();
+        self.entities_in_areas.clear();
+        self.area_watchers.clear();
+        self.speed_trigger_states.clear();
+        self.colliding_pairs.clear();
+    }
+
+    /// Collects the outcomes of the scene's SceneEnter or SceneExit triggers
+    fn collect_scene_transition_outcomes(&mut self, scene_name: &str, transition: SceneTriggerType) -> Vec<Outcome> {
+        let mut outcomes = Vec::new();
+
+        let scene = self.scene_manager.read().unwrap().get_scene(scene_name);
+        if let Some(scene) = scene {
+            let scene_triggers = scene.read().unwrap().get_triggers();
+            let master_entity_list = self.master_entity_list.clone();
+            let master_entity_list = master_entity_list.read().unwrap();
+
+            for (trigger_index, scene_trigger) in scene_triggers.into_iter().enumerate() {
+                if std::mem::discriminant(&scene_trigger.scene_trigger_type) != std::mem::discriminant(&transition) {
+                    continue;
+                }
+
+                let trigger_key = trigger_key(&scene_trigger.id, scene_name, trigger_index);
+                if !self.is_trigger_ready_traced(&trigger_key, scene_name, scene_trigger.enabled, scene_trigger.cooldown_seconds, scene_trigger.max_fires) {
+                    continue;
+                }
+
+                let fires = match &scene_trigger.conditions {
+                    Some(conditions) => self.check_conditions(conditions, &master_entity_list, None, false, None, false),
+                    None => true,
+                };
+                if fires {
+                    self.record_trigger_fired(trigger_key, scene_name, &scene_trigger.outcome);
+                    outcomes.extend(scene_trigger.outcome);
+                }
+                else {
+                    self.trace(TraceKind::ConditionsFailed, scene_name, trigger_key);
                 }
             }
         }
-        return false;
+        outcomes
+    }
+
+    /// The SceneExit outcomes to run before swapping to the given scene.
+    /// Empty when they have already run and the swap can go ahead.
+    fn take_scene_exit_outcomes(&mut self, next_scene_name: &str) -> Vec<Outcome> {
+        if self.exiting_to.as_deref() == Some(next_scene_name) {
+            self.exiting_to = None;
+            return Vec::new();
+        }
+
+        let current_scene_name = self.game_state.read().unwrap().get_current_scene_name();
+        let exit_outcomes = self.collect_scene_transition_outcomes(&current_scene_name, SceneTriggerType::SceneExit);
+        if !exit_outcomes.is_empty() {
+            self.exiting_to = Some(next_scene_name.to_owned());
+        }
+        exit_outcomes
     }
-    
 
-    pub fn check_key_not_pressed_trigger(&self, trigger_condition: KeyCondition) -> bool {
-        for key in trigger_condition.keys.iter() {
-            if let Some(key) = char_to_glfw_key(*key) {
-                if self.key_states.read().unwrap().is_key_pressed_raw(key) {
-                    return false;
+    /// Queues the SceneEnter outcomes of the scene that was just loaded and the OnSpawn outcomes of everything in it
+    fn queue_scene_loaded_outcomes(&mut self) {
+        let current_scene_name = self.game_state.read().unwrap().get_current_scene_name();
+        self.publish_event(EngineEvent::SceneLoaded { scene_name: current_scene_name.clone() });
+        let enter_outcomes = self.collect_scene_transition_outcomes(&current_scene_name, SceneTriggerType::SceneEnter);
+        self.event_outcomes.extend(enter_outcomes);
+
+        let entity_names: Vec<String> = self.master_entity_list.read().unwrap().get_entities().read().unwrap().keys().cloned().collect();
+        for entity_name in entity_names {
+            self.reset_spawn_trigger_states(&entity_name);
+            self.check_spawn_triggers(&entity_name);
+        }
+    }
+
+    /// Everything in a (re)loaded scene spawns again, so its OnSpawn triggers get their fires and cooldowns back.
+    /// An EnableTrigger or DisableTrigger on them still holds.
+    fn reset_spawn_trigger_states(&mut self, entity_name: &str) {
+        let triggers = match self.master_entity_list.read().unwrap().get_entity(entity_name) {
+            Some(entity) => entity.read().unwrap().get_triggers().clone(),
+            None => return,
+        };
+
+        for (trigger_index, trigger) in triggers.iter().enumerate() {
+            if let TriggerType::OnSpawn = trigger.trigger_type {
+                if let Some(trigger_state) = self.trigger_states.get_mut(&trigger_key(&trigger.id, entity_name, trigger_index)) {
+                    trigger_state.last_fired = None;
+                    trigger_state.fire_count = 0;
+                }
+            }
+        }
+    }
+
+    /// Queues the OnSpawn outcomes of an entity that was just created
+    fn check_spawn_triggers(&mut self, entity_name: &str) {
+        let master_entity_list = self.master_entity_list.clone();
+        let master_entity_list = master_entity_list.read().unwrap();
+        let triggers = match master_entity_list.get_entity(entity_name) {
+            Some(entity) => entity.read().unwrap().get_triggers().clone(),
+            None => return,
+        };
+
+        for (trigger_index, trigger) in triggers.iter().enumerate() {
+            if let TriggerType::OnSpawn = trigger.trigger_type {
+                let trigger_key = trigger_key(&trigger.id, entity_name, trigger_index);
+                if !self.is_trigger_ready_traced(&trigger_key, entity_name, trigger.enabled, trigger.cooldown_seconds, trigger.max_fires) {
+                    continue;
+                }
+
+                let fires = match &trigger.conditions {
+                    Some(conditions) => self.check_conditions(conditions, &master_entity_list, None, false, None, false),
+                    None => true,
+                };
+                if fires {
+                    self.record_trigger_fired(trigger_key, entity_name, &trigger.outcome);
+                    for outcome in trigger.outcome.iter() {
+                        self.event_outcomes.push(resolve_placeholders(outcome, entity_name, None));
+                    }
+                }
+                else {
+                    self.trace(TraceKind::ConditionsFailed, entity_name, trigger_key);
                 }
             }
         }
-        return true;
     }
 
-    pub fn check_timer_trigger(&self) {
-        println!("Timer trigger is not implemented yet");
+    /// Fires EnteredArea, LeftArea, SpeedAbove and SpeedBelow triggers for every entity that has them
+    pub fn check_area_and_speed_triggers(&mut self) {
+        let areas = self.game_state.read().unwrap().get_areas().clone();
+        let master_entity_list = self.master_entity_list.clone();
+        let master_entity_list = master_entity_list.read().unwrap();
+        let master_graphics_list = self.master_graphics_list.clone();
+        let master_graphics_list = master_graphics_list.read().unwrap();
+
+        let entities: Vec<Arc<RwLock<GenericEntity>>> = master_entity_list.get_entities().read().unwrap().values().cloned().collect();
+        let mut entities_in_areas: HashSet<(String, String)> = HashSet::new();
+        let mut area_watchers: HashSet<String> = HashSet::new();
+
+        for entity in entities {
+            let (entity_name, triggers, speed) = {
+                let entity_read = entity.read().unwrap();
+                (entity_read.get_name().to_owned(), entity_read.get_triggers().clone(), entity_read.get_velocity().magnitude())
+            };
+
+            let watches_areas = triggers.iter().any(|trigger| matches!(trigger.trigger_type, TriggerType::EnteredArea | TriggerType::LeftArea));
+            let mut entered_areas: Vec<String> = Vec::new();
+            let mut left_areas: Vec<String> = Vec::new();
+            if watches_areas {
+                if let Some(object) = master_graphics_list.get_object(&entity_name) {
+                    let position = object.read().unwrap().get_position();
+                    // Like speed triggers, the first frame only records where the entity starts, it hasn't entered anything yet
+                    let seen_before = self.area_watchers.contains(&entity_name);
+                    area_watchers.insert(entity_name.clone());
+                    for area in areas.iter() {
+                        let key = (entity_name.clone(), area.name.clone());
+                        let inside = area.shape.contains(Vector2::new(position.x, position.y));
+                        let was_inside = self.entities_in_areas.contains(&key);
+
+                        if inside && !was_inside && seen_before {
+                            entered_areas.push(area.name.clone());
+                        }
+                        else if !inside && was_inside {
+                            left_areas.push(area.name.clone());
+                        }
+                        if inside {
+                            entities_in_areas.insert(key);
+                        }
+                    }
+                }
+            }
+
+            for (trigger_index, trigger) in triggers.iter().enumerate() {
+                let trigger_key = trigger_key(&trigger.id, &entity_name, trigger_index);
+
+                // Each trigger fires once per area it crossed, or once for a speed threshold crossing
+                let firing_areas: Vec<Option<&str>> = match trigger.trigger_type {
+                    TriggerType::EnteredArea => entered_areas.iter().map(|area| Some(area.as_str())).collect(),
+                    TriggerType::LeftArea => left_areas.iter().map(|area| Some(area.as_str())).collect(),
+                    TriggerType::SpeedAbove | TriggerType::SpeedBelow => {
+                        let threshold = match trigger.conditions.as_ref().and_then(find_speed_condition) {
+                            Some(speed_condition) => speed_condition.speed,
+                            None => {
+                                println!("A {:?} trigger on {} has no speed condition. Ignoring.", trigger.trigger_type, entity_name);
+                                continue;
+                            }
+                        };
+                        let crossed = match trigger.trigger_type {
+                            TriggerType::SpeedAbove => speed > threshold,
+                            _ => speed < threshold,
+                        };
+                        // The first frame only records where the entity starts, it hasn't crossed anything yet
+                        let crossed_before = self.speed_trigger_states.insert(trigger_key.clone(), crossed);
+                        if crossed && crossed_before == Some(false) { vec![None] } else { Vec::new() }
+                    }
+                    _ => Vec::new(),
+                };
+
+                for area in firing_areas {
+                    if !self.is_trigger_ready_traced(&trigger_key, &entity_name, trigger.enabled, trigger.cooldown_seconds, trigger.max_fires) {
+                        break;
+                    }
+
+                    let fires = match &trigger.conditions {
+                        Some(conditions) => self.check_conditions(conditions, &master_entity_list, None, true, area, false),
+                        None => true,
+                    };
+                    if fires {
+                        self.record_trigger_fired(trigger_key.clone(), &entity_name, &trigger.outcome);
+                        for outcome in trigger.outcome.iter() {
+                            self.event_outcomes.push(resolve_placeholders(outcome, &entity_name, None));
+                        }
+                    }
+                    else {
+                        self.trace(TraceKind::ConditionsFailed, &entity_name, format!("{} in {}", trigger_key, area.unwrap_or("no area")));
+                    }
+                }
+            }
+        }
+
+        self.entities_in_areas = entities_in_areas;
+        self.area_watchers = area_watchers;
     }
 
-    pub fn swap_scene_without_saving(&self, scene_name: String) {
+    pub fn swap_scene_without_saving(&mut self, scene_name: String) {
+        self.publish_scene_unloaded();
+        self.reset_scene_timers();
+        self.scheduler.clear_for_scene_swap();
         self.master_entity_list.write().unwrap().remove_all();
         self.master_graphics_list.write().unwrap().remove_all();
         self.scene_manager.read().unwrap().load_scene(&mut self.game_state.write().unwrap(), &self.master_entity_list.write().unwrap(), &self.master_graphics_list.write().unwrap(), scene_name);
+        self.queue_scene_loaded_outcomes();
     }
 
-    pub fn swap_scene(&self, scene_name: String) {
+    pub fn swap_scene(&mut self, scene_name: String) {
+        self.publish_scene_unloaded();
+        self.reset_scene_timers();
+        self.scheduler.clear_for_scene_swap();
         self.scene_manager.write().unwrap().save_scene(&self.game_state.read().unwrap().get_current_scene_name(), &self.master_entity_list.read().unwrap(), &self.master_graphics_list.read().unwrap());
 
         self.master_entity_list.write().unwrap().remove_all();
         self.master_graphics_list.write().unwrap().remove_all();
         self.scene_manager.read().unwrap().load_scene(&mut self.game_state.write().unwrap(), &self.master_entity_list.write().unwrap(), &self.master_graphics_list.write().unwrap(), scene_name);
+        self.queue_scene_loaded_outcomes();
+    }
+
+    fn publish_scene_unloaded(&mut self) {
+        let current_scene_name = self.game_state.read().unwrap().get_current_scene_name();
+        // Nothing is loaded before the first scene
+        if !current_scene_name.is_empty() {
+            self.publish_event(EngineEvent::SceneUnloaded { scene_name: current_scene_name });
+        }
+    }
+
+    /// Writes the current scene name and all variables to a save file
+    pub fn save_game(&self, save_name: &str) -> OutcomeResult {
+        if let Err(e) = fs::create_dir_all("src\\resources\\saves") {
+            return Err(format!("failed to create the saves directory: {}", e));
+        }
+
+        let file_path = format!("src\\resources\\saves\\{}.json", save_name);
+        match self.game_state.read().unwrap().save_to_json(&file_path) {
+            Ok(()) => {
+                println!("Saved the game to {}", file_path);
+                Ok(())
+            }
+            Err(e) => Err(format!("failed to save the game to {}: {}", file_path, e)),
+        }
     }
 
-    pub fn create_object(&self, create_object_args: ObjectData) {
+    /// Restores the variables from a save file and swaps to the scene it was made in
+    pub fn load_game(&mut self, save_name: &str) -> OutcomeResult {
+        let file_path = format!("src\\resources\\saves\\{}.json", save_name);
+        let save_data = self.game_state.write().unwrap().load_from_json(&file_path);
+
+        match save_data {
+            Ok(save_data) => {
+                // Quests added since the save was made still start on their own
+                self.quest_tracker.read().unwrap().start_auto_quests(&mut self.game_state.write().unwrap());
+                if !save_data.current_scene_name.is_empty() {
+                    self.swap_scene_without_saving(save_data.current_scene_name.clone());
+                }
+                self.game_state.write().unwrap().restore_physics_overrides(&save_data);
+                Ok(())
+            }
+            Err(e) => Err(format!("failed to load the game from {}: {}", file_path, e)),
+        }
+    }
+
+    pub fn create_object(&mut self, create_object_args: ObjectData) {
 
         let json_shader = CustomShader::new(
             &create_object_args.graphics.vertex_shader,
@@ -291,7 +1202,7 @@ impl EventHandler {
         });
 
         let mut json_collision_modes = HashSet::new();
-        for collision_mode in create_object_args.entity.collision_modes {
+        for collision_mode in create_object_args.entity.collision_modes.iter() {
             match collision_mode.as_str() {
                 "AABB" => { json_collision_modes.insert(CollisionMode::AABB); }
                 "Circle" => { json_collision_modes.insert(CollisionMode::Circle); }
@@ -300,9 +1211,20 @@ impl EventHandler {
             }
         }
 
+        let offset = match &create_object_args.relative_to {
+            Some(relative_to) => match self.get_object_position_2d(relative_to) {
+                Some(offset) => offset,
+                None => {
+                    println!("Can't create {} relative to {}, no such object.", create_object_args.graphics.name, relative_to);
+                    return;
+                }
+            },
+            None => Vector2::new(0.0, 0.0),
+        };
+
         let position = Vector3::new(
-            create_object_args.graphics.position[0],
-            create_object_args.graphics.position[1],
+            create_object_args.graphics.position[0] + offset.x,
+            create_object_args.graphics.position[1] + offset.y,
             create_object_args.graphics.position[2],
         );
 
@@ -321,87 +1243,273 @@ impl EventHandler {
             animation_config,
         );
 
-        let velocity = create_object_args.entity.velocity.unwrap_or_else(|| vec![0.0, 0.0]);
+        let velocity = create_object_args.entity.velocity.clone().unwrap_or_else(|| vec![0.0, 0.0]);
         let velocity_vector = Vector2::new(velocity[0], velocity[1]);
 
+        let angular_velocity = create_object_args.entity.angular_velocity.unwrap_or(0.0);
+
+        // Materials are resolved here so the entity only ever sees the final values
+        let (width, height) = graphics_object.dimensions();
+        let physical_properties = self.scene_manager.read().unwrap().resolve_physical_properties(&create_object_args.entity, width * height);
+
+        // Default to a solid rectangle the size of the graphics object
+        let moment_of_inertia = create_object_args.entity.moment_of_inertia.unwrap_or_else(|| rectangle_moment_of_inertia(physical_properties.weight, width, height));
+
         // Default collision_priority to 0 if None
         let collision_priority = create_object_args.entity.collision_priority.unwrap_or(0);
 
         let triggers = create_object_args.entity.triggers.unwrap_or_default();
 
-        let entity = GenericEntity::new(
-            create_object_args.entity.name.clone(),
-            create_object_args.entity.weight,
-            velocity_vector,
-            create_object_args.entity.affected_by_gravity,
-            create_object_args.entity.is_static,
-            create_object_args.entity.elasticity,
-            create_object_args.entity.active_collision,
+        let entity = GenericEntity::new(EntityConfig {
+            name: create_object_args.entity.name.clone(),
+            weight: physical_properties.weight,
+            velocity: velocity_vector,
+            angular_velocity,
+            moment_of_inertia,
+            affected_by_gravity: create_object_args.entity.affected_by_gravity,
+            gravity_scale: create_object_args.entity.gravity_scale.unwrap_or(1.0),
+            linear_drag: create_object_args.entity.linear_drag.unwrap_or(0.0),
+            is_static: create_object_args.entity.is_static,
+            elasticity: physical_properties.elasticity,
+            friction: physical_properties.friction,
+            active_collision: create_object_args.entity.active_collision,
             collision_priority,
-            json_collision_modes,
+            collision_modes: json_collision_modes,
             triggers,
-        );
+            allow_sleep: create_object_args.entity.allow_sleep.unwrap_or(false),
+            script: create_object_args.entity.script.clone(),
+        });
 
         let wrapped_graphics_object = Arc::new(RwLock::new(graphics_object));
         let wrapped_entity = Arc::new(RwLock::new(entity));
 
         self.master_entity_list.write().unwrap().add_entity(wrapped_entity);
         self.master_graphics_list.write().unwrap().add_object(wrapped_graphics_object);
+
+        self.publish_event(EngineEvent::EntityCreated { entity_name: create_object_args.entity.name.clone() });
+        self.check_spawn_triggers(&create_object_args.entity.name);
     }
     
-    pub fn destroy_object(&self, entity_name: String) -> Vec<Outcome> {
+    /// Graphics-only objects can be destroyed too, only a name that matches neither is an error
+    pub fn destroy_object(&mut self, entity_name: String) -> Result<Vec<Outcome>, String> {
         let mut event_outcomes: Vec<Outcome> = Vec::new();
 
-        if let Some(entity) = self.master_entity_list.read().unwrap().get_entity(&entity_name) {
-            if let Ok(entity) = entity.read() {
-                self.check_destruction_triggers(entity.get_triggers(), &mut event_outcomes);
+        {
+            let master_entity_list = self.master_entity_list.clone();
+            let master_entity_list = master_entity_list.read().unwrap();
+            if let Some(entity) = master_entity_list.get_entity(&entity_name) {
+                let triggers = entity.read().unwrap().get_triggers().clone();
+                self.check_destruction_triggers(&triggers, &entity_name, &master_entity_list, &mut event_outcomes);
+            }
+            else if self.master_graphics_list.read().unwrap().get_object(&entity_name).is_none() {
+                return Err(format!("no object named {} to destroy", entity_name));
             }
         }
 
+        // Anything resting on it would otherwise sleep on in mid-air
+        self.wake_entities_touching(&entity_name);
+
         self.master_entity_list.write().unwrap().remove_entity(&entity_name);
         self.master_graphics_list.write().unwrap().remove_object(&entity_name);
+        self.publish_event(EngineEvent::EntityDestroyed { entity_name });
 
-        return event_outcomes;
+        return Ok(event_outcomes);
     }
 
-    pub fn teleport_object(&self, object_name: String, new_position: Vec<f32>) {
+    /// Wakes every entity that touches the object, or collided with it last frame
+    fn wake_entities_touching(&self, object_name: &str) {
+        let master_graphics_list = self.master_graphics_list.read().unwrap();
+        let Some(object) = master_graphics_list.get_object(object_name) else { return; };
+        let object_box = OrientedBox::from_object(&object.read().unwrap());
+
+        let entities = self.master_entity_list.read().unwrap().get_entities();
+        for (name, entity) in entities.read().unwrap().iter() {
+            if name == object_name {
+                continue;
+            }
+            let collided = self.colliding_pairs.contains(&ordered_pair(name, object_name));
+            let touching = master_graphics_list.get_object(name)
+                .is_some_and(|other| object_box.is_near(&OrientedBox::from_object(&other.read().unwrap()), CONTACT_MARGIN));
+            if collided || touching {
+                entity.write().unwrap().wake();
+            }
+        }
+    }
+
+    pub fn wake_object(&self, object_name: String) -> OutcomeResult {
+        if let Some(entity) = self.master_entity_list.read().unwrap().get_entity(&object_name) {
+            entity.write().unwrap().wake();
+            Ok(())
+        }
+        else {
+            Err(format!("no entity named {} to wake", object_name))
+        }
+    }
+
+    pub fn teleport_object(&self, object_name: String, new_position: Vec<f32>, relative_to: Option<String>) -> OutcomeResult {
+        if new_position.len() != 2 && new_position.len() != 3 {
+            return Err(format!("invalid position size for {}, expected 2D or 2D+Z position", object_name));
+        }
+
+        // Read before locking the teleporting object for writing, it may be relative to itself
+        let offset = match relative_to {
+            Some(relative_to) => match self.get_object_position_2d(&relative_to) {
+                Some(offset) => offset,
+                None => return Err(format!("can't teleport {} relative to {}, no such object", object_name, relative_to)),
+            },
+            None => Vector2::new(0.0, 0.0),
+        };
+
         let teleporting_object_option = self.master_graphics_list.write().unwrap().get_object(&object_name);
-        if let Some(teleporting_object) = teleporting_object_option {
-            let mut teleporting_object_write = teleporting_object.write().unwrap();
-
-            let current_position = teleporting_object_write.get_position();
-
-            // If the provided destination doesn't include a new Z-value we'll keep the existing one
-            let new_position = if new_position.len() == 2 {
-                Vector3::new(new_position[0], new_position[1], current_position.z)
-            } else if new_position.len() == 3 {
-                Vector3::new(new_position[0], new_position[1], new_position[2])
-            } else {
-                println!("Invalid position size, expected 2D or 2D+Z position.");
-                return; // Leave before passing anything
-            };
+        let Some(teleporting_object) = teleporting_object_option else {
+            return Err(format!("no object named {} to teleport", object_name));
+        };
+        let mut teleporting_object_write = teleporting_object.write().unwrap();
+
+        // If the provided destination doesn't include a new Z-value we'll keep the existing one
+        let z = new_position.get(2).copied().unwrap_or(teleporting_object_write.get_position().z);
+        teleporting_object_write.set_position(Vector3::new(new_position[0] + offset.x, new_position[1] + offset.y, z));
+
+        // A sleeping entity wouldn't notice it is now somewhere else
+        if let Some(entity) = self.master_entity_list.read().unwrap().get_entity(&object_name) {
+            entity.write().unwrap().wake();
+        }
+        Ok(())
+    }
+
+    /// The x and y position of a graphics object, used to place things relative to it
+    fn get_object_position_2d(&self, object_name: &str) -> Option<Vector2<f32>> {
+        let object = self.master_graphics_list.read().unwrap().get_object(object_name)?;
+        let position = object.read().unwrap().get_position();
+        Some(Vector2::new(position.x, position.y))
+    }
+
+    pub fn set_entity_property(&self, object_name: String, property: EntityProperty) -> OutcomeResult {
+        // Numbers that would break the physics are refused rather than clamped, so a typo in a scene file is noticed
+        let valid = match property {
+            EntityProperty::Weight(weight) => weight.is_finite() && weight > 0.0,
+            EntityProperty::Elasticity(value) | EntityProperty::Friction(value) | EntityProperty::GravityScale(value) | EntityProperty::LinearDrag(value) => value.is_finite() && value >= 0.0,
+            _ => true,
+        };
+        if !valid {
+            return Err(format!("invalid value for {:?} on {}", property, object_name));
+        }
 
-            teleporting_object_write.set_position(new_position);
+        if let Some(entity) = self.master_entity_list.read().unwrap().get_entity(&object_name) {
+            let mut entity = entity.write().unwrap();
+            match property {
+                EntityProperty::Weight(weight) => entity.set_weight(weight),
+                EntityProperty::Elasticity(elasticity) => entity.set_elasticity(elasticity),
+                EntityProperty::Friction(friction) => entity.set_friction(friction),
+                EntityProperty::GravityScale(gravity_scale) => entity.set_gravity_scale(gravity_scale),
+                EntityProperty::LinearDrag(linear_drag) => entity.set_linear_drag(linear_drag),
+                EntityProperty::AffectedByGravity(affected_by_gravity) => entity.set_affected_by_gravity(affected_by_gravity),
+                EntityProperty::IsStatic(is_static) => entity.set_is_static(is_static),
+                EntityProperty::ActiveCollision(active_collision) => entity.set_active_collision(active_collision),
+            }
+            // Whatever changed, the entity should get a chance to react to it
+            entity.wake();
+            Ok(())
+        }
+        else {
+            Err(format!("no entity named {} to set {:?} on", object_name, property))
         }
     }
 
-    pub fn enqueue_audio(&self, audio_name: String, audio_type: AudioType, volume: f32) {
-        self.audio_manager.read().unwrap().enqueue_audio(&audio_name, audio_type, volume, false);
+    /// Changes gravity for the running game and for the current scene, so it is still changed after coming back to the scene
+    pub fn set_scene_gravity(&self, gravity: &[f32]) -> OutcomeResult {
+        let Some(gravity) = vector_from_values(gravity) else {
+            return Err("invalid gravity size, expected 1 or 2 values".to_owned());
+        };
+
+        // Only until the next scene loads, the scene file keeps its own gravity
+        self.game_state.write().unwrap().set_gravity_override(Some(gravity));
+
+        // Entities resting under the old gravity need to feel the new one
+        for entity in self.master_entity_list.read().unwrap().get_entities().read().unwrap().values() {
+            entity.write().unwrap().wake();
+        }
+        Ok(())
     }
 
-    pub fn set_atlas_config(&self, object_name: String, new_atlas_config: AtlasConfig) {
+    pub fn set_terminal_velocity(&self, terminal_velocity: &[f32]) -> OutcomeResult {
+        let terminal_velocity = match vector_from_values(terminal_velocity) {
+            Some(terminal_velocity) if terminal_velocity.x >= 0.0 && terminal_velocity.y >= 0.0 => terminal_velocity,
+            _ => return Err("invalid terminal velocity, expected 1 or 2 values that are not negative".to_owned()),
+        };
+
+        self.game_state.write().unwrap().set_terminal_velocity_override(Some(terminal_velocity));
+        Ok(())
+    }
+
+    pub fn stop_audio(&self, audio_type: Option<AudioType>) {
+        let audio_manager = self.audio_manager.read().unwrap();
+        match audio_type {
+            Some(AudioType::Music) => audio_manager.stop_music_sinks(),
+            Some(AudioType::Sound) => audio_manager.stop_sound_sinks(),
+            Some(AudioType::UI) => audio_manager.stop_ui_sinks(),
+            None => audio_manager.stop_audio(),
+        }
+    }
+
+    pub fn set_rotation(&self, object_name: String, rotation: f32) -> OutcomeResult {
+        if !rotation.is_finite() {
+            return Err(format!("invalid rotation for {}", object_name));
+        }
+
+        let Some(object) = self.master_graphics_list.read().unwrap().get_object(&object_name) else {
+            return Err(format!("no object named {} to rotate", object_name));
+        };
+        object.write().unwrap().set_rotation(rotation);
+        // Graphics-only objects have no entity to wake
+        if let Some(entity) = self.master_entity_list.read().unwrap().get_entity(&object_name) {
+            entity.write().unwrap().wake();
+        }
+        Ok(())
+    }
+
+    pub fn set_scale(&self, object_name: String, scale: f32) -> OutcomeResult {
+        if !scale.is_finite() || scale <= 0.0 {
+            return Err(format!("invalid scale {} for {}, it has to be above zero", scale, object_name));
+        }
+
+        let Some(object) = self.master_graphics_list.read().unwrap().get_object(&object_name) else {
+            return Err(format!("no object named {} to scale", object_name));
+        };
+        object.write().unwrap().set_scale(scale);
+        // Graphics-only objects have no entity to wake
+        if let Some(entity) = self.master_entity_list.read().unwrap().get_entity(&object_name) {
+            entity.write().unwrap().wake();
+        }
+        Ok(())
+    }
+
+    pub fn enqueue_audio(&mut self, audio_name: String, audio_type: AudioType, volume: f32) {
+        self.audio_manager.read().unwrap().enqueue_audio(&audio_name, audio_type.clone(), volume, false);
+        self.publish_event(EngineEvent::AudioStarted { audio_name, audio_type });
+    }
+
+    pub fn set_atlas_config(&self, object_name: String, new_atlas_config: AtlasConfig) -> OutcomeResult {
         let master_graphics_list_write = self.master_graphics_list.write().unwrap();
         if let Some(object) = master_graphics_list_write.get_object(&object_name) {
             let mut object_write = object.write().unwrap();
             object_write.set_atlas_config(Some(new_atlas_config));
+            Ok(())
+        }
+        else {
+            Err(format!("no object named {} to set the atlas config of", object_name))
         }
     }
 
-    pub fn set_animation_config(&self, object_name: String, new_animation_config: AnimationConfig) {
+    pub fn set_animation_config(&self, object_name: String, new_animation_config: AnimationConfig) -> OutcomeResult {
         let master_graphics_list_write = self.master_graphics_list.write().unwrap();
         if let Some(object) = master_graphics_list_write.get_object(&object_name) {
             let mut object_write = object.write().unwrap();
             object_write.set_animation_config(Some(new_animation_config));
+            Ok(())
+        }
+        else {
+            Err(format!("no object named {} to set the animation config of", object_name))
         }
     }
 
@@ -417,38 +1525,93 @@ impl EventHandler {
             self.camera.write().unwrap().set_tracking_target(None);
         }
     }
+}
 
-    pub fn homebringer_sequence(&self) {
-        if let Some(player_object) = self.master_graphics_list.read().unwrap().get_object("player") {
-            let player_z = player_object.read().unwrap().get_position().z;
-            player_object.write().unwrap().set_position(Vector3::new(0.0, 0.0, player_z));
-            self.audio_manager.read().unwrap().enqueue_audio("Homebringer", AudioType::UI, 0.6, false);
-        }
+/// Triggers are tracked by their owner (entity or scene name) and their id, or their position in the owner's list if they have no id.
+/// Entities spawned from the same template share ids, the owner keeps their states apart.
+fn trigger_key(id: &Option<String>, owner: &str, trigger_index: usize) -> String {
+    match id {
+        Some(id) => format!("{}:{}", owner, id),
+        None => format!("{}#{}", owner, trigger_index),
     }
+}
+
+/// Queues outcomes to run straight after the one at index, ahead of everything already queued behind it,
+/// so the outcomes of an If, RandomChoice or Repeat run where they were written
+fn run_next(event_outcomes: &mut Vec<Outcome>, index: usize, outcomes: Vec<Outcome>) {
+    event_outcomes.splice(index + 1..index + 1, outcomes);
+}
+
+/// A pair of entity names in name order, the way colliding_pairs stores them
+fn ordered_pair(name_1: &str, name_2: &str) -> (String, String) {
+    if name_1 <= name_2 { (name_1.to_owned(), name_2.to_owned()) } else { (name_2.to_owned(), name_1.to_owned()) }
+}
+
+/// Reads a 2D vector the way scene files write them, a single value is the y-axis
+fn vector_from_values(values: &[f32]) -> Option<Vector2<f32>> {
+    match values.len() {
+        2 => Some(Vector2::new(values[0], values[1])),
+        1 => Some(Vector2::new(0.0, values[0])),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rusted_engine::{events::triggers::{IfArgs, SetVariableArgs, VariableCondition, WakeObjectArgs}, game_state::{Comparison, GameVariable}};
 
-    pub fn gorbino_sequence(&self) {
-        self.audio_manager.read().unwrap().enqueue_audio("gorbino", AudioType::Music, 0.6, false);
+    use super::*;
+
+    fn wake(object_name: &str) -> Outcome {
+        Outcome::WakeObject(WakeObjectArgs { object_name: object_name.to_owned() })
     }
 
-    pub fn explosion_sequence(&self) {
-        if let Some(player_object) = self.master_graphics_list.read().unwrap().get_object("testscene_obj4") {
-            player_object.write().unwrap().set_position(Vector3::new(0.0, 0.0, 0.0));
-            self.audio_manager.read().unwrap().enqueue_audio("RobloxExplosion", AudioType::UI, 0.6, false);
-        }
+    fn set_x() -> Outcome {
+        Outcome::SetVariable(SetVariableArgs { variable_name: "x".to_owned(), value: GameVariable::Int(1) })
     }
 
-    pub fn gravity_sequence(&self) {
-        if let Some(player_entity) = self.master_entity_list.read().unwrap().get_entity("player") {
-            let mut player_entity_write = player_entity.write().unwrap();
-            let toggle_gravity = !player_entity_write.is_affected_by_gravity();
-            player_entity_write.set_affected_by_gravity(toggle_gravity);
-            self.audio_manager.read().unwrap().enqueue_audio("Gravity", AudioType::UI, 0.6, false);
-        }
+    fn if_x_is_set(then: Vec<Outcome>) -> Outcome {
+        let condition = TriggerConditions::VariableConditions(VariableCondition { variable_name: "x".to_owned(), comparison: Comparison::Equal, value: GameVariable::Int(1) });
+        Outcome::If(IfArgs { condition, then, otherwise: Vec::new() })
     }
 
-    pub fn reset_sequence(&self) {
-        self.swap_scene("testscene".to_owned());
-        self.teleport_object("player".to_owned(), vec![0.0, 0.0, 0.0]);
-        self.audio_manager.read().unwrap().enqueue_audio("TechMysterious", AudioType::UI, 0.6, false);
+    fn describe(outcomes: &[Outcome]) -> Vec<String> {
+        outcomes.iter().map(|outcome| match outcome {
+            Outcome::WakeObject(args) => args.object_name.clone(),
+            Outcome::SetVariable(args) => format!("set {}", args.variable_name),
+            Outcome::If(_) => "if".to_owned(),
+            other => panic!("unexpected outcome {:?}", other),
+        }).collect()
+    }
+
+    #[test]
+    fn a_branch_runs_before_the_outcomes_written_after_it() {
+        // The first If sets x, which the second If reads, before the last outcome runs
+        let mut event_outcomes = vec![if_x_is_set(Vec::new()), if_x_is_set(vec![wake("sound")]), wake("after")];
+
+        run_next(&mut event_outcomes, 0, vec![set_x()]);
+        assert_eq!(describe(&event_outcomes), vec!["if", "set x", "if", "after"]);
+
+        run_next(&mut event_outcomes, 2, vec![wake("sound")]);
+        assert_eq!(describe(&event_outcomes), vec!["if", "set x", "if", "sound", "after"]);
+    }
+
+    #[test]
+    fn a_branch_keeps_its_own_order() {
+        let mut event_outcomes = vec![wake("repeat"), wake("after")];
+
+        run_next(&mut event_outcomes, 0, vec![wake("first"), wake("second"), wake("third")]);
+
+        assert_eq!(describe(&event_outcomes), vec!["repeat", "first", "second", "third", "after"]);
+    }
+
+    #[test]
+    fn a_branch_of_the_last_outcome_goes_on_the_end() {
+        let mut event_outcomes = vec![wake("before"), wake("choice")];
+
+        run_next(&mut event_outcomes, 1, vec![wake("chosen")]);
+        run_next(&mut event_outcomes, 2, Vec::new());
+
+        assert_eq!(describe(&event_outcomes), vec!["before", "choice", "chosen"]);
     }
 }
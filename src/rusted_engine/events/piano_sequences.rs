@@ -1,51 +1,42 @@
-use crate::rusted_engine::input::piano::Piano;
+use std::{fs::File, io::Read};
 
-use super::event_handler::EventHandler;
+use serde::{Deserialize, Serialize};
 
-pub fn check_piano_sequences(piano: &mut Piano, event_handler: &EventHandler) {
-    homebringer_sequence(piano, event_handler);
-    gorbino_sequence(piano, event_handler);
-    explosion_sequence(piano, event_handler);
-    gravity_sequence(piano, event_handler);
-    reset_sequence(piano, event_handler);
-}
+use crate::rusted_engine::input::piano::Piano;
 
-fn homebringer_sequence(piano: &mut Piano, event_handler: &EventHandler) {
-    let sequence = ["A4", "A4", "E5", "E5", "Db5", "Db5", "Ab5", "Ab5"];
-    if piano.check_for_sequence_and_clear(&sequence) {
-        println!("Found the Homebringer sequence! History cleared.");
-        event_handler.homebringer_sequence();
-    }
-}
+use super::{event_bus::EngineEvent, event_handler::EventHandler, triggers::Outcome};
 
-fn gorbino_sequence(piano: &mut Piano, event_handler: &EventHandler) {
-    let sequence = ["G5", "A5", "E5", "C5", "B4", "A4", "D5", "F5"];
-    if piano.check_for_sequence_and_clear(&sequence) {
-        println!("Found the Gorbino sequence! History cleared.");
-        event_handler.gorbino_sequence();
-    }
+/// A run of notes that, once played on the piano, emits its outcomes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PianoSequence {
+    pub name: String,
+    pub notes: Vec<String>,
+    #[serde(default)]
+    pub tempo_tolerance: Option<f32>, // Most seconds allowed between two notes of the sequence, no limit if missing
+    pub outcome: Vec<Outcome>,
 }
 
-fn explosion_sequence(piano: &mut Piano, event_handler: &EventHandler) {
-    let sequence = ["F5", "A5", "A4", "C5", "G5", "D5", "E5", "B4"];
-    if piano.check_for_sequence_and_clear(&sequence) {
-        println!("Found the Explosion sequence! History cleared.");
-        event_handler.explosion_sequence();
-    }
-}
+/// Loads a piano sequences file, a JSON list of piano sequences
+pub fn load_piano_sequences_from_json(file_path: &str) -> Result<Vec<PianoSequence>, Box<dyn std::error::Error>> {
+    let mut file = File::open(file_path)?;
+    let mut data = String::new();
+    file.read_to_string(&mut data)?;
 
-fn gravity_sequence(piano: &mut Piano, event_handler: &EventHandler) {
-    let sequence = ["B4", "B4", "B4", "G5", "G5", "G5"];
-    if piano.check_for_sequence_and_clear(&sequence) {
-        println!("Found the Gravity sequence! History cleared.");
-        event_handler.gravity_sequence();
-    }
+    let piano_sequences: Vec<PianoSequence> = serde_json::from_str(&data)?;
+    Ok(piano_sequences)
 }
 
-fn reset_sequence(piano: &mut Piano, event_handler: &EventHandler) {
-    let sequence = ["B4", "Gb5", "B4", "Gb5", "B4", "Gb5", "B4", "Gb5"];
-    if piano.check_for_sequence_and_clear(&sequence) {
-        println!("Found the Reset sequence! History cleared.");
-        event_handler.reset_sequence();
+/// Checks every piano sequence registered for the current scene against the note history and queues the outcomes of the first match
+pub fn check_piano_sequences(piano: &mut Piano, event_handler: &mut EventHandler) {
+    for piano_sequence in event_handler.get_current_piano_sequences() {
+        if piano.check_for_sequence_and_clear(&piano_sequence.notes, piano_sequence.tempo_tolerance) {
+            println!("Found the {} piano sequence! History cleared.", piano_sequence.name);
+            event_handler.publish_event(EngineEvent::PianoSequencePlayed { sequence_name: piano_sequence.name.clone() });
+            for outcome in piano_sequence.outcome {
+                event_handler.queue_outcome(outcome);
+            }
+            // The history is gone, nothing else can match
+            return;
+        }
     }
-}
\ No newline at end of file
+}
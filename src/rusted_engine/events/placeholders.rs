@@ -0,0 +1,120 @@
+use serde_json::Value;
+
+use super::triggers::Outcome;
+
+pub const SELF_PLACEHOLDER: &str = "$self";
+pub const OTHER_PLACEHOLDER: &str = "$other";
+
+/// Returns a copy of the outcome with "$self" and "$other" replaced in every string argument.
+/// $self is the entity that owns the trigger, $other is the entity it collided with, if there is one.
+pub fn resolve_placeholders(outcome: &Outcome, self_name: &str, other_name: Option<&str>) -> Outcome {
+    let mut value = match serde_json::to_value(outcome) {
+        Ok(value) => value,
+        Err(e) => {
+            println!("Failed to resolve placeholders in {:?}: {}", outcome, e);
+            return outcome.clone();
+        }
+    };
+
+    replace_in_value(&mut value, self_name, other_name);
+
+    match serde_json::from_value(value) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            println!("Failed to resolve placeholders in {:?}: {}", outcome, e);
+            outcome.clone()
+        }
+    }
+}
+
+fn replace_in_value(value: &mut Value, self_name: &str, other_name: Option<&str>) {
+    match value {
+        Value::String(string) => {
+            if string.contains(SELF_PLACEHOLDER) {
+                *string = string.replace(SELF_PLACEHOLDER, self_name);
+            }
+            if string.contains(OTHER_PLACEHOLDER) {
+                match other_name {
+                    Some(other_name) => *string = string.replace(OTHER_PLACEHOLDER, other_name),
+                    None => println!("{} was used by {} where there is no other entity, it is left as is.", OTHER_PLACEHOLDER, self_name),
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values.iter_mut() {
+                replace_in_value(value, self_name, other_name);
+            }
+        }
+        Value::Object(map) => {
+            for value in map.values_mut() {
+                replace_in_value(value, self_name, other_name);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rusted_engine::{events::triggers::{DelayArgs, DestroyObjectArgs, IfArgs, SetVariableArgs, TeleportObjectArgs, TriggerConditions}, game_state::GameVariable};
+
+    #[test]
+    fn placeholders_are_replaced_inside_nested_outcomes() {
+        let outcome = Outcome::Delay(DelayArgs {
+            seconds: 1.0,
+            tag: Some("$self_timer".to_owned()),
+            clear_on_scene_swap: false,
+            outcome: vec![Outcome::If(IfArgs {
+                condition: TriggerConditions::All(Vec::new()),
+                then: vec![Outcome::TeleportObject(TeleportObjectArgs { object_name: "$self".to_owned(), new_position: vec![0.0, 0.0], relative_to: Some("$other".to_owned()) })],
+                otherwise: vec![Outcome::DestroyObject(DestroyObjectArgs { object_name: "$other".to_owned() })],
+            })],
+        });
+
+        let resolved = match resolve_placeholders(&outcome, "player", Some("coin")) {
+            Outcome::Delay(delay_args) => delay_args,
+            other => panic!("unexpected outcome {:?}", other),
+        };
+
+        assert_eq!(resolved.tag.as_deref(), Some("player_timer"));
+        let if_args = match &resolved.outcome[0] {
+            Outcome::If(if_args) => if_args,
+            other => panic!("unexpected outcome {:?}", other),
+        };
+        match &if_args.then[0] {
+            Outcome::TeleportObject(args) => {
+                assert_eq!(args.object_name, "player");
+                assert_eq!(args.relative_to.as_deref(), Some("coin"));
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        match &if_args.otherwise[0] {
+            Outcome::DestroyObject(args) => assert_eq!(args.object_name, "coin"),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[test]
+    fn other_is_left_as_is_when_there_is_no_other_entity() {
+        let outcome = Outcome::DestroyObject(DestroyObjectArgs { object_name: "$other".to_owned() });
+
+        match resolve_placeholders(&outcome, "player", None) {
+            Outcome::DestroyObject(args) => assert_eq!(args.object_name, "$other"),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[test]
+    fn string_values_are_resolved_too() {
+        let outcome = Outcome::SetVariable(SetVariableArgs { variable_name: "last_hit_by_$self".to_owned(), value: GameVariable::String("$other".to_owned()) });
+
+        match resolve_placeholders(&outcome, "player", Some("spike")) {
+            Outcome::SetVariable(args) => {
+                assert_eq!(args.variable_name, "last_hit_by_player");
+                assert_eq!(args.value, GameVariable::String("spike".to_owned()));
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+}
@@ -1,47 +1,120 @@
-use std::sync::{Arc, RwLock};
+use std::{collections::HashSet, sync::{Arc, RwLock}};
 
 use glfw::Key;
 use nalgebra::{Vector2, Vector3};
 use rusted_open::framework::{events::movement, graphics::{internal_object::graphics_object::Generic2DGraphicsObject, util::master_graphics_list::MasterGraphicsList}};
 
-use crate::rusted_engine::{entities::util::master_entity_list::MasterEntityList, input::key_states::KeyStates};
+use crate::rusted_engine::{entities::{generic_entity::GenericEntity, util::master_entity_list::MasterEntityList}, input::key_states::KeyStates, scenes::{fluid_volume::FluidVolume, physics_zone::{resolve_zone_forces, PhysicsZone}}};
 
-use super::triggers::AccelerateObjectArgs;
+use super::triggers::{AccelerateObjectArgs, ApplyImpulseArgs, ApplyTorqueArgs, SetVelocityArgs};
+
+/// Checks with a read lock first so sleeping entities never have to be write locked
+fn is_sleeping(entity: &Arc<RwLock<GenericEntity>>) -> bool {
+    entity.read().map(|entity| entity.is_sleeping()).unwrap_or(false)
+}
 
 /// A more refined movement based on directional velocity.
-pub fn accelerate_object(accelerate_object_args: AccelerateObjectArgs, master_entity_list: &MasterEntityList, delta_time: f32) {
+/// This and the other movement outcomes return why nothing was moved, if nothing was.
+pub fn accelerate_object(accelerate_object_args: AccelerateObjectArgs, master_entity_list: &MasterEntityList, delta_time: f32) -> Result<(), String> {
+    if accelerate_object_args.acceleration.len() != 2 {
+        return Err(format!("invalid acceleration size for {}, expected a 2D acceleration", accelerate_object_args.object_name));
+    }
+
     if let Some(entity) = master_entity_list.get_entity(&accelerate_object_args.object_name) {
         if let Ok(mut entity) = entity.write() {
-            if accelerate_object_args.acceleration.len() == 2 {
-                let mut acceleration_matrix = Vector2::new(accelerate_object_args.acceleration[0], accelerate_object_args.acceleration[1]);
-                // Normalize the acceleration vector to prevent faster diagonal movement
-                if accelerate_object_args.normalize == true {
-                    if acceleration_matrix.magnitude() > 0.0 {
-                        acceleration_matrix = acceleration_matrix.normalize();
-                    }
+            entity.wake();
+            let mut acceleration_matrix = Vector2::new(accelerate_object_args.acceleration[0], accelerate_object_args.acceleration[1]);
+            // Normalize the acceleration vector to prevent faster diagonal movement
+            if accelerate_object_args.normalize == true {
+                if acceleration_matrix.magnitude() > 0.0 {
+                    acceleration_matrix = acceleration_matrix.normalize();
                 }
+            }
 
-                // Apply acceleration to the entity's velocity
-                let new_velocity = entity.get_velocity() + acceleration_matrix * delta_time;
+            // Apply acceleration to the entity's velocity
+            let new_velocity = entity.get_velocity() + acceleration_matrix * delta_time;
 
-                entity.set_velocity(new_velocity);
+            entity.set_velocity(new_velocity);
 
-                let mut velocity = entity.get_velocity();
-                let current_speed = velocity.magnitude();
+            let mut velocity = entity.get_velocity();
+            let current_speed = velocity.magnitude();
 
-                // If the current speed exceeds the max speed, normalize and scale it
-                if current_speed > accelerate_object_args.max_speed {
-                    velocity = velocity.normalize() * accelerate_object_args.max_speed;
-                    entity.set_velocity(velocity); // Set the capped velocity back to the entity
-                }
+            // If the current speed exceeds the max speed, normalize and scale it
+            if current_speed > accelerate_object_args.max_speed {
+                velocity = velocity.normalize() * accelerate_object_args.max_speed;
+                entity.set_velocity(velocity); // Set the capped velocity back to the entity
             }
+            Ok(())
+        }
+        else {
+            Err(format!("couldn't acquire a write lock on {} to accelerate it", accelerate_object_args.object_name))
+        }
+    }
+    else {
+        Err(format!("no entity named {} to accelerate", accelerate_object_args.object_name))
+    }
+}
+
+/// Adds torque to an entity, which is turned into angular velocity during process_movement.
+pub fn apply_torque(apply_torque_args: ApplyTorqueArgs, master_entity_list: &MasterEntityList) -> Result<(), String> {
+    if let Some(entity) = master_entity_list.get_entity(&apply_torque_args.object_name) {
+        if let Ok(mut entity) = entity.write() {
+            entity.wake();
+            entity.apply_torque(apply_torque_args.torque);
+            Ok(())
         }
         else {
-            println!("Couldn't acquire a read lock on that entity. Cannot process object acceleration");
+            Err(format!("couldn't acquire a write lock on {} to apply torque", apply_torque_args.object_name))
         }
     }
     else {
-        println!("No entity with that name could be found. Cannot process object acceleration");
+        Err(format!("no entity named {} to apply torque to", apply_torque_args.object_name))
+    }
+}
+
+pub fn set_velocity(set_velocity_args: SetVelocityArgs, master_entity_list: &MasterEntityList) -> Result<(), String> {
+    if set_velocity_args.velocity.len() != 2 {
+        return Err(format!("invalid velocity size for {}, expected a 2D velocity", set_velocity_args.object_name));
+    }
+
+    if let Some(entity) = master_entity_list.get_entity(&set_velocity_args.object_name) {
+        if let Ok(mut entity) = entity.write() {
+            entity.wake();
+            entity.set_velocity(Vector2::new(set_velocity_args.velocity[0], set_velocity_args.velocity[1]));
+            Ok(())
+        }
+        else {
+            Err(format!("couldn't acquire a write lock on {} to set its velocity", set_velocity_args.object_name))
+        }
+    }
+    else {
+        Err(format!("no entity named {} to set the velocity of", set_velocity_args.object_name))
+    }
+}
+
+pub fn apply_impulse(apply_impulse_args: ApplyImpulseArgs, master_entity_list: &MasterEntityList) -> Result<(), String> {
+    if apply_impulse_args.impulse.len() != 2 {
+        return Err(format!("invalid impulse size for {}, expected a 2D impulse", apply_impulse_args.object_name));
+    }
+
+    if let Some(entity) = master_entity_list.get_entity(&apply_impulse_args.object_name) {
+        if let Ok(mut entity) = entity.write() {
+            // Static entities don't move, and a weightless one would fly off to infinity
+            if entity.is_static() || entity.get_weight() <= 0.0 {
+                return Err(format!("{} is static or weightless, an impulse can't move it", apply_impulse_args.object_name));
+            }
+            entity.wake();
+            let impulse = Vector2::new(apply_impulse_args.impulse[0], apply_impulse_args.impulse[1]);
+            let new_velocity = entity.get_velocity() + impulse / entity.get_weight();
+            entity.set_velocity(new_velocity);
+            Ok(())
+        }
+        else {
+            Err(format!("couldn't acquire a write lock on {} to apply an impulse", apply_impulse_args.object_name))
+        }
+    }
+    else {
+        Err(format!("no entity named {} to apply an impulse to", apply_impulse_args.object_name))
     }
 }
 
@@ -56,12 +129,18 @@ pub fn process_all_entities_fake_friction(velocity_friction: f32, base_friction:
     let entities = entities.read().unwrap();
 
     for (entity_name, entity_ref) in entities.iter() {
+        if is_sleeping(entity_ref) {
+            continue;
+        }
+
         if let Ok(mut entity) = entity_ref.write() {
             let mut velocity = entity.get_velocity();
+            // Slippery materials lose less speed, grippy ones more
+            let friction_multiplier = entity.get_friction();
 
             // Apply friction in the x direction
             if velocity.x != 0.0 {
-                let friction_x = base_friction + velocity_friction * velocity.x.abs();
+                let friction_x = (base_friction + velocity_friction * velocity.x.abs()) * friction_multiplier;
                 velocity.x -= friction_x * velocity.x.signum() * delta_time;
 
                 // Prevent overshooting to the opposite direction
@@ -72,7 +151,7 @@ pub fn process_all_entities_fake_friction(velocity_friction: f32, base_friction:
 
             // Apply vertical friction if enabled
             if vertical_friction && velocity.y != 0.0 {
-                let friction_y = base_friction + velocity_friction * velocity.y.abs();
+                let friction_y = (base_friction + velocity_friction * velocity.y.abs()) * friction_multiplier;
                 velocity.y -= friction_y * velocity.y.signum() * delta_time;
 
                 // Prevent overshooting to the opposite direction
@@ -121,21 +200,106 @@ pub fn process_object_raw_movement(controlled_obj: Arc<RwLock<Generic2DGraphicsO
     }
 }
 
-pub fn gravity(gravity: Vector2<f32>, terminal_velocity: Vector2<f32>, master_entity_list: &MasterEntityList, delta_time: f32) {
+/// Applies gravity, physics zone forces and linear drag to every entity.
+pub fn gravity(gravity: Vector2<f32>, terminal_velocity: Vector2<f32>, physics_zones: &[PhysicsZone], master_entity_list: &MasterEntityList, master_graphics_list: &MasterGraphicsList, delta_time: f32) {
     let entities = master_entity_list.get_entities();
     let entities_read = entities.read().unwrap();
     for entity in entities_read.values() {
+        if is_sleeping(entity) {
+            continue;
+        }
+
         if let Ok(mut entity) = entity.write() {
-            entity.apply_gravity(gravity, terminal_velocity, delta_time);
+            // Only look up the position if there is a zone it could be inside
+            let (entity_gravity, zone_force) = match master_graphics_list.get_object(entity.get_name()) {
+                Some(object) if !physics_zones.is_empty() => {
+                    let position = object.read().unwrap().get_position();
+                    resolve_zone_forces(physics_zones, gravity, Vector2::new(position.x, position.y))
+                }
+                _ => (gravity, Vector2::new(0.0, 0.0)),
+            };
+
+            entity.apply_gravity(entity_gravity, terminal_velocity, delta_time);
+
+            if zone_force != Vector2::new(0.0, 0.0) && !entity.is_static() {
+                let velocity = entity.get_velocity() + zone_force * delta_time;
+                entity.set_velocity(velocity);
+            }
+
+            entity.apply_drag(delta_time);
         }
     }
 }
 
+/// Pushes entities out of fluid volumes in proportion to how much of them is submerged, and applies the fluid drag.
+/// Returns every (entity name, fluid volume name) pair that is currently touching, so enter and exit outcomes can be worked out.
+pub fn buoyancy(gravity: Vector2<f32>, physics_zones: &[PhysicsZone], fluid_volumes: &[FluidVolume], master_entity_list: &MasterEntityList, master_graphics_list: &MasterGraphicsList, delta_time: f32) -> HashSet<(String, String)> {
+    let mut submerged = HashSet::new();
+    if fluid_volumes.is_empty() {
+        return submerged;
+    }
+
+    let entities = master_entity_list.get_entities();
+    let entities_read = entities.read().unwrap();
+    for entity in entities_read.values() {
+        // Sleepers are still counted as submerged, or they would leave the fluid by falling asleep in it
+        if let Ok(mut entity) = entity.write() {
+            let Some(object) = master_graphics_list.get_object(entity.get_name()) else { continue; };
+            let (position, (width, height)) = {
+                let object_read = object.read().unwrap();
+                (object_read.get_position(), object_read.dimensions())
+            };
+            let center = Vector2::new(position.x, position.y);
+            let half_extents = Vector2::new(width / 2.0, height / 2.0);
+            let (local_gravity, _) = resolve_zone_forces(physics_zones, gravity, center);
+
+            for fluid_volume in fluid_volumes {
+                let submerged_fraction = fluid_volume.shape.overlap_fraction(center, half_extents);
+                if submerged_fraction <= 0.0 {
+                    continue;
+                }
+                submerged.insert((entity.get_name().to_owned(), fluid_volume.name.clone()));
+
+                apply_fluid_forces(&mut entity, fluid_volume, local_gravity, submerged_fraction * width * height, submerged_fraction, delta_time);
+            }
+        }
+    }
+
+    submerged
+}
+
+/// Buoyancy and drag from one fluid volume on an entity with submerged_area of it under the surface
+fn apply_fluid_forces(entity: &mut GenericEntity, fluid_volume: &FluidVolume, local_gravity: Vector2<f32>, submerged_area: f32, submerged_fraction: f32, delta_time: f32) {
+    if entity.is_sleeping() || entity.is_static() || entity.get_weight() <= 0.0 {
+        return;
+    }
+
+    // The fluid pushes back against gravity with the weight of the fluid that was displaced, scaled the same way the entity's gravity is.
+    // Without as much gravity pulling the other way that push would carry the entity straight out.
+    let mut velocity = entity.get_velocity();
+    if entity.is_affected_by_gravity() {
+        let displaced_weight = fluid_volume.density * submerged_area;
+        velocity -= local_gravity * entity.get_gravity_scale() * (displaced_weight / entity.get_weight()) * delta_time;
+    }
+
+    // Fluid drag only acts on the submerged part
+    velocity *= (1.0 - fluid_volume.drag * submerged_fraction * delta_time).max(0.0);
+
+    entity.set_velocity(velocity);
+}
+
 pub fn process_movement(master_entity_list: &MasterEntityList, master_graphics_list: &MasterGraphicsList, delta_time: f32) {
     let entities = master_entity_list.get_entities();
     let entities_read = entities.read().unwrap();
     for entity in entities_read.values() {
-        if let Ok(entity) = entity.write() {
+        if is_sleeping(entity) {
+            continue;
+        }
+
+        if let Ok(mut entity) = entity.write() {
+            // Turn any torque applied this frame into angular velocity
+            entity.integrate_torque(delta_time);
+
             // Retrieve the corresponding graphics object
             if let Some(entity_graphics_object) = master_graphics_list.get_object(&entity.get_name()) {
                 if let Ok(mut graphics_object) = entity_graphics_object.write() { // Acquire a write lock on the graphics object
@@ -145,8 +309,96 @@ pub fn process_movement(master_entity_list: &MasterEntityList, master_graphics_l
 
                     // Update the position based on velocity and the passed delta_time
                     movement::move_object(&mut graphics_object, Vector3::new(velocity.x, velocity.y, 0.0), delta_time);
+
+                    // Update the rotation based on angular velocity
+                    let angular_velocity = entity.get_angular_velocity();
+                    if angular_velocity != 0.0 {
+                        movement::rotate_object(&mut graphics_object, angular_velocity * delta_time);
+                    }
                 }
             }
+
+            entity.update_sleep(delta_time);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::rusted_engine::{entities::generic_entity::EntityConfig, scenes::physics_zone::{RectangleShape, ZoneShape}};
+
+    use super::*;
+
+    const GRAVITY: Vector2<f32> = Vector2::new(0.0, -10.0);
+    const TERMINAL_VELOCITY: Vector2<f32> = Vector2::new(100.0, 100.0);
+
+    fn floating_entity(gravity_scale: f32) -> GenericEntity {
+        GenericEntity::new(EntityConfig {
+            name: "crate".to_owned(),
+            weight: 1.0,
+            velocity: Vector2::new(0.0, 0.0),
+            angular_velocity: 0.0,
+            moment_of_inertia: 1.0,
+            affected_by_gravity: true,
+            gravity_scale,
+            linear_drag: 0.0,
+            is_static: false,
+            elasticity: 0.0,
+            friction: 0.0,
+            active_collision: true,
+            collision_priority: 0,
+            collision_modes: HashSet::new(),
+            triggers: Vec::new(),
+            allow_sleep: false,
+            script: None,
+        })
+    }
+
+    // Twice as dense as the unit sized entity, so fully under it is pushed up with twice its weight
+    fn water() -> FluidVolume {
+        FluidVolume {
+            name: "water".to_owned(),
+            shape: ZoneShape::Rectangle(RectangleShape { center: [0.0, 0.0], size: [10.0, 10.0] }),
+            density: 2.0,
+            drag: 0.0,
+            on_enter: Vec::new(),
+            on_exit: Vec::new(),
+        }
+    }
+
+    // A second of frames of gravity followed by buoyancy, the order the engine applies them in
+    fn float_for_a_second(entity: &mut GenericEntity) {
+        let delta_time = 1.0 / 60.0;
+        for _ in 0..60 {
+            entity.apply_gravity(GRAVITY, TERMINAL_VELOCITY, delta_time);
+            apply_fluid_forces(entity, &water(), GRAVITY, 1.0, 1.0, delta_time);
+        }
+    }
+
+    #[test]
+    fn buoyancy_rises_against_the_full_weight() {
+        let mut entity = floating_entity(1.0);
+        float_for_a_second(&mut entity);
+
+        // Pushed up with twice its weight, it rises at one gravity
+        assert!((entity.get_velocity().y - 10.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn buoyancy_is_scaled_with_the_entity_gravity() {
+        let mut entity = floating_entity(0.1);
+        float_for_a_second(&mut entity);
+
+        assert!((entity.get_velocity().y - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn an_entity_without_gravity_is_not_launched_out_of_the_fluid() {
+        let mut entity = floating_entity(0.0);
+        float_for_a_second(&mut entity);
+
+        assert_eq!(entity.get_velocity(), Vector2::new(0.0, 0.0));
+    }
+}
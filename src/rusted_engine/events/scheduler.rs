@@ -0,0 +1,158 @@
+use super::triggers::{DelayArgs, Outcome, RepeatArgs};
+
+struct ScheduledOutcomes {
+    due_time: f32, // Scheduler time at which the outcomes run
+    tag: Option<String>,
+    clear_on_scene_swap: bool,
+    outcomes: Vec<Outcome>,
+}
+
+/// Holds outcomes that were delayed and hands them back once they are due.
+pub struct Scheduler {
+    time: f32, // Game time that has passed since the scheduler was created
+    scheduled: Vec<ScheduledOutcomes>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            time: 0.0,
+            scheduled: Vec::new(),
+        }
+    }
+
+    pub fn schedule(&mut self, delay_args: DelayArgs) {
+        self.scheduled.push(ScheduledOutcomes {
+            due_time: self.time + delay_args.seconds.max(0.0),
+            tag: delay_args.tag,
+            clear_on_scene_swap: delay_args.clear_on_scene_swap,
+            outcomes: delay_args.outcome,
+        });
+    }
+
+    /// Schedules count runs of the outcomes spaced out by the interval. The first run is due right away,
+    /// so its outcomes are returned instead, along with every other run when there is no interval.
+    pub fn schedule_repeats(&mut self, repeat_args: RepeatArgs) -> Vec<Outcome> {
+        let mut due_outcomes = Vec::new();
+        for repetition in 0..repeat_args.count {
+            if repetition == 0 || repeat_args.interval_seconds <= 0.0 {
+                due_outcomes.extend(repeat_args.outcome.iter().cloned());
+            } else {
+                self.schedule(DelayArgs {
+                    seconds: repeat_args.interval_seconds * repetition as f32,
+                    tag: None,
+                    clear_on_scene_swap: true,
+                    outcome: repeat_args.outcome.clone(),
+                });
+            }
+        }
+        due_outcomes
+    }
+
+    /// Advances the clock and returns the outcomes that are now due, earliest first.
+    /// Outcomes that fall due on the same frame keep the order they were scheduled in.
+    pub fn update(&mut self, delta_time: f32) -> Vec<Outcome> {
+        self.time += delta_time;
+
+        let mut due: Vec<ScheduledOutcomes> = Vec::new();
+        let mut index = 0;
+        while index < self.scheduled.len() {
+            if self.scheduled[index].due_time <= self.time {
+                due.push(self.scheduled.remove(index));
+            }
+            else {
+                index += 1;
+            }
+        }
+
+        // Stable sort, so equal due times stay in scheduling order
+        due.sort_by(|a, b| a.due_time.total_cmp(&b.due_time));
+        due.into_iter().flat_map(|scheduled| scheduled.outcomes).collect()
+    }
+
+    /// Drops every scheduled outcome with the given tag
+    pub fn cancel(&mut self, tag: &str) {
+        self.scheduled.retain(|scheduled| scheduled.tag.as_deref() != Some(tag));
+    }
+
+    /// Drops the scheduled outcomes that should not outlive the current scene
+    pub fn clear_for_scene_swap(&mut self) {
+        self.scheduled.retain(|scheduled| !scheduled.clear_on_scene_swap);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rusted_engine::events::triggers::WakeObjectArgs;
+
+    fn wake(object_name: &str) -> Outcome {
+        Outcome::WakeObject(WakeObjectArgs { object_name: object_name.to_owned() })
+    }
+
+    fn woken_names(outcomes: &[Outcome]) -> Vec<String> {
+        outcomes.iter().map(|outcome| match outcome {
+            Outcome::WakeObject(args) => args.object_name.clone(),
+            other => panic!("unexpected outcome {:?}", other),
+        }).collect()
+    }
+
+    fn delay(seconds: f32, object_name: &str, tag: Option<&str>) -> DelayArgs {
+        DelayArgs { seconds, tag: tag.map(str::to_owned), clear_on_scene_swap: false, outcome: vec![wake(object_name)] }
+    }
+
+    #[test]
+    fn outcomes_wait_for_their_delay() {
+        let mut scheduler = Scheduler::new();
+        scheduler.schedule(delay(1.0, "a", None));
+
+        assert!(scheduler.update(0.5).is_empty());
+        assert_eq!(woken_names(&scheduler.update(0.5)), vec!["a"]);
+        assert!(scheduler.update(10.0).is_empty());
+    }
+
+    #[test]
+    fn outcomes_due_on_the_same_frame_come_out_earliest_first() {
+        let mut scheduler = Scheduler::new();
+        scheduler.schedule(delay(0.3, "late", None));
+        scheduler.schedule(delay(0.1, "early", None));
+        scheduler.schedule(delay(0.2, "middle", None));
+        scheduler.schedule(delay(0.1, "early_too", None));
+
+        assert_eq!(woken_names(&scheduler.update(1.0)), vec!["early", "early_too", "middle", "late"]);
+    }
+
+    #[test]
+    fn cancelling_drops_only_the_tagged_outcomes() {
+        let mut scheduler = Scheduler::new();
+        scheduler.schedule(delay(1.0, "a", Some("door")));
+        scheduler.schedule(delay(1.0, "b", None));
+
+        scheduler.cancel("door");
+
+        assert_eq!(woken_names(&scheduler.update(1.0)), vec!["b"]);
+    }
+
+    #[test]
+    fn repeats_run_once_now_and_then_once_per_interval() {
+        let mut scheduler = Scheduler::new();
+
+        let due_now = scheduler.schedule_repeats(RepeatArgs { count: 3, interval_seconds: 1.0, outcome: vec![wake("a")] });
+
+        assert_eq!(woken_names(&due_now), vec!["a"]);
+        assert_eq!(woken_names(&scheduler.update(1.0)), vec!["a"]);
+        assert_eq!(woken_names(&scheduler.update(1.0)), vec!["a"]);
+        assert!(scheduler.update(1.0).is_empty());
+    }
+
+    #[test]
+    fn repeats_without_an_interval_all_run_now() {
+        let mut scheduler = Scheduler::new();
+
+        let due_now = scheduler.schedule_repeats(RepeatArgs { count: 3, interval_seconds: 0.0, outcome: vec![wake("a")] });
+
+        assert_eq!(woken_names(&due_now), vec!["a", "a", "a"]);
+        assert!(scheduler.update(10.0).is_empty());
+        assert!(scheduler.schedule_repeats(RepeatArgs { count: 0, interval_seconds: 0.0, outcome: vec![wake("a")] }).is_empty());
+    }
+}
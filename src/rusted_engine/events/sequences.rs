@@ -0,0 +1,139 @@
+use std::{collections::HashMap, fs::File, io::Read};
+
+use serde::{Deserialize, Serialize};
+
+use super::triggers::Outcome;
+
+/// One step of a named sequence. The outcomes run together once the wait has passed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SequenceStep {
+    #[serde(default)]
+    pub wait_seconds: f32, // Counted from the previous step, or from the start for the first step
+    pub outcome: Vec<Outcome>,
+}
+
+/// Loads a sequences file, a JSON object mapping sequence names to their list of steps
+pub fn load_sequences_from_json(file_path: &str) -> Result<HashMap<String, Vec<SequenceStep>>, Box<dyn std::error::Error>> {
+    let mut file = File::open(file_path)?;
+    let mut data = String::new();
+    file.read_to_string(&mut data)?;
+
+    let sequences: HashMap<String, Vec<SequenceStep>> = serde_json::from_str(&data)?;
+    Ok(sequences)
+}
+
+struct RunningSequence {
+    name: String,
+    steps: Vec<SequenceStep>,
+    next_step: usize,
+    wait_remaining: f32,
+}
+
+/// Keeps track of every sequence that is currently playing and hands out their outcomes as they come due.
+pub struct SequenceRunner {
+    running: Vec<RunningSequence>,
+}
+
+impl SequenceRunner {
+    pub fn new() -> Self {
+        Self {
+            running: Vec::new(),
+        }
+    }
+
+    pub fn start(&mut self, name: &str, steps: Vec<SequenceStep>) {
+        let wait_remaining = steps.first().map(|step| step.wait_seconds).unwrap_or(0.0);
+        self.running.push(RunningSequence {
+            name: name.to_owned(),
+            steps,
+            next_step: 0,
+            wait_remaining,
+        });
+    }
+
+    /// Advances every running sequence and returns the outcomes of all steps that are now due, in order
+    pub fn update(&mut self, delta_time: f32) -> Vec<Outcome> {
+        let mut due_outcomes = Vec::new();
+
+        for sequence in self.running.iter_mut() {
+            sequence.wait_remaining -= delta_time;
+
+            while sequence.next_step < sequence.steps.len() && sequence.wait_remaining <= 0.0 {
+                due_outcomes.extend(sequence.steps[sequence.next_step].outcome.iter().cloned());
+                sequence.next_step += 1;
+
+                if let Some(next_step) = sequence.steps.get(sequence.next_step) {
+                    sequence.wait_remaining += next_step.wait_seconds;
+                }
+            }
+        }
+
+        self.running.retain(|sequence| sequence.next_step < sequence.steps.len());
+        due_outcomes
+    }
+
+    pub fn stop(&mut self, name: &str) {
+        self.running.retain(|sequence| sequence.name != name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rusted_engine::events::triggers::WakeObjectArgs;
+
+    fn step(wait_seconds: f32, object_name: &str) -> SequenceStep {
+        SequenceStep { wait_seconds, outcome: vec![Outcome::WakeObject(WakeObjectArgs { object_name: object_name.to_owned() })] }
+    }
+
+    fn woken_names(outcomes: &[Outcome]) -> Vec<String> {
+        outcomes.iter().map(|outcome| match outcome {
+            Outcome::WakeObject(args) => args.object_name.clone(),
+            other => panic!("unexpected outcome {:?}", other),
+        }).collect()
+    }
+
+    #[test]
+    fn steps_run_in_order_after_their_waits() {
+        let mut runner = SequenceRunner::new();
+        runner.start("intro", vec![step(0.0, "a"), step(1.0, "b"), step(0.5, "c")]);
+
+        assert_eq!(woken_names(&runner.update(0.0)), vec!["a"]);
+        assert!(runner.update(0.75).is_empty());
+        assert_eq!(woken_names(&runner.update(0.25)), vec!["b"]);
+        assert_eq!(woken_names(&runner.update(0.5)), vec!["c"]);
+        assert!(runner.update(10.0).is_empty());
+    }
+
+    #[test]
+    fn a_long_frame_runs_every_step_it_covers() {
+        let mut runner = SequenceRunner::new();
+        runner.start("intro", vec![step(0.5, "a"), step(0.5, "b"), step(5.0, "c")]);
+
+        assert_eq!(woken_names(&runner.update(1.0)), vec!["a", "b"]);
+        // The time left over from the long frame counts towards the next wait
+        assert_eq!(woken_names(&runner.update(5.0)), vec!["c"]);
+    }
+
+    #[test]
+    fn the_same_sequence_can_run_more_than_once_at_a_time() {
+        let mut runner = SequenceRunner::new();
+        runner.start("intro", vec![step(1.0, "a")]);
+        runner.start("intro", vec![step(1.0, "a")]);
+
+        assert_eq!(woken_names(&runner.update(1.0)), vec!["a", "a"]);
+        assert!(runner.update(1.0).is_empty());
+    }
+
+    #[test]
+    fn stopping_drops_the_remaining_steps() {
+        let mut runner = SequenceRunner::new();
+        runner.start("intro", vec![step(0.0, "a"), step(1.0, "b")]);
+        runner.start("other", vec![step(1.0, "c")]);
+
+        assert_eq!(woken_names(&runner.update(0.0)), vec!["a"]);
+        runner.stop("intro");
+
+        assert_eq!(woken_names(&runner.update(1.0)), vec!["c"]);
+    }
+}
@@ -0,0 +1,175 @@
+use std::{collections::{HashMap, VecDeque}, fs::File, io::Write};
+
+use serde::Serialize;
+
+use super::triggers::Outcome;
+
+/// How many entries are kept before the oldest start being dropped
+pub const DEFAULT_TRACE_CAPACITY: usize = 2000;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum TraceKind {
+    TriggerFired,
+    TriggerBlocked, // Disabled, cooling down or out of fires
+    ConditionsFailed,
+    OutcomeQueued,
+    OutcomeApplied,
+    OutcomeIgnored,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TraceEntry {
+    pub frame: u64,
+    pub kind: TraceKind,
+    pub owner: String, // The entity or scene the trigger belongs to, empty for outcomes that don't know where they came from
+    pub detail: String,
+}
+
+/// A bounded record of what the triggers and outcomes did, for working out why a scene misbehaves.
+/// Triggers that are polled every frame (KeyPressed, Timer, Variable) only show up when they fire, anything else also records why it didn't.
+pub struct TraceLog {
+    entries: VecDeque<TraceEntry>,
+    capacity: usize,
+    frame: u64,
+    repeating: HashMap<String, (TraceKind, String, u64)>, // The last kind, detail and frame recorded under each repeat key
+}
+
+impl TraceLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            frame: 0,
+            repeating: HashMap::new(),
+        }
+    }
+
+    /// Called once at the start of every frame
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    pub fn record(&mut self, kind: TraceKind, owner: &str, detail: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(TraceEntry { frame: self.frame, kind, owner: owner.to_owned(), detail });
+    }
+
+    /// For checks that run every frame while something lasts, like a collision trigger during a resting contact.
+    /// Only records when the kind or detail differs from what was recorded under the repeat key last frame, or after a frame without it.
+    pub fn record_repeating(&mut self, repeat_key: &str, kind: TraceKind, owner: &str, detail: String) {
+        let frame = self.frame;
+        let unchanged = self.repeating.get(repeat_key)
+            .is_some_and(|(last_kind, last_detail, last_frame)| *last_kind == kind && *last_detail == detail && frame - last_frame <= 1);
+        self.repeating.insert(repeat_key.to_owned(), (kind.clone(), detail.clone(), frame));
+        if !unchanged {
+            self.record(kind, owner, detail);
+        }
+    }
+
+    pub fn record_outcome(&mut self, kind: TraceKind, owner: &str, outcome: &Outcome) {
+        // Written the way scene files write outcomes, so it can be matched against them
+        let detail = serde_json::to_string(outcome).unwrap_or_else(|_| format!("{:?}", outcome));
+        self.record(kind, owner, detail);
+    }
+
+    /// The entries recorded for one entity or scene and/or during the last few frames, oldest first.
+    /// Leaving a filter out keeps everything it would have dropped.
+    pub fn get_entries_matching(&self, owner: Option<&str>, last_frames: Option<u64>) -> Vec<TraceEntry> {
+        let since_frame = last_frames.map_or(0, |frames| (self.frame + 1).saturating_sub(frames));
+        self.entries.iter()
+            .filter(|entry| entry.frame >= since_frame && owner.is_none_or(|owner| entry.owner == owner))
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.repeating.clear();
+    }
+
+    /// Writes the matching entries, oldest first, to a JSON file
+    pub fn dump_to_json(&self, file_path: &str, owner: Option<&str>, last_frames: Option<u64>) -> Result<(), Box<dyn std::error::Error>> {
+        let data = serde_json::to_string_pretty(&self.get_entries_matching(owner, last_frames))?;
+        let mut file = File::create(file_path)?;
+        file.write_all(data.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeating_entries_are_recorded_once_while_they_last() {
+        let mut trace_log = TraceLog::new(DEFAULT_TRACE_CAPACITY);
+        for _ in 0..5 {
+            trace_log.next_frame();
+            trace_log.record_repeating("player#0 against floor", TraceKind::ConditionsFailed, "player", "player#0 against floor".to_owned());
+        }
+
+        assert_eq!(trace_log.get_entries_matching(None, None).len(), 1);
+    }
+
+    #[test]
+    fn repeating_entries_are_recorded_again_when_they_change_or_come_back() {
+        let mut trace_log = TraceLog::new(DEFAULT_TRACE_CAPACITY);
+        trace_log.next_frame();
+        trace_log.record_repeating("player#0 against floor", TraceKind::ConditionsFailed, "player", "player#0 against floor".to_owned());
+        trace_log.next_frame();
+        trace_log.record_repeating("player#0 against floor", TraceKind::TriggerBlocked, "player", "player#0 is cooling down".to_owned());
+
+        // A frame without the contact, then it starts again
+        trace_log.next_frame();
+        trace_log.next_frame();
+        trace_log.record_repeating("player#0 against floor", TraceKind::TriggerBlocked, "player", "player#0 is cooling down".to_owned());
+
+        let kinds: Vec<TraceKind> = trace_log.get_entries_matching(None, None).iter().map(|entry| entry.kind.clone()).collect();
+        assert_eq!(kinds, vec![TraceKind::ConditionsFailed, TraceKind::TriggerBlocked, TraceKind::TriggerBlocked]);
+    }
+
+    fn details(entries: Vec<TraceEntry>) -> Vec<String> {
+        entries.into_iter().map(|entry| entry.detail).collect()
+    }
+
+    fn three_frames_of_two_owners() -> TraceLog {
+        let mut trace_log = TraceLog::new(DEFAULT_TRACE_CAPACITY);
+        for frame in 1..=3 {
+            trace_log.next_frame();
+            trace_log.record(TraceKind::TriggerFired, "player", format!("player on frame {}", frame));
+            trace_log.record(TraceKind::TriggerFired, "door", format!("door on frame {}", frame));
+        }
+        trace_log
+    }
+
+    #[test]
+    fn entries_can_be_filtered_by_owner() {
+        let trace_log = three_frames_of_two_owners();
+
+        assert_eq!(details(trace_log.get_entries_matching(Some("door"), None)), vec!["door on frame 1", "door on frame 2", "door on frame 3"]);
+        assert!(trace_log.get_entries_matching(Some("nobody"), None).is_empty());
+    }
+
+    #[test]
+    fn entries_can_be_filtered_to_the_last_few_frames() {
+        let trace_log = three_frames_of_two_owners();
+
+        assert_eq!(details(trace_log.get_entries_matching(None, Some(1))), vec!["player on frame 3", "door on frame 3"]);
+        assert_eq!(details(trace_log.get_entries_matching(Some("player"), Some(2))), vec!["player on frame 2", "player on frame 3"]);
+        assert_eq!(trace_log.get_entries_matching(None, Some(10)).len(), 6);
+    }
+
+    #[test]
+    fn clearing_starts_the_log_over() {
+        let mut trace_log = three_frames_of_two_owners();
+        trace_log.clear();
+        trace_log.record(TraceKind::TriggerFired, "player", "after the clear".to_owned());
+
+        assert_eq!(details(trace_log.get_entries_matching(None, None)), vec!["after the clear"]);
+    }
+}
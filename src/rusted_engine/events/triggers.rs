@@ -1,13 +1,24 @@
 use rusted_open::framework::graphics::internal_object::{animation_config::AnimationConfig, atlas_config::AtlasConfig};
-use serde::{Deserialize, Serialize};
+use std::fmt;
 
-use crate::rusted_engine::{audio::audio_manager::AudioType, scenes::scene_manager::ObjectData};
+use serde::{de::{value::MapAccessDeserializer, MapAccess, SeqAccess, Visitor}, Deserialize, Deserializer, Serialize};
+
+use crate::rusted_engine::{audio::audio_manager::AudioType, game_state::{Comparison, GameVariable}, scenes::scene_manager::ObjectData};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Trigger {
     pub trigger_type: TriggerType,  // Trigger type (collision, destruction, etc.)
     pub conditions: Option<TriggerConditions>,  // The conditions are specific to each trigger type
-    pub outcome: Outcome,   // Outcome tells not only which action to take but also the arguments for the action
+    #[serde(deserialize_with = "one_or_many_outcomes")]
+    pub outcome: Vec<Outcome>,   // Outcome tells not only which action to take but also the arguments for the action, a single outcome doesn't need a list
+    #[serde(default)]
+    pub id: Option<String>, // Needed to enable or disable the trigger from an outcome
+    #[serde(default)]
+    pub cooldown_seconds: Option<f32>, // Minimum time between two fires
+    #[serde(default)]
+    pub max_fires: Option<u32>, // Stops firing for good after this many fires
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -15,27 +26,89 @@ pub struct SceneTrigger {
     pub scene_trigger_type: SceneTriggerType,
     pub conditions: Option<TriggerConditions>,
     pub outcome: Vec<Outcome>,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub cooldown_seconds: Option<f32>,
+    #[serde(default)]
+    pub max_fires: Option<u32>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Tells a single outcome from a list by the JSON shape, so a broken outcome reports its own error
+/// instead of serde's "did not match any variant"
+struct OneOrManyOutcomesVisitor;
+
+impl<'de> Visitor<'de> for OneOrManyOutcomesVisitor {
+    type Value = Vec<Outcome>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an outcome or a list of outcomes")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        Ok(vec![Outcome::deserialize(MapAccessDeserializer::new(map))?])
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut outcomes = Vec::new();
+        while let Some(outcome) = seq.next_element()? {
+            outcomes.push(outcome);
+        }
+        Ok(outcomes)
+    }
+}
+
+/// Entity triggers used to take exactly one outcome, this keeps those files working next to the list form
+fn one_or_many_outcomes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Outcome>, D::Error> {
+    deserializer.deserialize_any(OneOrManyOutcomesVisitor)
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum SceneTriggerType {
     KeyPressed,
-    KeyNotPressed,
+    KeyNotPressed, // Key conditions pass while their keys aren't held, every other condition reads as usual
     Timer,
+    Variable, // Fires once each time its variable condition goes from false to true
+    SceneEnter, // Fires once the scene has been loaded, these need no conditions
+    SceneExit, // Fires just before the scene is swapped out, while its objects still exist
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum TriggerType {
     Collision,
     Destruction,
+    OnSpawn, // When the entity is created, either by CreateObject or by its scene loading
+    EnteredArea, // AreaConditions picks the area, without them any area counts
+    LeftArea,
+    SpeedAbove, // Fires once when the speed rises past the SpeedConditions threshold
+    SpeedBelow, // Fires once when the speed drops under the SpeedConditions threshold
+    // Area and speed triggers only fire on a change. The first frame an entity is checked, including after a scene (re)load,
+    // only records where it starts, so spawning inside an area or already above a threshold fires nothing
 }
 
-// Different condition structures for each trigger type
+// Different condition structures for each trigger type, which can be combined with All, Any and Not
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum TriggerConditions {
+    All(Vec<TriggerConditions>),
+    Any(Vec<TriggerConditions>),
+    Not(Box<TriggerConditions>),
     CollisionConditions(CollisionCondition),
     KeyConditions(KeyCondition),
+    ActionConditions(ActionCondition),
+    GamepadButtonConditions(GamepadButtonCondition),
+    GamepadAxisConditions(GamepadAxisCondition),
     TimerConditions(TimerCondition),
+    VariableConditions(VariableCondition),
+    EntityConditions(EntityCondition),
+    AreaConditions(AreaCondition),
+    SpeedConditions(SpeedCondition),
+    QuestConditions(QuestCondition),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -43,21 +116,104 @@ pub struct CollisionCondition {
     pub collided_with: String,
 }
 
+/// Raw keys, prefer ActionConditions so the keys can be rebound
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct KeyCondition {
     pub keys: Vec<char>,
+    #[serde(default)]
+    pub require_all: bool, // By default any one of the keys is enough
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ActionCondition {
+    pub actions: Vec<String>, // Action names from the bindings file
+    #[serde(default)]
+    pub require_all: bool, // By default any one of the actions is enough
+}
+
+/// Raw gamepad buttons, named without glfw's Button prefix ("A", "DpadUp")
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GamepadButtonCondition {
+    pub buttons: Vec<String>,
+    #[serde(default)]
+    pub require_all: bool, // By default any one of the buttons is enough
+}
+
+/// Compares an axis, dead zone applied, against a value. Sticks run from -1.0 to 1.0 with up negative, triggers from 0.0 to 1.0.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GamepadAxisCondition {
+    pub axis: String, // Named without glfw's Axis prefix ("LeftX", "RightTrigger")
+    pub comparison: Comparison,
+    pub value: f32,
 }
 
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TimerCondition {
-    pub time_in_seconds: f32,
+    pub time_in_seconds: f32, // Delay before the first time the timer fires
+    #[serde(default)]
+    pub repeating: bool,
+    #[serde(default)]
+    pub interval_in_seconds: Option<f32>, // Time between repeats, defaults to time_in_seconds
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VariableCondition {
+    pub variable_name: String,
+    pub comparison: Comparison,
+    pub value: GameVariable, // A missing variable never passes the condition
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AreaCondition {
+    pub area_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SpeedCondition {
+    pub speed: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EntityCondition {
+    pub entity_name: String,
+    pub state: EntityState,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum EntityState {
+    Exists,
+    Missing,
+    Sleeping,
+    Awake,
+    Moving,
+    Stationary,
+    AffectedByGravity,
+    Weightless, // Not affected by gravity
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuestCondition {
+    pub quest_name: String,
+    pub state: QuestState,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum QuestState {
+    NotStarted,
+    Active,
+    Completed,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Outcome {
     AccelerateObject(AccelerateObjectArgs),
+    ApplyTorque(ApplyTorqueArgs),
+    WakeObject(WakeObjectArgs),
     Sequence(SequenceArgs),
+    StopSequence(SequenceArgs),
+    Delay(DelayArgs),
+    CancelScheduled(CancelScheduledArgs),
     SwapScene(SwapSceneArgs),
     CreateObject(ObjectData),
     DestroyObject(DestroyObjectArgs),
@@ -67,6 +223,34 @@ pub enum Outcome {
     SetAnimationConfig(SetAnimationConfigArgs),
     SetCameraZoom(SetCameraZoomArgs),
     SetCameraTrackingTarget(SetCameraTrackingTargetArgs),
+    SetVariable(SetVariableArgs),
+    IncrementVariable(IncrementVariableArgs),
+    ToggleFlag(ToggleFlagArgs),
+    SaveGame(SaveGameArgs),
+    LoadGame(LoadGameArgs),
+    EnableTrigger(TriggerIdArgs),
+    DisableTrigger(TriggerIdArgs),
+    If(IfArgs),
+    RandomChoice(RandomChoiceArgs),
+    Repeat(RepeatArgs),
+    SetVelocity(SetVelocityArgs),
+    ApplyImpulse(ApplyImpulseArgs),
+    SetEntityProperty(SetEntityPropertyArgs),
+    SetSceneGravity(SetSceneGravityArgs),
+    SetTerminalVelocity(SetTerminalVelocityArgs),
+    StopAudio(StopAudioArgs),
+    SetRotation(SetRotationArgs),
+    SetScale(SetScaleArgs),
+    Script(ScriptArgs),
+    DumpTraceLog(DumpTraceLogArgs),
+    StartDialogue(StartDialogueArgs),
+    RebindAction(RebindActionArgs),
+    StartQuest(QuestArgs),
+    AdvanceObjective(AdvanceObjectiveArgs),
+    PlayCutscene(CutsceneArgs),
+    PauseCutscene(CutsceneArgs),
+    ResumeCutscene(CutsceneArgs),
+    SkipCutscene(CutsceneArgs),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -77,11 +261,37 @@ pub struct  AccelerateObjectArgs {
     pub normalize: bool,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  ApplyTorqueArgs {
+    pub object_name: String,
+    pub torque: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  WakeObjectArgs {
+    pub object_name: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct  SequenceArgs {
     pub sequence_name: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  DelayArgs {
+    pub seconds: f32,
+    #[serde(default)]
+    pub tag: Option<String>, // Lets a CancelScheduled outcome drop these outcomes before they run
+    #[serde(default)]
+    pub clear_on_scene_swap: bool,
+    pub outcome: Vec<Outcome>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  CancelScheduledArgs {
+    pub tag: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct  SwapSceneArgs {
     pub scene_name: String,
@@ -96,6 +306,8 @@ pub struct  DestroyObjectArgs {
 pub struct  TeleportObjectArgs {
     pub object_name: String,
     pub new_position: Vec<f32>,
+    #[serde(default)]
+    pub relative_to: Option<String>, // new_position is an offset from this object if set
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -126,4 +338,218 @@ pub struct  SetCameraZoomArgs {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct  SetCameraTrackingTargetArgs {
     pub tracking_target: String,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  SetVariableArgs {
+    pub variable_name: String,
+    pub value: GameVariable,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  IncrementVariableArgs {
+    pub variable_name: String,
+    #[serde(default = "default_increment")]
+    pub amount: GameVariable,
+}
+
+fn default_increment() -> GameVariable {
+    GameVariable::Int(1)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  ToggleFlagArgs {
+    pub flag_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  SaveGameArgs {
+    pub save_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  LoadGameArgs {
+    pub save_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  TriggerIdArgs {
+    pub trigger_id: String, // The trigger's id, or "#index" (its position in the owner's list) for a trigger without one
+    pub owners: Vec<String>, // Entity or scene names, the trigger is switched on each of them. Ids are only unique per owner
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  IfArgs {
+    pub condition: TriggerConditions,
+    #[serde(default)]
+    pub then: Vec<Outcome>,
+    #[serde(default, rename = "else")]
+    pub otherwise: Vec<Outcome>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  RandomChoiceArgs {
+    pub choices: Vec<WeightedOutcomes>, // Exactly one of these runs
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  WeightedOutcomes {
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    pub outcome: Vec<Outcome>, // Can be empty, for a chance that nothing happens
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  RepeatArgs {
+    pub count: u32,
+    #[serde(default)]
+    pub interval_seconds: f32, // With no interval every repeat runs this frame
+    pub outcome: Vec<Outcome>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  SetVelocityArgs {
+    pub object_name: String,
+    pub velocity: Vec<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  ApplyImpulseArgs {
+    pub object_name: String,
+    pub impulse: Vec<f32>, // Divided by the entity weight, so heavy things are harder to push
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  SetEntityPropertyArgs {
+    pub object_name: String,
+    pub property: EntityProperty,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum EntityProperty {
+    Weight(f32),
+    Elasticity(f32),
+    Friction(f32),
+    GravityScale(f32),
+    LinearDrag(f32),
+    AffectedByGravity(bool),
+    IsStatic(bool),
+    ActiveCollision(bool),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  SetSceneGravityArgs {
+    pub gravity: Vec<f32>, // Like the scene file, a single value is gravity on the y-axis
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  SetTerminalVelocityArgs {
+    pub terminal_velocity: Vec<f32>, // Like the scene file, a single value is terminal velocity on the y-axis
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  StopAudioArgs {
+    #[serde(default)]
+    pub audio_type: Option<AudioType>, // Stops everything if missing
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  SetRotationArgs {
+    pub object_name: String,
+    pub rotation: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  SetScaleArgs {
+    pub object_name: String,
+    pub scale: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  ScriptArgs {
+    pub script_name: String,
+    #[serde(default)]
+    pub function: Option<String>, // Runs the whole script from the top if missing
+    #[serde(default)]
+    pub args: Vec<String>, // Passed to the function as strings, so $self and $other can be used
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  DumpTraceLogArgs {
+    pub file_name: String, // Written to src\resources\traces\<file_name>.json
+    #[serde(default)]
+    pub owner: Option<String>, // Only the entries of this entity or scene
+    #[serde(default)]
+    pub last_frames: Option<u64>, // Only the entries of the last few frames
+    #[serde(default)]
+    pub clear: bool, // Empties the log after writing it, so the next dump only has what happened in between
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  StartDialogueArgs {
+    pub dialogue_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  CutsceneArgs {
+    pub cutscene_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  QuestArgs {
+    pub quest_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  AdvanceObjectiveArgs {
+    pub quest_name: String,
+    pub objective_id: String,
+    #[serde(default = "default_objective_amount")]
+    pub amount: u32,
+}
+
+fn default_objective_amount() -> u32 {
+    1
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct  RebindActionArgs {
+    pub action_name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger_with_outcome(outcome: &str) -> String {
+        format!(r#"{{ "trigger_type": "Collision", "conditions": null, "outcome": {} }}"#, outcome)
+    }
+
+    #[test]
+    fn a_single_outcome_or_a_list_both_load() {
+        let single: Trigger = serde_json::from_str(&trigger_with_outcome(r#"{ "DestroyObject": { "object_name": "coin" } }"#)).unwrap();
+        let list: Trigger = serde_json::from_str(&trigger_with_outcome(r#"[{ "DestroyObject": { "object_name": "coin" } }, { "WakeObject": { "object_name": "door" } }]"#)).unwrap();
+
+        assert_eq!(single.outcome.len(), 1);
+        assert_eq!(list.outcome.len(), 2);
+        assert!(matches!(&list.outcome[1], Outcome::WakeObject(args) if args.object_name == "door"));
+    }
+
+    #[test]
+    fn a_broken_single_outcome_reports_its_own_error() {
+        let error = serde_json::from_str::<Trigger>(&trigger_with_outcome(r#"{ "TeleportObject": { "object_name": "player" } }"#)).unwrap_err();
+
+        assert!(error.to_string().contains("new_position"), "error was {}", error);
+    }
+
+    #[test]
+    fn a_broken_outcome_in_a_list_reports_its_own_error() {
+        let error = serde_json::from_str::<Trigger>(&trigger_with_outcome(r#"[{ "DestroyObject": { "object_name": "coin" } }, { "Teleport": {} }]"#)).unwrap_err();
+
+        assert!(error.to_string().contains("Teleport"), "error was {}", error);
+    }
+}
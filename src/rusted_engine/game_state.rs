@@ -1,9 +1,91 @@
+use std::{collections::HashMap, fs::File, io::{Read, Write}};
+
 use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+
+use super::{quests::quest::QuestProgress, scenes::{area::Area, fluid_volume::FluidVolume, physics_zone::PhysicsZone}};
+
+/// A value stored in the game state that triggers can set and test.
+/// In JSON it is written as a plain bool, number or string.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum GameVariable {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl GameVariable {
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            GameVariable::Int(value) => Some(*value as f64),
+            GameVariable::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Compares this variable against a value. Ints and floats compare as numbers,
+    /// bools and strings can only be tested for (in)equality.
+    pub fn compare(&self, comparison: &Comparison, other: &GameVariable) -> bool {
+        if let (Some(left), Some(right)) = (self.as_number(), other.as_number()) {
+            return match comparison {
+                Comparison::Equal => left == right,
+                Comparison::NotEqual => left != right,
+                Comparison::Greater => left > right,
+                Comparison::GreaterOrEqual => left >= right,
+                Comparison::Less => left < right,
+                Comparison::LessOrEqual => left <= right,
+            };
+        }
+
+        match comparison {
+            Comparison::Equal => self == other,
+            Comparison::NotEqual => self != other,
+            _ => {
+                println!("Can't compare {:?} and {:?} with {:?}.", self, other, comparison);
+                false
+            }
+        }
+    }
+}
+
+/// The part of the game state that goes into a save file
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+    pub current_scene_name: String,
+    #[serde(default)]
+    pub variables: HashMap<String, GameVariable>,
+    #[serde(default)]
+    pub quests: HashMap<String, QuestProgress>,
+    #[serde(default)]
+    pub gravity_override: Option<[f32; 2]>,
+    #[serde(default)]
+    pub terminal_velocity_override: Option<[f32; 2]>,
+}
 
 pub struct GameState {
     current_scene_name: String,
-    gravity: Vector2<f32>,
+    gravity: Vector2<f32>, // The scene's own gravity
     terminal_velocity: Vector2<f32>,
+    gravity_override: Option<Vector2<f32>>, // Set by SetSceneGravity, the scene's own gravity is back once a scene loads
+    terminal_velocity_override: Option<Vector2<f32>>,
+    physics_zones: Vec<PhysicsZone>,
+    fluid_volumes: Vec<FluidVolume>,
+    areas: Vec<Area>,
+    input_locked: bool, // Set while a cutscene plays, key driven scene triggers are skipped
+    variables: HashMap<String, GameVariable>, // Survives scene swaps, this is how triggers remember things
+    quests: HashMap<String, QuestProgress>, // Progress of every quest that was started, keyed by quest name
 }
 
 impl GameState {
@@ -12,6 +94,14 @@ impl GameState {
             current_scene_name: "".to_owned(),
             gravity: Vector2::new(0.0, 0.0),
             terminal_velocity: Vector2::new(f32::MAX, f32::MAX),
+            gravity_override: None,
+            terminal_velocity_override: None,
+            physics_zones: Vec::new(),
+            fluid_volumes: Vec::new(),
+            areas: Vec::new(),
+            input_locked: false,
+            variables: HashMap::new(),
+            quests: HashMap::new(),
         }
     }
 
@@ -27,8 +117,9 @@ impl GameState {
         self.gravity = gravity;
     }
 
+    /// The override if there is one, otherwise the scene's gravity
     pub fn get_gravity(&self) -> Vector2<f32> {
-        self.gravity
+        self.gravity_override.unwrap_or(self.gravity)
     }
 
     pub fn set_terminal_velocity(&mut self, terminal_velocity: Vector2<f32>) {
@@ -36,6 +127,252 @@ impl GameState {
     }
 
     pub fn get_terminal_velocity(&self) -> Vector2<f32> {
-        self.terminal_velocity
+        self.terminal_velocity_override.unwrap_or(self.terminal_velocity)
+    }
+
+    pub fn set_gravity_override(&mut self, gravity_override: Option<Vector2<f32>>) {
+        self.gravity_override = gravity_override;
+    }
+
+    pub fn set_terminal_velocity_override(&mut self, terminal_velocity_override: Option<Vector2<f32>>) {
+        self.terminal_velocity_override = terminal_velocity_override;
+    }
+
+    /// Goes back to the gravity and terminal velocity of the scene
+    pub fn clear_physics_overrides(&mut self) {
+        self.gravity_override = None;
+        self.terminal_velocity_override = None;
+    }
+
+    pub fn set_physics_zones(&mut self, physics_zones: Vec<PhysicsZone>) {
+        self.physics_zones = physics_zones;
+    }
+
+    pub fn get_physics_zones(&self) -> &Vec<PhysicsZone> {
+        &self.physics_zones
+    }
+
+    pub fn set_fluid_volumes(&mut self, fluid_volumes: Vec<FluidVolume>) {
+        self.fluid_volumes = fluid_volumes;
+    }
+
+    pub fn get_fluid_volumes(&self) -> &Vec<FluidVolume> {
+        &self.fluid_volumes
+    }
+
+    pub fn set_areas(&mut self, areas: Vec<Area>) {
+        self.areas = areas;
+    }
+
+    pub fn get_areas(&self) -> &Vec<Area> {
+        &self.areas
+    }
+
+    pub fn set_input_locked(&mut self, input_locked: bool) {
+        self.input_locked = input_locked;
+    }
+
+    pub fn is_input_locked(&self) -> bool {
+        self.input_locked
+    }
+
+    // variables
+
+    pub fn get_variable(&self, name: &str) -> Option<GameVariable> {
+        self.variables.get(name).cloned()
+    }
+
+    pub fn set_variable(&mut self, name: String, value: GameVariable) {
+        self.variables.insert(name, value);
+    }
+
+    pub fn get_variables(&self) -> &HashMap<String, GameVariable> {
+        &self.variables
+    }
+
+    /// Adds the amount to a number variable. A missing variable starts from the amount.
+    /// An int stays an int only while it is incremented by ints.
+    pub fn increment_variable(&mut self, name: &str, amount: GameVariable) {
+        let new_value = match (self.variables.get(name), &amount) {
+            (None, GameVariable::Int(_)) | (None, GameVariable::Float(_)) => amount.clone(),
+            (Some(GameVariable::Int(value)), GameVariable::Int(amount)) => GameVariable::Int(value + amount),
+            (Some(value), amount) => match (value.as_number(), amount.as_number()) {
+                (Some(value), Some(amount)) => GameVariable::Float(value + amount),
+                _ => {
+                    println!("Can't increment variable {} ({:?}) by {:?}.", name, value, amount);
+                    return;
+                }
+            },
+            (None, amount) => {
+                println!("Can't increment variable {} by {:?}.", name, amount);
+                return;
+            }
+        };
+        self.variables.insert(name.to_owned(), new_value);
+    }
+
+    /// Flips a bool variable. A missing flag counts as false, so the first toggle sets it to true.
+    pub fn toggle_flag(&mut self, name: &str) {
+        let new_value = match self.variables.get(name) {
+            Some(GameVariable::Bool(value)) => !value,
+            None => true,
+            Some(value) => {
+                println!("Can't toggle variable {}, it is not a flag: {:?}", name, value);
+                return;
+            }
+        };
+        self.variables.insert(name.to_owned(), GameVariable::Bool(new_value));
+    }
+
+    // quests
+
+    pub fn get_quest_progress(&self, quest_name: &str) -> Option<&QuestProgress> {
+        self.quests.get(quest_name)
+    }
+
+    pub fn get_quest_progress_mut(&mut self, quest_name: &str) -> Option<&mut QuestProgress> {
+        self.quests.get_mut(quest_name)
+    }
+
+    pub fn set_quest_progress(&mut self, quest_name: String, quest_progress: QuestProgress) {
+        self.quests.insert(quest_name, quest_progress);
+    }
+
+    pub fn get_quests(&self) -> &HashMap<String, QuestProgress> {
+        &self.quests
+    }
+
+    // saving
+
+    pub fn save_to_json(&self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let save_data = SaveData {
+            current_scene_name: self.current_scene_name.clone(),
+            variables: self.variables.clone(),
+            quests: self.quests.clone(),
+            gravity_override: self.gravity_override.map(|gravity| [gravity.x, gravity.y]),
+            terminal_velocity_override: self.terminal_velocity_override.map(|terminal_velocity| [terminal_velocity.x, terminal_velocity.y]),
+        };
+
+        let mut file = File::create(file_path)?;
+        file.write_all(serde_json::to_string_pretty(&save_data)?.as_bytes())?;
+        Ok(())
+    }
+
+    /// Reads a save file and restores the variables and quests from it. The caller is responsible for loading the saved scene,
+    /// then restore_physics_overrides, since loading a scene clears them.
+    pub fn load_from_json(&mut self, file_path: &str) -> Result<SaveData, Box<dyn std::error::Error>> {
+        let mut file = File::open(file_path)?;
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+
+        let save_data: SaveData = serde_json::from_str(&data)?;
+        self.variables = save_data.variables.clone();
+        self.quests = save_data.quests.clone();
+        Ok(save_data)
+    }
+
+    pub fn restore_physics_overrides(&mut self, save_data: &SaveData) {
+        self.gravity_override = save_data.gravity_override.map(Vector2::from);
+        self.terminal_velocity_override = save_data.terminal_velocity_override.map(Vector2::from);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_compare_across_ints_and_floats() {
+        let three = GameVariable::Int(3);
+
+        assert!(three.compare(&Comparison::Equal, &GameVariable::Float(3.0)));
+        assert!(three.compare(&Comparison::NotEqual, &GameVariable::Int(4)));
+        assert!(three.compare(&Comparison::Greater, &GameVariable::Float(2.5)));
+        assert!(three.compare(&Comparison::GreaterOrEqual, &GameVariable::Int(3)));
+        assert!(three.compare(&Comparison::Less, &GameVariable::Int(4)));
+        assert!(three.compare(&Comparison::LessOrEqual, &GameVariable::Float(3.0)));
+        assert!(!three.compare(&Comparison::Less, &GameVariable::Int(3)));
+        assert!(!three.compare(&Comparison::Greater, &GameVariable::Int(3)));
+    }
+
+    #[test]
+    fn bools_and_strings_only_test_equality() {
+        let name = GameVariable::String("gorbino".to_owned());
+
+        assert!(name.compare(&Comparison::Equal, &GameVariable::String("gorbino".to_owned())));
+        assert!(name.compare(&Comparison::NotEqual, &GameVariable::String("homebringer".to_owned())));
+        assert!(!name.compare(&Comparison::Greater, &GameVariable::String("a".to_owned())));
+        assert!(GameVariable::Bool(true).compare(&Comparison::Equal, &GameVariable::Bool(true)));
+        assert!(!GameVariable::Bool(true).compare(&Comparison::LessOrEqual, &GameVariable::Bool(true)));
+    }
+
+    #[test]
+    fn mismatched_types_are_never_equal() {
+        assert!(!GameVariable::Int(1).compare(&Comparison::Equal, &GameVariable::Bool(true)));
+        assert!(GameVariable::Int(1).compare(&Comparison::NotEqual, &GameVariable::Bool(true)));
+        assert!(!GameVariable::String("1".to_owned()).compare(&Comparison::Equal, &GameVariable::Int(1)));
+        assert!(!GameVariable::String("1".to_owned()).compare(&Comparison::Less, &GameVariable::Int(2)));
+    }
+
+    #[test]
+    fn incrementing_keeps_ints_until_a_float_is_added() {
+        let mut game_state = GameState::new();
+
+        game_state.increment_variable("coins", GameVariable::Int(2));
+        game_state.increment_variable("coins", GameVariable::Int(3));
+        assert_eq!(game_state.get_variable("coins"), Some(GameVariable::Int(5)));
+
+        game_state.increment_variable("coins", GameVariable::Float(0.5));
+        assert_eq!(game_state.get_variable("coins"), Some(GameVariable::Float(5.5)));
+    }
+
+    #[test]
+    fn incrementing_or_toggling_the_wrong_type_leaves_the_variable_alone() {
+        let mut game_state = GameState::new();
+        game_state.set_variable("name".to_owned(), GameVariable::String("gorbino".to_owned()));
+
+        game_state.increment_variable("name", GameVariable::Int(1));
+        game_state.toggle_flag("name");
+
+        assert_eq!(game_state.get_variable("name"), Some(GameVariable::String("gorbino".to_owned())));
+    }
+
+    #[test]
+    fn overrides_win_over_the_scene_gravity_until_cleared() {
+        let mut game_state = GameState::new();
+        game_state.set_gravity(Vector2::new(0.0, -3.8));
+
+        game_state.set_gravity_override(Some(Vector2::new(0.0, 1.0)));
+        assert_eq!(game_state.get_gravity(), Vector2::new(0.0, 1.0));
+
+        game_state.clear_physics_overrides();
+        assert_eq!(game_state.get_gravity(), Vector2::new(0.0, -3.8));
+    }
+
+    #[test]
+    fn overrides_survive_a_save_round_trip() {
+        let file_path = std::env::temp_dir().join("rusted_engine_physics_overrides_test.json");
+        let file_path = file_path.to_str().unwrap();
+        let mut game_state = GameState::new();
+        game_state.set_gravity_override(Some(Vector2::new(1.0, 2.0)));
+        game_state.save_to_json(file_path).unwrap();
+
+        let mut loaded = GameState::new();
+        let save_data = loaded.load_from_json(file_path).unwrap();
+        loaded.restore_physics_overrides(&save_data);
+        std::fs::remove_file(file_path).ok();
+
+        assert_eq!(loaded.get_gravity(), Vector2::new(1.0, 2.0));
+        assert_eq!(loaded.get_terminal_velocity(), Vector2::new(f32::MAX, f32::MAX));
+    }
+
+    #[test]
+    fn a_missing_flag_toggles_to_true() {
+        let mut game_state = GameState::new();
+
+        game_state.toggle_flag("door_open");
+        assert_eq!(game_state.get_variable("door_open"), Some(GameVariable::Bool(true)));
+        game_state.toggle_flag("door_open");
+        assert_eq!(game_state.get_variable("door_open"), Some(GameVariable::Bool(false)));
     }
 }
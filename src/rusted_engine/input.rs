@@ -1,2 +1,4 @@
+pub mod action_map;
+pub mod gamepad_states;
 pub mod key_states;
 pub mod piano;
\ No newline at end of file
@@ -0,0 +1,499 @@
+use std::{collections::{HashMap, HashSet}, fs::File, io::{Read, Write}, path::Path};
+
+use glfw::Key;
+use serde::{Deserialize, Serialize};
+
+use crate::rusted_engine::util::{gamepad_names::{axis_name_to_glfw_axis, button_name_to_glfw_button, glfw_button_to_button_name}, key_names::{glfw_key_to_key_name, key_name_to_glfw_key}};
+
+use super::{gamepad_states::GamepadStates, key_states::KeyStates};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ModifierKey {
+    Shift,
+    Control,
+    Alt,
+    Super,
+}
+
+impl ModifierKey {
+    /// Either the left or the right one counts
+    fn is_held(&self, key_states: &KeyStates) -> bool {
+        let (left, right) = match self {
+            ModifierKey::Shift => (Key::LeftShift, Key::RightShift),
+            ModifierKey::Control => (Key::LeftControl, Key::RightControl),
+            ModifierKey::Alt => (Key::LeftAlt, Key::RightAlt),
+            ModifierKey::Super => (Key::LeftSuper, Key::RightSuper),
+        };
+        key_states.is_key_pressed_raw(left) || key_states.is_key_pressed_raw(right)
+    }
+
+    fn from_key(key: Key) -> Option<Self> {
+        match key {
+            Key::LeftShift | Key::RightShift => Some(ModifierKey::Shift),
+            Key::LeftControl | Key::RightControl => Some(ModifierKey::Control),
+            Key::LeftAlt | Key::RightAlt => Some(ModifierKey::Alt),
+            Key::LeftSuper | Key::RightSuper => Some(ModifierKey::Super),
+            _ => None,
+        }
+    }
+}
+
+/// A key, named the way glfw names it ("W", "Kp1", "Space"), plus the modifiers that have to be held with it.
+/// Modifiers that aren't listed may be held or not, unless another binding of the same key lists them,
+/// in which case the binding with more modifiers wins. Ctrl+S then doesn't also count as S, while Shift+S still does if nothing is bound to it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KeyBinding {
+    pub key: String,
+    #[serde(default)]
+    pub modifiers: Vec<ModifierKey>,
+}
+
+impl KeyBinding {
+    fn is_held(&self, key_states: &KeyStates) -> bool {
+        match key_name_to_glfw_key(&self.key) {
+            Some(key) => key_states.is_key_pressed_raw(key) && self.modifiers_held(key_states),
+            None => false,
+        }
+    }
+
+    fn is_pressed(&self, key_states: &KeyStates) -> bool {
+        match key_name_to_glfw_key(&self.key) {
+            Some(key) => key_states.is_key_pressed(key) && self.modifiers_held(key_states),
+            None => false,
+        }
+    }
+
+    fn modifiers_held(&self, key_states: &KeyStates) -> bool {
+        self.modifiers.iter().all(|modifier| modifier.is_held(key_states))
+    }
+
+    /// Whether the other binding is the same key with every modifier of this one and more besides
+    fn is_more_specific(&self, other: &KeyBinding) -> bool {
+        key_name_to_glfw_key(&self.key) == key_name_to_glfw_key(&other.key)
+            && other.modifiers.len() > self.modifiers.len()
+            && self.modifiers.iter().all(|modifier| other.modifiers.contains(modifier))
+    }
+}
+
+/// A gamepad button, named without glfw's Button prefix ("A", "LeftBumper", "DpadUp")
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GamepadButtonBinding {
+    pub button: String,
+}
+
+/// An axis pushed past a threshold, named without glfw's Axis prefix ("LeftX", "RightTrigger").
+/// A negative threshold is crossed going down, so {"axis": "LeftY", "threshold": -0.5} is the left stick pushed up.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GamepadAxisBinding {
+    pub axis: String,
+    pub threshold: f32,
+}
+
+/// Anything that can perform an action. In JSON the fields tell them apart: key, button or axis.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum InputBinding {
+    Key(KeyBinding),
+    GamepadButton(GamepadButtonBinding),
+    GamepadAxis(GamepadAxisBinding),
+}
+
+impl InputBinding {
+    fn is_held(&self, key_states: &KeyStates, gamepad_states: &GamepadStates) -> bool {
+        match self {
+            InputBinding::Key(binding) => binding.is_held(key_states),
+            InputBinding::GamepadButton(binding) => button_name_to_glfw_button(&binding.button).is_some_and(|button| gamepad_states.is_button_pressed_raw(button)),
+            InputBinding::GamepadAxis(binding) => axis_name_to_glfw_axis(&binding.axis).is_some_and(|axis| gamepad_states.is_axis_past(axis, binding.threshold)),
+        }
+    }
+
+    fn is_pressed(&self, key_states: &KeyStates, gamepad_states: &GamepadStates) -> bool {
+        match self {
+            InputBinding::Key(binding) => binding.is_pressed(key_states),
+            InputBinding::GamepadButton(binding) => button_name_to_glfw_button(&binding.button).is_some_and(|button| gamepad_states.is_button_pressed(button)),
+            InputBinding::GamepadAxis(binding) => axis_name_to_glfw_axis(&binding.axis).is_some_and(|axis| gamepad_states.did_axis_cross(axis, binding.threshold)),
+        }
+    }
+
+    /// What's wrong with the binding, if it names something that doesn't exist
+    fn unknown_name(&self) -> Option<String> {
+        match self {
+            InputBinding::Key(binding) if key_name_to_glfw_key(&binding.key).is_none() => Some(format!("unknown key: {}", binding.key)),
+            InputBinding::GamepadButton(binding) if button_name_to_glfw_button(&binding.button).is_none() => Some(format!("unknown gamepad button: {}", binding.button)),
+            InputBinding::GamepadAxis(binding) if axis_name_to_glfw_axis(&binding.axis).is_none() => Some(format!("unknown gamepad axis: {}", binding.axis)),
+            _ => None,
+        }
+    }
+}
+
+/// Maps named actions ("move_left", "piano_note_A") to the keys and gamepad inputs that perform them, so nothing else has to know about either.
+/// Defaults come from a bindings file, anything rebound at runtime is written to a second file that overrides them.
+pub struct ActionMap {
+    actions: HashMap<String, Vec<InputBinding>>,
+    user_bindings_path: Option<String>, // Where rebinds are saved, nothing is saved without one
+    user_actions: HashSet<String>, // Actions the user rebound, the only ones saved to the user file
+    rebinding: Option<String>, // An action waiting for the next key or button press to become its binding
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self {
+            actions: HashMap::new(),
+            user_bindings_path: None,
+            user_actions: HashSet::new(),
+            rebinding: None,
+        }
+    }
+
+    /// Loads a bindings file, a JSON map of action names to their bindings.
+    /// Actions in the file replace the bindings they had before, the rest are kept.
+    pub fn load_bindings_from_json(&mut self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.load_actions_from_json(file_path)?;
+        Ok(())
+    }
+
+    /// Loads a bindings file like load_bindings_from_json, returning the names of the actions it had
+    fn load_actions_from_json(&mut self, file_path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut file = File::open(file_path)?;
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+
+        let actions: HashMap<String, Vec<InputBinding>> = serde_json::from_str(&data)?;
+        let action_names = actions.keys().cloned().collect();
+        for (action_name, bindings) in actions {
+            for problem in bindings.iter().filter_map(InputBinding::unknown_name) {
+                println!("Action {} is bound to an {}", action_name, problem);
+            }
+            self.actions.insert(action_name, bindings);
+        }
+        Ok(action_names)
+    }
+
+    /// Loads the default bindings, then the user's rebinds over them if there are any.
+    /// Later rebinds are saved to the user file.
+    pub fn load_bindings(&mut self, default_bindings_path: &str, user_bindings_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.load_bindings_from_json(default_bindings_path)?;
+        if Path::new(user_bindings_path).exists() {
+            let user_actions = self.load_actions_from_json(user_bindings_path)?;
+            self.user_actions.extend(user_actions);
+        }
+        self.user_bindings_path = Some(user_bindings_path.to_owned());
+        Ok(())
+    }
+
+    /// Saves only the actions the user rebound, so changes to the other defaults still reach them
+    fn save_user_bindings(&self) {
+        if let Some(user_bindings_path) = &self.user_bindings_path {
+            if let Err(e) = self.save_user_bindings_to_json(user_bindings_path) {
+                println!("Failed to save the key bindings to {}: {}", user_bindings_path, e);
+            }
+        }
+    }
+
+    fn save_user_bindings_to_json(&self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let user_actions: HashMap<&String, &Vec<InputBinding>> = self.actions.iter()
+            .filter(|(action_name, _)| self.user_actions.contains(*action_name))
+            .collect();
+        let mut file = File::create(file_path)?;
+        file.write_all(serde_json::to_string_pretty(&user_actions)?.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn get_bindings(&self, action_name: &str) -> Option<&Vec<InputBinding>> {
+        self.actions.get(action_name)
+    }
+
+    pub fn get_action_names(&self) -> HashSet<String> {
+        self.actions.keys().cloned().collect()
+    }
+
+    /// Replaces every binding of an action and saves the change
+    pub fn set_bindings(&mut self, action_name: &str, bindings: Vec<InputBinding>) {
+        self.actions.insert(action_name.to_owned(), bindings);
+        self.user_actions.insert(action_name.to_owned());
+        self.save_user_bindings();
+    }
+
+    /// The next key pressed, with whatever modifiers are held, or the next gamepad button replaces the action's bindings. Escape cancels.
+    /// No action is performed until then.
+    pub fn start_rebind(&mut self, action_name: &str) {
+        println!("Press a key to bind to {}", action_name);
+        self.rebinding = Some(action_name.to_owned());
+    }
+
+    pub fn is_rebinding(&self) -> bool {
+        self.rebinding.is_some()
+    }
+
+    /// Finishes a rebind started by start_rebind once a key other than a modifier, or a gamepad button, is pressed.
+    /// The key or button is consumed, so it doesn't also perform whatever it was bound to before.
+    pub fn process_rebind(&mut self, key_states: &mut KeyStates, gamepad_states: &mut GamepadStates) {
+        let action_name = match &self.rebinding {
+            Some(action_name) => action_name.clone(),
+            None => return,
+        };
+
+        let pressed_keys = key_states.get_pressed_keys();
+        if pressed_keys.contains(&Key::Escape) {
+            println!("Cancelled rebinding {}", action_name);
+            key_states.consume_key(Key::Escape);
+            self.rebinding = None;
+            return;
+        }
+
+        if let Some(key) = pressed_keys.into_iter().find(|key| ModifierKey::from_key(*key).is_none()) {
+            let modifiers = [ModifierKey::Shift, ModifierKey::Control, ModifierKey::Alt, ModifierKey::Super]
+                .into_iter()
+                .filter(|modifier| modifier.is_held(key_states))
+                .collect();
+            key_states.consume_key(key);
+            self.finish_rebind(&action_name, InputBinding::Key(KeyBinding { key: glfw_key_to_key_name(key), modifiers }));
+        }
+        else if let Some(button) = gamepad_states.get_pressed_buttons().into_iter().next() {
+            gamepad_states.consume_button(button);
+            self.finish_rebind(&action_name, InputBinding::GamepadButton(GamepadButtonBinding { button: glfw_button_to_button_name(button) }));
+        }
+    }
+
+    fn finish_rebind(&mut self, action_name: &str, binding: InputBinding) {
+        println!("Bound {} to {:?}", action_name, binding);
+        self.rebinding = None;
+        self.set_bindings(action_name, vec![binding]);
+    }
+
+    /// True if any binding of the action was just pressed this frame
+    pub fn is_action_pressed(&self, action_name: &str, key_states: &KeyStates, gamepad_states: &GamepadStates) -> bool {
+        if self.is_rebinding() {
+            return false;
+        }
+        match self.actions.get(action_name) {
+            Some(bindings) => bindings.iter().any(|binding| binding.is_pressed(key_states, gamepad_states) && !self.is_outranked(binding, key_states)),
+            None => false,
+        }
+    }
+
+    /// True if any binding of the action is held down, including the frame it was pressed
+    pub fn is_action_held(&self, action_name: &str, key_states: &KeyStates, gamepad_states: &GamepadStates) -> bool {
+        if self.is_rebinding() {
+            return false;
+        }
+        match self.actions.get(action_name) {
+            Some(bindings) => bindings.iter().any(|binding| binding.is_held(key_states, gamepad_states) && !self.is_outranked(binding, key_states)),
+            None => false,
+        }
+    }
+
+    /// Whether a key binding gives way to one of any action for the same key that needs more modifiers, all of them held
+    fn is_outranked(&self, binding: &InputBinding, key_states: &KeyStates) -> bool {
+        let binding = match binding {
+            InputBinding::Key(binding) => binding,
+            _ => return false,
+        };
+        self.actions.values().flatten().any(|other| match other {
+            InputBinding::Key(other) => binding.is_more_specific(other) && other.modifiers_held(key_states),
+            _ => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use glfw::{Action, Modifiers, WindowEvent};
+
+    use crate::rusted_engine::util::fixture_dir::FixtureDir;
+
+    use super::*;
+
+    const DEFAULT_BINDINGS: &str = r#"{
+        "jump": [{ "key": "Space" }, { "button": "A" }],
+        "save": [{ "key": "S", "modifiers": ["Control"] }],
+        "move_down": [{ "key": "S" }],
+        "move_up": [{ "axis": "LeftY", "threshold": -0.5 }]
+    }"#;
+
+    fn press(key_states: &mut KeyStates, key: Key) {
+        key_states.handle_key_event(WindowEvent::Key(key, 0, Action::Press, Modifiers::empty()));
+    }
+
+    fn action_map_with_defaults() -> ActionMap {
+        let fixture_dir = FixtureDir::new("defaults");
+        let mut action_map = ActionMap::new();
+        action_map.load_bindings_from_json(&fixture_dir.write("bindings.json", DEFAULT_BINDINGS)).unwrap();
+        action_map
+    }
+
+    #[test]
+    fn bindings_are_told_apart_by_their_fields() {
+        let bindings: Vec<InputBinding> = serde_json::from_str(r#"[
+            { "key": "S", "modifiers": ["Control", "Shift"] },
+            { "key": "W" },
+            { "button": "DpadUp" },
+            { "axis": "RightTrigger", "threshold": 0.5 }
+        ]"#).unwrap();
+
+        match &bindings[0] {
+            InputBinding::Key(binding) => {
+                assert_eq!(binding.key, "S");
+                assert_eq!(binding.modifiers, vec![ModifierKey::Control, ModifierKey::Shift]);
+            }
+            other => panic!("Expected a key binding, got {:?}", other),
+        }
+        assert!(matches!(&bindings[1], InputBinding::Key(binding) if binding.modifiers.is_empty()));
+        assert!(matches!(&bindings[2], InputBinding::GamepadButton(binding) if binding.button == "DpadUp"));
+        assert!(matches!(&bindings[3], InputBinding::GamepadAxis(binding) if binding.axis == "RightTrigger" && binding.threshold == 0.5));
+    }
+
+    #[test]
+    fn unknown_names_are_reported() {
+        let bindings: Vec<InputBinding> = serde_json::from_str(r#"[
+            { "key": "NotAKey" }, { "button": "Z" }, { "axis": "MiddleX", "threshold": 0.5 }, { "key": "W" }
+        ]"#).unwrap();
+
+        let problems: Vec<String> = bindings.iter().filter_map(InputBinding::unknown_name).collect();
+        assert_eq!(problems, vec!["unknown key: NotAKey", "unknown gamepad button: Z", "unknown gamepad axis: MiddleX"]);
+    }
+
+    #[test]
+    fn user_bindings_override_only_the_actions_they_have() {
+        let fixture_dir = FixtureDir::new("override");
+        let default_path = fixture_dir.write("bindings.json", DEFAULT_BINDINGS);
+        let user_path = fixture_dir.write("user_bindings.json", r#"{ "jump": [{ "key": "W" }] }"#);
+
+        let mut action_map = ActionMap::new();
+        action_map.load_bindings(&default_path, &user_path).unwrap();
+
+        assert!(matches!(&action_map.get_bindings("jump").unwrap()[..], [InputBinding::Key(binding)] if binding.key == "W"));
+        assert!(matches!(&action_map.get_bindings("move_down").unwrap()[..], [InputBinding::Key(binding)] if binding.key == "S"));
+        assert_eq!(action_map.get_action_names().len(), 4);
+    }
+
+    #[test]
+    fn only_rebound_actions_are_saved_to_the_user_file() {
+        let fixture_dir = FixtureDir::new("save");
+        let default_path = fixture_dir.write("bindings.json", DEFAULT_BINDINGS);
+        let user_path = fixture_dir.write("user_bindings.json", r#"{ "jump": [{ "key": "W" }] }"#);
+
+        let mut action_map = ActionMap::new();
+        action_map.load_bindings(&default_path, &user_path).unwrap();
+        action_map.set_bindings("move_up", Vec::new());
+
+        let saved: HashMap<String, Vec<InputBinding>> = serde_json::from_str(&fs::read_to_string(&user_path).unwrap()).unwrap();
+        let mut saved_names: Vec<&String> = saved.keys().collect();
+        saved_names.sort();
+        assert_eq!(saved_names, vec!["jump", "move_up"]);
+        assert!(saved["move_up"].is_empty());
+    }
+
+    #[test]
+    fn nothing_is_saved_without_a_user_file() {
+        let fixture_dir = FixtureDir::new("no_user_file");
+        let mut action_map = action_map_with_defaults();
+        action_map.set_bindings("jump", Vec::new());
+
+        assert!(!Path::new(&fixture_dir.file_path("user_bindings.json")).exists());
+    }
+
+    #[test]
+    fn a_binding_with_more_modifiers_wins_over_the_bare_key() {
+        let action_map = action_map_with_defaults();
+        let gamepad_states = GamepadStates::new();
+        let mut key_states = KeyStates::new();
+        press(&mut key_states, Key::LeftControl);
+        press(&mut key_states, Key::S);
+
+        assert!(action_map.is_action_pressed("save", &key_states, &gamepad_states));
+        assert!(!action_map.is_action_pressed("move_down", &key_states, &gamepad_states));
+        assert!(!action_map.is_action_held("move_down", &key_states, &gamepad_states));
+    }
+
+    #[test]
+    fn listed_modifiers_have_to_be_held() {
+        let action_map = action_map_with_defaults();
+        let gamepad_states = GamepadStates::new();
+        let mut key_states = KeyStates::new();
+        press(&mut key_states, Key::S);
+
+        assert!(!action_map.is_action_pressed("save", &key_states, &gamepad_states));
+        assert!(action_map.is_action_pressed("move_down", &key_states, &gamepad_states));
+    }
+
+    #[test]
+    fn unlisted_modifiers_nothing_else_uses_may_be_held() {
+        let action_map = action_map_with_defaults();
+        let gamepad_states = GamepadStates::new();
+        let mut key_states = KeyStates::new();
+        press(&mut key_states, Key::LeftShift);
+        press(&mut key_states, Key::S);
+
+        assert!(action_map.is_action_pressed("move_down", &key_states, &gamepad_states));
+        assert!(!action_map.is_action_pressed("save", &key_states, &gamepad_states));
+    }
+
+    #[test]
+    fn held_keys_count_as_held_but_not_pressed() {
+        let action_map = action_map_with_defaults();
+        let gamepad_states = GamepadStates::new();
+        let mut key_states = KeyStates::new();
+        press(&mut key_states, Key::Space);
+        key_states.update_pressed_to_held();
+
+        assert!(!action_map.is_action_pressed("jump", &key_states, &gamepad_states));
+        assert!(action_map.is_action_held("jump", &key_states, &gamepad_states));
+    }
+
+    #[test]
+    fn rebinding_takes_the_key_with_its_modifiers_and_consumes_it() {
+        let mut action_map = action_map_with_defaults();
+        let mut gamepad_states = GamepadStates::new();
+        let mut key_states = KeyStates::new();
+        action_map.start_rebind("jump");
+
+        // A modifier on its own doesn't finish the rebind
+        press(&mut key_states, Key::LeftAlt);
+        action_map.process_rebind(&mut key_states, &mut gamepad_states);
+        assert!(action_map.is_rebinding());
+
+        press(&mut key_states, Key::S);
+        action_map.process_rebind(&mut key_states, &mut gamepad_states);
+        assert!(!action_map.is_rebinding());
+        match &action_map.get_bindings("jump").unwrap()[..] {
+            [InputBinding::Key(binding)] => {
+                assert_eq!(binding.key, "S");
+                assert_eq!(binding.modifiers, vec![ModifierKey::Alt]);
+            }
+            other => panic!("Expected a single key binding, got {:?}", other),
+        }
+        // The press that finished the rebind doesn't also move down
+        assert!(!key_states.is_key_pressed_raw(Key::S));
+        assert!(!action_map.is_action_held("move_down", &key_states, &gamepad_states));
+    }
+
+    #[test]
+    fn escape_cancels_a_rebind_and_is_consumed() {
+        let mut action_map = action_map_with_defaults();
+        let mut gamepad_states = GamepadStates::new();
+        let mut key_states = KeyStates::new();
+        action_map.start_rebind("jump");
+
+        press(&mut key_states, Key::Escape);
+        action_map.process_rebind(&mut key_states, &mut gamepad_states);
+
+        assert!(!action_map.is_rebinding());
+        assert!(!key_states.is_key_pressed(Key::Escape));
+        assert_eq!(action_map.get_bindings("jump").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn no_action_is_performed_while_a_rebind_waits() {
+        let mut action_map = action_map_with_defaults();
+        let gamepad_states = GamepadStates::new();
+        let mut key_states = KeyStates::new();
+        press(&mut key_states, Key::Space);
+        action_map.start_rebind("move_down");
+
+        assert!(!action_map.is_action_pressed("jump", &key_states, &gamepad_states));
+        assert!(!action_map.is_action_held("jump", &key_states, &gamepad_states));
+    }
+}
@@ -0,0 +1,328 @@
+use glfw::{Action, GamepadAxis, GamepadButton, Glfw, JoystickId};
+use std::collections::HashMap;
+
+use crate::rusted_engine::util::gamepad_names::{GAMEPAD_AXES, GAMEPAD_BUTTONS};
+
+const JOYSTICK_IDS: [JoystickId; 16] = [
+    JoystickId::Joystick1, JoystickId::Joystick2, JoystickId::Joystick3, JoystickId::Joystick4,
+    JoystickId::Joystick5, JoystickId::Joystick6, JoystickId::Joystick7, JoystickId::Joystick8,
+    JoystickId::Joystick9, JoystickId::Joystick10, JoystickId::Joystick11, JoystickId::Joystick12,
+    JoystickId::Joystick13, JoystickId::Joystick14, JoystickId::Joystick15, JoystickId::Joystick16,
+];
+
+// The same states a key can be in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ButtonState {
+    Pressed,
+    Held,
+    Released,
+    Consumed, // Still down, but something used it that doesn't share it
+}
+
+/// Gamepad buttons and axes, polled from glfw once a frame.
+/// Every connected gamepad feeds the same state, so any of them can play.
+pub struct GamepadStates {
+    buttons: HashMap<GamepadButton, ButtonState>,
+    axes: HashMap<GamepadAxis, f32>, // Dead zones already applied, triggers run from 0.0 to 1.0
+    previous_axes: HashMap<GamepadAxis, f32>, // Last frame's axes, to tell when a threshold was just crossed
+    stick_dead_zone: f32, // How far a stick has to move before it counts, as a fraction of its full throw
+    trigger_dead_zone: f32,
+}
+
+impl GamepadStates {
+    pub fn new() -> Self {
+        Self {
+            buttons: HashMap::new(),
+            axes: HashMap::new(),
+            previous_axes: HashMap::new(),
+            stick_dead_zone: 0.2,
+            trigger_dead_zone: 0.1,
+        }
+    }
+
+    pub fn get_stick_dead_zone(&self) -> f32 {
+        self.stick_dead_zone
+    }
+
+    pub fn set_stick_dead_zone(&mut self, stick_dead_zone: f32) {
+        self.stick_dead_zone = stick_dead_zone.clamp(0.0, 0.99);
+    }
+
+    pub fn get_trigger_dead_zone(&self) -> f32 {
+        self.trigger_dead_zone
+    }
+
+    pub fn set_trigger_dead_zone(&mut self, trigger_dead_zone: f32) {
+        self.trigger_dead_zone = trigger_dead_zone.clamp(0.0, 0.99);
+    }
+
+    /// Reads every connected gamepad. Call once a frame after polling events.
+    pub fn update(&mut self, glfw: &Glfw) {
+        let mut buttons_down: Vec<GamepadButton> = Vec::new();
+        let mut raw_axes: HashMap<GamepadAxis, f32> = HashMap::new();
+
+        for joystick_id in JOYSTICK_IDS {
+            let joystick = glfw.get_joystick(joystick_id);
+            if !joystick.is_gamepad() {
+                continue;
+            }
+            let gamepad_state = match joystick.get_gamepad_state() {
+                Some(gamepad_state) => gamepad_state,
+                None => continue,
+            };
+
+            for button in GAMEPAD_BUTTONS {
+                if gamepad_state.get_button_state(button) == Action::Press {
+                    buttons_down.push(button);
+                }
+            }
+            // With more than one gamepad, whichever pushes an axis furthest wins
+            for axis in GAMEPAD_AXES {
+                let value = gamepad_state.get_axis(axis);
+                let value = match axis {
+                    GamepadAxis::AxisLeftTrigger | GamepadAxis::AxisRightTrigger => (value + 1.0) / 2.0, // Triggers rest at -1.0
+                    _ => value,
+                };
+                let strongest = raw_axes.entry(axis).or_insert(0.0);
+                if value.abs() > strongest.abs() {
+                    *strongest = value;
+                }
+            }
+        }
+
+        self.apply_frame(&buttons_down, &raw_axes);
+    }
+
+    /// Moves the buttons on a frame and applies the dead zones to the axes, with triggers already running from 0.0 to 1.0
+    fn apply_frame(&mut self, buttons_down: &[GamepadButton], raw_axes: &HashMap<GamepadAxis, f32>) {
+        for button in GAMEPAD_BUTTONS {
+            let previous = self.buttons.get(&button).copied();
+            let down = buttons_down.contains(&button);
+            match (down, previous) {
+                (true, Some(ButtonState::Pressed)) | (true, Some(ButtonState::Held)) => {
+                    self.buttons.insert(button, ButtonState::Held);
+                }
+                (true, Some(ButtonState::Consumed)) => {}
+                (true, _) => {
+                    self.buttons.insert(button, ButtonState::Pressed);
+                }
+                (false, Some(ButtonState::Pressed)) | (false, Some(ButtonState::Held)) | (false, Some(ButtonState::Consumed)) => {
+                    self.buttons.insert(button, ButtonState::Released);
+                }
+                (false, Some(ButtonState::Released)) => {
+                    self.buttons.remove(&button);
+                }
+                (false, None) => {}
+            }
+        }
+
+        self.previous_axes = std::mem::take(&mut self.axes);
+        self.apply_stick_dead_zone(raw_axes, GamepadAxis::AxisLeftX, GamepadAxis::AxisLeftY);
+        self.apply_stick_dead_zone(raw_axes, GamepadAxis::AxisRightX, GamepadAxis::AxisRightY);
+        for trigger in [GamepadAxis::AxisLeftTrigger, GamepadAxis::AxisRightTrigger] {
+            let value = raw_axes.get(&trigger).copied().unwrap_or(0.0);
+            self.axes.insert(trigger, rescale_past_dead_zone(value, self.trigger_dead_zone));
+        }
+    }
+
+    /// The dead zone is a circle, so a stick pushed diagonally isn't cut off sooner than one pushed straight
+    fn apply_stick_dead_zone(&mut self, raw_axes: &HashMap<GamepadAxis, f32>, x_axis: GamepadAxis, y_axis: GamepadAxis) {
+        let x = raw_axes.get(&x_axis).copied().unwrap_or(0.0);
+        let y = raw_axes.get(&y_axis).copied().unwrap_or(0.0);
+        let length = (x * x + y * y).sqrt();
+        let scale = if length > 0.0 { rescale_past_dead_zone(length, self.stick_dead_zone) / length } else { 0.0 };
+        self.axes.insert(x_axis, x * scale);
+        self.axes.insert(y_axis, y * scale);
+    }
+
+    // Returns true if the button was just pressed (not held from the previous frame)
+    pub fn is_button_pressed(&self, button: GamepadButton) -> bool {
+        matches!(self.buttons.get(&button), Some(ButtonState::Pressed))
+    }
+
+    // Returns true if the button is pressed (either newly pressed or held)
+    pub fn is_button_pressed_raw(&self, button: GamepadButton) -> bool {
+        matches!(self.buttons.get(&button), Some(ButtonState::Pressed) | Some(ButtonState::Held))
+    }
+
+    // Returns true if the button was let go of this frame
+    pub fn is_button_released(&self, button: GamepadButton) -> bool {
+        matches!(self.buttons.get(&button), Some(ButtonState::Released))
+    }
+
+    // Hides a pressed or held button from everything else until it is released and pressed again
+    pub fn consume_button(&mut self, button: GamepadButton) {
+        if self.is_button_pressed_raw(button) {
+            self.buttons.insert(button, ButtonState::Consumed);
+        }
+    }
+
+    // Returns every button that was just pressed this frame
+    pub fn get_pressed_buttons(&self) -> Vec<GamepadButton> {
+        GAMEPAD_BUTTONS.into_iter().filter(|button| self.is_button_pressed(*button)).collect()
+    }
+
+    /// Sticks run from -1.0 to 1.0 (up is negative on the y axes), triggers from 0.0 to 1.0
+    pub fn get_axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    /// Whether the axis is past the threshold, on the side of zero the threshold is on
+    pub fn is_axis_past(&self, axis: GamepadAxis, threshold: f32) -> bool {
+        is_past(self.get_axis(axis), threshold)
+    }
+
+    /// Whether the axis went past the threshold this frame
+    pub fn did_axis_cross(&self, axis: GamepadAxis, threshold: f32) -> bool {
+        let previous = self.previous_axes.get(&axis).copied().unwrap_or(0.0);
+        self.is_axis_past(axis, threshold) && !is_past(previous, threshold)
+    }
+}
+
+fn is_past(value: f32, threshold: f32) -> bool {
+    if threshold < 0.0 {
+        value <= threshold
+    } else {
+        value >= threshold
+    }
+}
+
+/// Zero inside the dead zone, then scaled so the rest of the throw still reaches 1.0
+fn rescale_past_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        return 0.0;
+    }
+    let rescaled = (value.abs() - dead_zone) / (1.0 - dead_zone);
+    rescaled.min(1.0).copysign(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn frame(gamepad_states: &mut GamepadStates, buttons_down: &[GamepadButton], axes: &[(GamepadAxis, f32)]) {
+        gamepad_states.apply_frame(buttons_down, &axes.iter().copied().collect());
+    }
+
+    #[test]
+    fn values_inside_the_dead_zone_are_zero() {
+        assert_eq!(rescale_past_dead_zone(0.2, 0.2), 0.0);
+        assert_eq!(rescale_past_dead_zone(-0.1, 0.2), 0.0);
+        assert_eq!(rescale_past_dead_zone(0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn values_past_the_dead_zone_still_reach_full_throw() {
+        assert!((rescale_past_dead_zone(0.6, 0.2) - 0.5).abs() < EPSILON);
+        assert!((rescale_past_dead_zone(-0.6, 0.2) + 0.5).abs() < EPSILON);
+        assert_eq!(rescale_past_dead_zone(1.0, 0.2), 1.0);
+        assert_eq!(rescale_past_dead_zone(-1.0, 0.2), -1.0);
+        // Some gamepads report a little past full throw
+        assert_eq!(rescale_past_dead_zone(1.05, 0.2), 1.0);
+    }
+
+    #[test]
+    fn the_stick_dead_zone_is_a_circle() {
+        let mut gamepad_states = GamepadStates::new();
+
+        // Each axis alone is past the dead zone of 0.2, but the stick is only pushed 0.18 from the centre
+        frame(&mut gamepad_states, &[], &[(GamepadAxis::AxisLeftX, 0.13), (GamepadAxis::AxisLeftY, 0.13)]);
+        assert_eq!(gamepad_states.get_axis(GamepadAxis::AxisLeftX), 0.0);
+        assert_eq!(gamepad_states.get_axis(GamepadAxis::AxisLeftY), 0.0);
+
+        // Pushed fully along a diagonal, the stick keeps its direction and reaches full length
+        let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+        frame(&mut gamepad_states, &[], &[(GamepadAxis::AxisLeftX, diagonal), (GamepadAxis::AxisLeftY, -diagonal)]);
+        assert!((gamepad_states.get_axis(GamepadAxis::AxisLeftX) - diagonal).abs() < EPSILON);
+        assert!((gamepad_states.get_axis(GamepadAxis::AxisLeftY) + diagonal).abs() < EPSILON);
+    }
+
+    #[test]
+    fn triggers_run_from_zero_to_one_past_their_dead_zone() {
+        let mut gamepad_states = GamepadStates::new();
+
+        frame(&mut gamepad_states, &[], &[(GamepadAxis::AxisLeftTrigger, 0.05), (GamepadAxis::AxisRightTrigger, 1.0)]);
+        assert_eq!(gamepad_states.get_axis(GamepadAxis::AxisLeftTrigger), 0.0);
+        assert_eq!(gamepad_states.get_axis(GamepadAxis::AxisRightTrigger), 1.0);
+
+        frame(&mut gamepad_states, &[], &[(GamepadAxis::AxisLeftTrigger, 0.55)]);
+        assert!((gamepad_states.get_axis(GamepadAxis::AxisLeftTrigger) - 0.5).abs() < EPSILON);
+        // An axis missing from a frame is back at rest
+        assert_eq!(gamepad_states.get_axis(GamepadAxis::AxisRightTrigger), 0.0);
+    }
+
+    #[test]
+    fn thresholds_are_passed_on_their_own_side_of_zero() {
+        assert!(is_past(0.5, 0.5));
+        assert!(is_past(0.9, 0.5));
+        assert!(!is_past(-0.9, 0.5));
+        assert!(is_past(-0.5, -0.5));
+        assert!(is_past(-0.9, -0.5));
+        assert!(!is_past(0.9, -0.5));
+    }
+
+    #[test]
+    fn an_axis_crosses_a_positive_threshold_once_on_the_way_up() {
+        let mut gamepad_states = GamepadStates::new();
+        gamepad_states.set_stick_dead_zone(0.0);
+
+        frame(&mut gamepad_states, &[], &[(GamepadAxis::AxisLeftX, 0.3)]);
+        assert!(!gamepad_states.did_axis_cross(GamepadAxis::AxisLeftX, 0.5));
+        frame(&mut gamepad_states, &[], &[(GamepadAxis::AxisLeftX, 0.7)]);
+        assert!(gamepad_states.did_axis_cross(GamepadAxis::AxisLeftX, 0.5));
+        frame(&mut gamepad_states, &[], &[(GamepadAxis::AxisLeftX, 0.9)]);
+        assert!(!gamepad_states.did_axis_cross(GamepadAxis::AxisLeftX, 0.5));
+        assert!(gamepad_states.is_axis_past(GamepadAxis::AxisLeftX, 0.5));
+    }
+
+    #[test]
+    fn an_axis_crosses_a_negative_threshold_once_on_the_way_down() {
+        let mut gamepad_states = GamepadStates::new();
+        gamepad_states.set_stick_dead_zone(0.0);
+
+        frame(&mut gamepad_states, &[], &[(GamepadAxis::AxisLeftY, -0.7)]);
+        assert!(gamepad_states.did_axis_cross(GamepadAxis::AxisLeftY, -0.5));
+        assert!(!gamepad_states.did_axis_cross(GamepadAxis::AxisLeftY, 0.5));
+        frame(&mut gamepad_states, &[], &[(GamepadAxis::AxisLeftY, -0.8)]);
+        assert!(!gamepad_states.did_axis_cross(GamepadAxis::AxisLeftY, -0.5));
+
+        // Going back up past the threshold and down again crosses it again
+        frame(&mut gamepad_states, &[], &[(GamepadAxis::AxisLeftY, -0.2)]);
+        assert!(!gamepad_states.did_axis_cross(GamepadAxis::AxisLeftY, -0.5));
+        frame(&mut gamepad_states, &[], &[(GamepadAxis::AxisLeftY, -0.6)]);
+        assert!(gamepad_states.did_axis_cross(GamepadAxis::AxisLeftY, -0.5));
+    }
+
+    #[test]
+    fn buttons_go_from_pressed_to_held_to_released() {
+        let mut gamepad_states = GamepadStates::new();
+
+        frame(&mut gamepad_states, &[GamepadButton::ButtonA], &[]);
+        assert!(gamepad_states.is_button_pressed(GamepadButton::ButtonA));
+        assert_eq!(gamepad_states.get_pressed_buttons(), vec![GamepadButton::ButtonA]);
+        frame(&mut gamepad_states, &[GamepadButton::ButtonA], &[]);
+        assert!(!gamepad_states.is_button_pressed(GamepadButton::ButtonA));
+        assert!(gamepad_states.is_button_pressed_raw(GamepadButton::ButtonA));
+        frame(&mut gamepad_states, &[], &[]);
+        assert!(gamepad_states.is_button_released(GamepadButton::ButtonA));
+        frame(&mut gamepad_states, &[], &[]);
+        assert!(!gamepad_states.is_button_released(GamepadButton::ButtonA));
+    }
+
+    #[test]
+    fn a_consumed_button_stays_hidden_until_it_is_pressed_again() {
+        let mut gamepad_states = GamepadStates::new();
+
+        frame(&mut gamepad_states, &[GamepadButton::ButtonA], &[]);
+        gamepad_states.consume_button(GamepadButton::ButtonA);
+        assert!(!gamepad_states.is_button_pressed_raw(GamepadButton::ButtonA));
+        frame(&mut gamepad_states, &[GamepadButton::ButtonA], &[]);
+        assert!(!gamepad_states.is_button_pressed_raw(GamepadButton::ButtonA));
+
+        frame(&mut gamepad_states, &[], &[]);
+        frame(&mut gamepad_states, &[GamepadButton::ButtonA], &[]);
+        assert!(gamepad_states.is_button_pressed(GamepadButton::ButtonA));
+    }
+}
@@ -7,6 +7,7 @@ enum KeyState {
     Pressed,
     Held,
     Released,
+    Consumed, // Still down, but something used it that doesn't share it
 }
 
 pub struct KeyStates {
@@ -59,6 +60,22 @@ impl KeyStates {
         }
     }
 
+    // Returns every key that was just pressed this frame
+    pub fn get_pressed_keys(&self) -> Vec<Key> {
+        self.keys
+            .iter()
+            .filter(|(_, &state)| state == KeyState::Pressed)
+            .map(|(&key, _)| key)
+            .collect()
+    }
+
+    // Hides a pressed or held key from everything else until it is released and pressed again
+    pub fn consume_key(&mut self, key: Key) {
+        if self.is_key_pressed_raw(key) {
+            self.keys.insert(key, KeyState::Consumed);
+        }
+    }
+
     // Returns true if the key is pressed (either newly pressed or held)
     pub fn is_key_pressed_raw(&self, key: Key) -> bool {
         match self.keys.get(&key) {
@@ -1,23 +1,31 @@
 use std::collections::VecDeque;
-use glfw::Key;
+use std::time::Instant;
 use std::sync::{Arc, RwLock};
 use crate::rusted_engine::audio::audio_manager::{AudioManager, AudioType}; 
 use crate::rusted_engine::input::key_states::KeyStates; 
+use crate::rusted_engine::input::action_map::ActionMap;
+use crate::rusted_engine::input::gamepad_states::GamepadStates;
 
 pub struct Piano {
     audio_manager: Arc<RwLock<AudioManager>>,
     key_states: Arc<RwLock<KeyStates>>,
+    gamepad_states: Arc<RwLock<GamepadStates>>,
+    action_map: Arc<RwLock<ActionMap>>,
     octave: i32,
     note_history: VecDeque<String>, // Stores the last 100 played notes
+    note_times: VecDeque<Instant>, // When each note in the history was played, kept in step with note_history
 }
 
 impl Piano {
-    pub fn new(audio_manager: Arc<RwLock<AudioManager>>, key_states: Arc<RwLock<KeyStates>>) -> Self {
+    pub fn new(audio_manager: Arc<RwLock<AudioManager>>, key_states: Arc<RwLock<KeyStates>>, gamepad_states: Arc<RwLock<GamepadStates>>, action_map: Arc<RwLock<ActionMap>>) -> Self {
         Self {
             audio_manager,
             key_states,
+            gamepad_states,
+            action_map,
             octave: 4,
             note_history: VecDeque::with_capacity(100), // Preallocate space for 100 notes
+            note_times: VecDeque::with_capacity(100),
         }
     }
 
@@ -29,18 +37,20 @@ impl Piano {
         {
             let audio_manager_write = self.audio_manager.write().unwrap();
             let key_states_read = self.key_states.read().unwrap();
+            let gamepad_states_read = self.gamepad_states.read().unwrap();
+            let action_map_read = self.action_map.read().unwrap();
             
-            let shift_held = key_states_read.is_key_pressed_raw(Key::LeftShift) || key_states_read.is_key_pressed_raw(Key::RightShift);
+            let flat_held = action_map_read.is_action_held("piano_flat", &key_states_read, &gamepad_states_read);
 
-            let key_to_note = [
-                (Key::Kp1, "A"), (Key::Kp2, "B"), (Key::Kp3, "C"),
-                (Key::Kp4, "D"), (Key::Kp6, "E"),
-                (Key::Kp7, "F"), (Key::Kp8, "G"), (Key::Kp9, "AH"),
+            let action_to_note = [
+                ("piano_note_A", "A"), ("piano_note_B", "B"), ("piano_note_C", "C"),
+                ("piano_note_D", "D"), ("piano_note_E", "E"),
+                ("piano_note_F", "F"), ("piano_note_G", "G"), ("piano_note_AH", "AH"),
             ];
         
-            for &(key, note_base) in key_to_note.iter() {
-                if key_states_read.is_key_pressed(key) {
-                    note = if shift_held {
+            for &(action_name, note_base) in action_to_note.iter() {
+                if action_map_read.is_action_pressed(action_name, &key_states_read, &gamepad_states_read) {
+                    note = if flat_held {
                         self.get_flat_note_for_octave(note_base)
                     } else {
                         self.get_note_for_octave(note_base)
@@ -50,15 +60,15 @@ impl Piano {
                 }
             }
 
-            if key_states_read.is_key_pressed(Key::Kp0) {
+            if action_map_read.is_action_pressed("piano_stop", &key_states_read, &gamepad_states_read) {
                 audio_manager_write.stop_audio();
                 self.print_note_history();
             }
         
-            if key_states_read.is_key_pressed(Key::KpAdd) && self.octave < 7 {
+            if action_map_read.is_action_pressed("piano_octave_up", &key_states_read, &gamepad_states_read) && self.octave < 7 {
                 self.octave += 1;
             }
-            if key_states_read.is_key_pressed(Key::KpSubtract) && self.octave > 0 {
+            if action_map_read.is_action_pressed("piano_octave_down", &key_states_read, &gamepad_states_read) && self.octave > 0 {
                 self.octave -= 1;
             }
         }
@@ -73,8 +83,10 @@ impl Piano {
     fn record_note(&mut self, note: &str) {
         if self.note_history.len() >= 100 {
             self.note_history.pop_front(); // Remove the oldest note if history exceeds 100
+            self.note_times.pop_front();
         }
         self.note_history.push_back(note.to_string());
+        self.note_times.push_back(Instant::now());
     }
 
     pub fn get_note_history(&self) -> Vec<String> {
@@ -89,14 +101,32 @@ impl Piano {
         }
     }
 
-    /// Checks if the note history contains a specific sequence of notes
-    pub fn check_for_sequence_and_clear(&mut self, sequence: &[&str]) -> bool {
+    /// Checks if the note history contains a specific sequence of notes.
+    /// With a tempo tolerance, no two neighbouring notes of the match may be further apart than that many seconds.
+    pub fn check_for_sequence_and_clear(&mut self, sequence: &[String], tempo_tolerance: Option<f32>) -> bool {
+        if sequence.is_empty() || sequence.len() > self.note_history.len() {
+            return false;
+        }
+
         let history: Vec<String> = self.note_history.iter().cloned().collect();
+        let times: Vec<Instant> = self.note_times.iter().cloned().collect();
 
         // Check if the history contains the sequence
-        for window in history.windows(sequence.len()) {
-            if window.iter().map(|s| s.as_str()).eq(sequence.iter().map(|s| *s)) {
+        for (start, window) in history.windows(sequence.len()).enumerate() {
+            if !window.iter().eq(sequence.iter()) {
+                continue;
+            }
+
+            let in_tempo = match tempo_tolerance {
+                Some(tolerance) => times[start..start + sequence.len()]
+                    .windows(2)
+                    .all(|pair| pair[1].duration_since(pair[0]).as_secs_f32() <= tolerance),
+                None => true,
+            };
+
+            if in_tempo {
                 self.note_history.clear(); // Clear history after finding the sequence
+                self.note_times.clear();
                 return true;
             }
         }
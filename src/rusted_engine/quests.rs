@@ -0,0 +1,2 @@
+pub mod quest;
+pub mod quest_tracker;
\ No newline at end of file
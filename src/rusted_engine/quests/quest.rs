@@ -0,0 +1,98 @@
+use std::{collections::{HashMap, HashSet}, fs::File, io::Read};
+
+use serde::{Deserialize, Serialize};
+
+use crate::rusted_engine::events::triggers::Outcome;
+
+/// A quest is done once every one of its objectives is. Objectives all count at the same time, in no particular order.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Quest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub auto_start: bool, // Active from the start of the game instead of waiting for a StartQuest outcome
+    pub objectives: Vec<Objective>,
+    #[serde(default)]
+    pub on_complete: Vec<Outcome>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Objective {
+    pub id: String,
+    #[serde(default)]
+    pub description: String,
+    pub goal: ObjectiveGoal,
+    #[serde(default = "default_count")]
+    pub count: u32, // How many times the goal has to happen
+    #[serde(default)]
+    pub on_complete: Vec<Outcome>,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+/// What an objective counts. Entity names match exactly or as a prefix followed by an underscore, so "slime" counts "slime_2".
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ObjectiveGoal {
+    DestroyEntities { entity_name: String },
+    EnterScene { scene_name: String },
+    PlayPianoSequence { sequence_name: String },
+    CollectItems { item_name: String, #[serde(default = "default_collector")] collector: String }, // Counted the first time the collector touches each item
+    Manual, // Only moved on by AdvanceObjective outcomes
+}
+
+fn default_collector() -> String {
+    "player".to_owned()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum QuestStatus {
+    Active,
+    Completed,
+}
+
+/// How far along a started quest is. This is what goes into the save file, the quest itself comes from its JSON.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuestProgress {
+    pub status: QuestStatus,
+    pub objectives: HashMap<String, u32>, // Count so far, keyed by objective id
+    #[serde(default)]
+    pub collected_items: HashMap<String, HashSet<String>>, // The items each CollectItems objective has counted, as "scene:entity"
+}
+
+impl QuestProgress {
+    pub fn new() -> Self {
+        Self {
+            status: QuestStatus::Active,
+            objectives: HashMap::new(),
+            collected_items: HashMap::new(),
+        }
+    }
+
+    pub fn get_objective_count(&self, objective_id: &str) -> u32 {
+        self.objectives.get(objective_id).copied().unwrap_or(0)
+    }
+
+    /// Remembers an item counted towards an objective, false if it was counted before
+    pub fn record_collected_item(&mut self, objective_id: &str, item_key: String) -> bool {
+        self.collected_items.entry(objective_id.to_owned()).or_default().insert(item_key)
+    }
+}
+
+/// Whether an entity name counts towards an objective about the given name
+pub fn entity_name_matches(entity_name: &str, objective_name: &str) -> bool {
+    entity_name == objective_name
+        || entity_name.strip_prefix(objective_name).is_some_and(|rest| rest.starts_with('_'))
+}
+
+/// Loads a quests file, a JSON list of quests
+pub fn load_quests_from_json(file_path: &str) -> Result<Vec<Quest>, Box<dyn std::error::Error>> {
+    let mut file = File::open(file_path)?;
+    let mut data = String::new();
+    file.read_to_string(&mut data)?;
+
+    let quests: Vec<Quest> = serde_json::from_str(&data)?;
+    Ok(quests)
+}
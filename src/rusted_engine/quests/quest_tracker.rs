@@ -0,0 +1,278 @@
+use std::{collections::{HashMap, HashSet}, fs};
+
+use crate::rusted_engine::{events::{event_bus::EngineEvent, triggers::Outcome}, game_state::GameState};
+
+use super::quest::{entity_name_matches, load_quests_from_json, ObjectiveGoal, Quest, QuestProgress, QuestStatus};
+
+/// Knows every quest and moves their objectives on as engine events come in.
+/// Progress is kept in the GameState so it is saved and loaded with everything else.
+pub struct QuestTracker {
+    quests: HashMap<String, Quest>,
+}
+
+impl QuestTracker {
+    pub fn new() -> Self {
+        Self {
+            quests: HashMap::new(),
+        }
+    }
+
+    pub fn add_quest(&mut self, quest: Quest) {
+        self.quests.insert(quest.name.clone(), quest);
+    }
+
+    /// Loads every quest JSON file in the directory
+    pub fn load_quests_from_directory(&mut self, dir_path: &str) -> Result<(), String> {
+        let paths = fs::read_dir(dir_path).map_err(|_| "Failed to read directory".to_string())?;
+
+        for path in paths {
+            let entry = path.map_err(|_| "Failed to read directory entry".to_string())?;
+            let full_path = entry.path();
+
+            if full_path.is_file() && full_path.extension().is_some_and(|extension| extension == "json") {
+                let quests = load_quests_from_json(full_path.to_str().unwrap())
+                    .map_err(|e| format!("Error loading quests '{}': {}", full_path.display(), e))?;
+                for quest in quests {
+                    self.add_quest(quest);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_quest(&self, quest_name: &str) -> Option<&Quest> {
+        self.quests.get(quest_name)
+    }
+
+    pub fn get_quest_names(&self) -> HashSet<String> {
+        self.quests.keys().cloned().collect()
+    }
+
+    /// Starts every auto_start quest that the game state doesn't know about yet
+    pub fn start_auto_quests(&self, game_state: &mut GameState) {
+        for quest in self.quests.values().filter(|quest| quest.auto_start) {
+            if game_state.get_quest_progress(&quest.name).is_none() {
+                game_state.set_quest_progress(quest.name.clone(), QuestProgress::new());
+            }
+        }
+    }
+
+    /// Starts a quest. One that was already started, finished or not, is left alone.
+    pub fn start_quest(&self, quest_name: &str, game_state: &mut GameState) {
+        if !self.quests.contains_key(quest_name) {
+            println!("No quest found for quest name: {}", quest_name);
+            return;
+        }
+        if game_state.get_quest_progress(quest_name).is_none() {
+            println!("Started quest {}", quest_name);
+            game_state.set_quest_progress(quest_name.to_owned(), QuestProgress::new());
+        }
+    }
+
+    /// Counts an event towards every active objective it fits, returns the outcomes of whatever got completed
+    pub fn handle_event(&self, event: &EngineEvent, game_state: &mut GameState) -> Vec<Outcome> {
+        let mut outcomes = Vec::new();
+        for quest in self.quests.values() {
+            for objective in quest.objectives.iter() {
+                let counts = match (&objective.goal, event) {
+                    (ObjectiveGoal::DestroyEntities { entity_name }, EngineEvent::EntityDestroyed { entity_name: destroyed }) => entity_name_matches(destroyed, entity_name),
+                    (ObjectiveGoal::EnterScene { scene_name }, EngineEvent::SceneLoaded { scene_name: loaded }) => scene_name == loaded,
+                    (ObjectiveGoal::PlayPianoSequence { sequence_name }, EngineEvent::PianoSequencePlayed { sequence_name: played }) => sequence_name == played,
+                    (ObjectiveGoal::CollectItems { item_name, collector }, EngineEvent::CollisionStarted { entity_1, entity_2 }) => {
+                        let item = if entity_1 == collector { entity_2 } else if entity_2 == collector { entity_1 } else { continue; };
+                        // Bumping into the same item again, or after a reload, doesn't count it twice
+                        entity_name_matches(item, item_name) && record_collected_item(&quest.name, &objective.id, item, game_state)
+                    }
+                    _ => false,
+                };
+                if counts {
+                    outcomes.extend(self.advance_objective(&quest.name, &objective.id, 1, game_state));
+                }
+            }
+        }
+        outcomes
+    }
+
+    /// Moves an objective of an active quest on. Returns the objective's on_complete outcomes if this finished it,
+    /// followed by the quest's if that was the last objective.
+    pub fn advance_objective(&self, quest_name: &str, objective_id: &str, amount: u32, game_state: &mut GameState) -> Vec<Outcome> {
+        let quest = match self.quests.get(quest_name) {
+            Some(quest) => quest,
+            None => {
+                println!("No quest found for quest name: {}", quest_name);
+                return Vec::new();
+            }
+        };
+        let objective = match quest.objectives.iter().find(|objective| objective.id == objective_id) {
+            Some(objective) => objective,
+            None => {
+                println!("Quest {} has no objective {}", quest_name, objective_id);
+                return Vec::new();
+            }
+        };
+        let progress = match game_state.get_quest_progress_mut(quest_name) {
+            Some(progress) if progress.status == QuestStatus::Active => progress,
+            _ => return Vec::new(),
+        };
+
+        let previous_count = progress.get_objective_count(objective_id);
+        if previous_count >= objective.count {
+            return Vec::new();
+        }
+        let count = (previous_count + amount).min(objective.count);
+        progress.objectives.insert(objective_id.to_owned(), count);
+
+        let mut outcomes = Vec::new();
+        if count >= objective.count {
+            println!("Completed objective {} of quest {}", objective_id, quest_name);
+            outcomes.extend(objective.on_complete.iter().cloned());
+
+            let quest_done = quest.objectives.iter().all(|objective| progress.get_objective_count(&objective.id) >= objective.count);
+            if quest_done {
+                println!("Completed quest {}", quest_name);
+                progress.status = QuestStatus::Completed;
+                outcomes.extend(quest.on_complete.iter().cloned());
+            }
+        }
+        outcomes
+    }
+}
+
+/// Remembers an item for an objective of an active quest. False if it was already counted, or the quest isn't active.
+/// Items are told apart by scene as well, entity names are only unique within one.
+fn record_collected_item(quest_name: &str, objective_id: &str, item_name: &str, game_state: &mut GameState) -> bool {
+    let item_key = format!("{}:{}", game_state.get_current_scene_name(), item_name);
+    match game_state.get_quest_progress_mut(quest_name) {
+        Some(progress) if progress.status == QuestStatus::Active => progress.record_collected_item(objective_id, item_key),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A quest to collect two coins and enter the courtyard
+    fn tracker() -> QuestTracker {
+        let quest: Quest = serde_json::from_str(r#"{
+            "name": "errands",
+            "objectives": [
+                {
+                    "id": "coins",
+                    "goal": { "CollectItems": { "item_name": "coin" } },
+                    "count": 2,
+                    "on_complete": [{ "SetVariable": { "variable_name": "coins_done", "value": true } }]
+                },
+                { "id": "courtyard", "goal": { "EnterScene": { "scene_name": "courtyard" } } }
+            ],
+            "on_complete": [{ "SetVariable": { "variable_name": "errands_done", "value": true } }]
+        }"#).unwrap();
+
+        let mut quest_tracker = QuestTracker::new();
+        quest_tracker.add_quest(quest);
+        quest_tracker
+    }
+
+    fn started_game_state(quest_tracker: &QuestTracker) -> GameState {
+        let mut game_state = GameState::new();
+        game_state.set_current_scene_name("testscene".to_owned());
+        quest_tracker.start_quest("errands", &mut game_state);
+        game_state
+    }
+
+    fn touch(entity_1: &str, entity_2: &str) -> EngineEvent {
+        EngineEvent::CollisionStarted { entity_1: entity_1.to_owned(), entity_2: entity_2.to_owned() }
+    }
+
+    fn coin_count(game_state: &GameState) -> u32 {
+        game_state.get_quest_progress("errands").unwrap().get_objective_count("coins")
+    }
+
+    #[test]
+    fn an_objective_completes_when_its_count_is_reached() {
+        let quest_tracker = tracker();
+        let mut game_state = started_game_state(&quest_tracker);
+
+        assert!(quest_tracker.handle_event(&touch("player", "coin_1"), &mut game_state).is_empty());
+        let outcomes = quest_tracker.handle_event(&touch("coin_2", "player"), &mut game_state);
+
+        assert_eq!(coin_count(&game_state), 2);
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(game_state.get_quest_progress("errands").unwrap().status, QuestStatus::Active);
+    }
+
+    #[test]
+    fn the_quest_completes_with_its_last_objective() {
+        let quest_tracker = tracker();
+        let mut game_state = started_game_state(&quest_tracker);
+        quest_tracker.advance_objective("errands", "coins", 2, &mut game_state);
+
+        let outcomes = quest_tracker.handle_event(&EngineEvent::SceneLoaded { scene_name: "courtyard".to_owned() }, &mut game_state);
+
+        // Only the quest's on_complete, the courtyard objective has none of its own
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(game_state.get_quest_progress("errands").unwrap().status, QuestStatus::Completed);
+        // Nothing moves once the quest is done
+        assert!(quest_tracker.advance_objective("errands", "courtyard", 1, &mut game_state).is_empty());
+    }
+
+    #[test]
+    fn an_item_is_counted_once_however_often_it_is_touched() {
+        let quest_tracker = tracker();
+        let mut game_state = started_game_state(&quest_tracker);
+
+        quest_tracker.handle_event(&touch("player", "coin_1"), &mut game_state);
+        quest_tracker.handle_event(&touch("player", "coin_1"), &mut game_state);
+        quest_tracker.handle_event(&touch("coin_1", "player"), &mut game_state);
+        assert_eq!(coin_count(&game_state), 1);
+
+        // The same name in another scene is another coin
+        game_state.set_current_scene_name("courtyard".to_owned());
+        quest_tracker.handle_event(&touch("player", "coin_1"), &mut game_state);
+        assert_eq!(coin_count(&game_state), 2);
+    }
+
+    #[test]
+    fn only_the_collector_touching_an_item_counts() {
+        let quest_tracker = tracker();
+        let mut game_state = started_game_state(&quest_tracker);
+
+        quest_tracker.handle_event(&touch("slime", "coin_1"), &mut game_state);
+        quest_tracker.handle_event(&touch("player", "coinpurse"), &mut game_state);
+
+        assert_eq!(coin_count(&game_state), 0);
+    }
+
+    #[test]
+    fn quests_that_were_not_started_do_not_count() {
+        let quest_tracker = tracker();
+        let mut game_state = GameState::new();
+
+        quest_tracker.handle_event(&touch("player", "coin_1"), &mut game_state);
+
+        assert!(game_state.get_quest_progress("errands").is_none());
+    }
+
+    #[test]
+    fn progress_and_collected_items_survive_a_save_round_trip() {
+        let file_path = std::env::temp_dir().join("rusted_engine_quest_progress_test.json");
+        let file_path = file_path.to_str().unwrap();
+        let quest_tracker = tracker();
+        let mut game_state = started_game_state(&quest_tracker);
+        quest_tracker.handle_event(&touch("player", "coin_1"), &mut game_state);
+        game_state.save_to_json(file_path).unwrap();
+
+        let mut loaded = GameState::new();
+        loaded.load_from_json(file_path).unwrap();
+        std::fs::remove_file(file_path).ok();
+        loaded.set_current_scene_name("testscene".to_owned());
+
+        assert_eq!(coin_count(&loaded), 1);
+        // The coin collected before saving is still known
+        quest_tracker.handle_event(&touch("player", "coin_1"), &mut loaded);
+        assert_eq!(coin_count(&loaded), 1);
+        quest_tracker.handle_event(&touch("player", "coin_2"), &mut loaded);
+        assert_eq!(coin_count(&loaded), 2);
+    }
+}
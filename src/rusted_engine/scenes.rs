@@ -1,3 +1,7 @@
 pub mod scene;
 pub mod scene_manager;
-pub mod scene_properties;
\ No newline at end of file
+pub mod scene_properties;
+pub mod scene_validator;
+pub mod physics_zone;
+pub mod fluid_volume;
+pub mod area;
\ No newline at end of file
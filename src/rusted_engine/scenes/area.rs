@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+use super::physics_zone::ZoneShape;
+
+/// A named region of a scene. It does nothing by itself, entities react to it through EnteredArea and LeftArea triggers.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Area {
+    pub name: String,
+    pub shape: ZoneShape,
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+use crate::rusted_engine::events::triggers::Outcome;
+
+use super::physics_zone::ZoneShape;
+
+/// A body of fluid. Entities inside it are pushed up by buoyancy and slowed down by the fluid's drag.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FluidVolume {
+    pub name: String,
+    pub shape: ZoneShape,
+    pub density: f32, // Weight of one unit of area of the fluid, compared against the entity weight
+    #[serde(default)]
+    pub drag: f32,
+    #[serde(default)]
+    pub on_enter: Vec<Outcome>, // Queued when an entity starts touching the fluid, $self is the entity and $other the fluid volume
+    #[serde(default)]
+    pub on_exit: Vec<Outcome>, // Queued when an entity stops touching the fluid, with the same placeholders
+}
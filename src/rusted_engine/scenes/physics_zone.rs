@@ -0,0 +1,107 @@
+use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+
+/// A region of a scene that changes how gravity and other forces act on the entities inside it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhysicsZone {
+    pub name: String,
+    pub shape: ZoneShape,
+    #[serde(default)]
+    pub gravity: Option<[f32; 2]>, // Replaces the scene gravity while inside the zone
+    #[serde(default)]
+    pub force: Option<[f32; 2]>, // Constant acceleration on top of gravity, for wind, updrafts and currents
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ZoneShape {
+    Rectangle(RectangleShape),
+    Circle(CircleShape),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RectangleShape {
+    pub center: [f32; 2],
+    pub size: [f32; 2],
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CircleShape {
+    pub center: [f32; 2],
+    pub radius: f32,
+}
+
+impl ZoneShape {
+    pub fn contains(&self, point: Vector2<f32>) -> bool {
+        match self {
+            ZoneShape::Rectangle(rectangle) => {
+                (point.x - rectangle.center[0]).abs() <= rectangle.size[0] / 2.0 &&
+                (point.y - rectangle.center[1]).abs() <= rectangle.size[1] / 2.0
+            }
+            ZoneShape::Circle(circle) => {
+                let dx = point.x - circle.center[0];
+                let dy = point.y - circle.center[1];
+                dx * dx + dy * dy <= circle.radius * circle.radius
+            }
+        }
+    }
+
+    /// Fraction (0 to 1) of an axis-aligned box that lies inside the shape.
+    /// Exact for rectangles, circles are sampled on a grid which is close enough for buoyancy.
+    pub fn overlap_fraction(&self, center: Vector2<f32>, half_extents: Vector2<f32>) -> f32 {
+        if half_extents.x <= 0.0 || half_extents.y <= 0.0 {
+            return if self.contains(center) { 1.0 } else { 0.0 };
+        }
+
+        match self {
+            ZoneShape::Rectangle(rectangle) => {
+                let overlap_x = (center.x + half_extents.x).min(rectangle.center[0] + rectangle.size[0] / 2.0) - (center.x - half_extents.x).max(rectangle.center[0] - rectangle.size[0] / 2.0);
+                let overlap_y = (center.y + half_extents.y).min(rectangle.center[1] + rectangle.size[1] / 2.0) - (center.y - half_extents.y).max(rectangle.center[1] - rectangle.size[1] / 2.0);
+
+                if overlap_x <= 0.0 || overlap_y <= 0.0 {
+                    return 0.0;
+                }
+                (overlap_x * overlap_y) / (4.0 * half_extents.x * half_extents.y)
+            }
+            ZoneShape::Circle(_) => {
+                let samples_per_axis = 6;
+                let mut inside = 0;
+                for i in 0..samples_per_axis {
+                    for j in 0..samples_per_axis {
+                        // Sample the middle of each grid cell
+                        let offset_x = ((i as f32 + 0.5) / samples_per_axis as f32) * 2.0 - 1.0;
+                        let offset_y = ((j as f32 + 0.5) / samples_per_axis as f32) * 2.0 - 1.0;
+                        let point = center + Vector2::new(offset_x * half_extents.x, offset_y * half_extents.y);
+                        if self.contains(point) {
+                            inside += 1;
+                        }
+                    }
+                }
+                inside as f32 / (samples_per_axis * samples_per_axis) as f32
+            }
+        }
+    }
+}
+
+impl PhysicsZone {
+    pub fn contains(&self, point: Vector2<f32>) -> bool {
+        self.shape.contains(point)
+    }
+}
+
+/// Works out the gravity and extra force for a point, given the scene gravity and every zone in the scene.
+/// Where zones overlap the last gravity override wins and forces are added together.
+pub fn resolve_zone_forces(zones: &[PhysicsZone], scene_gravity: Vector2<f32>, point: Vector2<f32>) -> (Vector2<f32>, Vector2<f32>) {
+    let mut gravity = scene_gravity;
+    let mut force = Vector2::new(0.0, 0.0);
+
+    for zone in zones.iter().filter(|zone| zone.contains(point)) {
+        if let Some(zone_gravity) = zone.gravity {
+            gravity = Vector2::new(zone_gravity[0], zone_gravity[1]);
+        }
+        if let Some(zone_force) = zone.force {
+            force += Vector2::new(zone_force[0], zone_force[1]);
+        }
+    }
+
+    (gravity, force)
+}
@@ -1,26 +1,30 @@
-use std::sync::{Arc, RwLock};
+use std::{collections::HashMap, sync::{Arc, RwLock}};
 
 use nalgebra::Vector2;
 use rusted_open::framework::graphics::internal_object::graphics_object::Generic2DGraphicsObject;
 
-use crate::rusted_engine::{entities::generic_entity::GenericEntity, events::triggers::SceneTrigger};
+use crate::rusted_engine::{entities::generic_entity::GenericEntity, events::{piano_sequences::PianoSequence, sequences::SequenceStep, triggers::SceneTrigger}};
 
-use super::scene_properties::SceneProperties;
+use super::{area::Area, fluid_volume::FluidVolume, physics_zone::PhysicsZone, scene_properties::SceneProperties};
 
 pub struct Scene {
     entities: Vec<Arc<RwLock<GenericEntity>>>,
     graphics_objects: Vec<Arc<RwLock<Generic2DGraphicsObject>>>,
     properties: SceneProperties,
     scene_triggers: Vec<SceneTrigger>, // Empty vector if no triggers in the scene
+    sequences: HashMap<String, Vec<SequenceStep>>, // Sequences only this scene can run, these take priority over the global ones
+    piano_sequences: Vec<PianoSequence>, // Checked before the global piano sequences
 }
 
 impl Scene {
-    pub fn new(properties: SceneProperties, scene_triggers: Vec<SceneTrigger>) -> Self {
+    pub fn new(properties: SceneProperties, scene_triggers: Vec<SceneTrigger>, sequences: HashMap<String, Vec<SequenceStep>>, piano_sequences: Vec<PianoSequence>) -> Self {
         Scene {
             entities: Vec::new(),
             graphics_objects: Vec::new(),
             properties,
             scene_triggers,
+            sequences,
+            piano_sequences,
         }
     }
 
@@ -66,6 +70,30 @@ impl Scene {
         self.properties.get_terminal_velocity()
     }
 
+    pub fn set_physics_zones(&mut self, physics_zones: Vec<PhysicsZone>) {
+        self.properties.set_physics_zones(physics_zones);
+    }
+
+    pub fn get_physics_zones(&self) -> Vec<PhysicsZone> {
+        self.properties.get_physics_zones().clone()
+    }
+
+    pub fn set_fluid_volumes(&mut self, fluid_volumes: Vec<FluidVolume>) {
+        self.properties.set_fluid_volumes(fluid_volumes);
+    }
+
+    pub fn get_fluid_volumes(&self) -> Vec<FluidVolume> {
+        self.properties.get_fluid_volumes().clone()
+    }
+
+    pub fn set_areas(&mut self, areas: Vec<Area>) {
+        self.properties.set_areas(areas);
+    }
+
+    pub fn get_areas(&self) -> Vec<Area> {
+        self.properties.get_areas().clone()
+    }
+
     // triggers
 
     pub fn get_triggers(&self) -> Vec<SceneTrigger> {
@@ -75,4 +103,28 @@ impl Scene {
     pub fn set_triggers(&mut self, scene_triggers: Vec<SceneTrigger>) {
         self.scene_triggers = scene_triggers;
     }
+
+    // sequences
+
+    pub fn get_sequence(&self, name: &str) -> Option<Vec<SequenceStep>> {
+        self.sequences.get(name).cloned()
+    }
+
+    pub fn get_sequences(&self) -> HashMap<String, Vec<SequenceStep>> {
+        self.sequences.clone()
+    }
+
+    pub fn set_sequences(&mut self, sequences: HashMap<String, Vec<SequenceStep>>) {
+        self.sequences = sequences;
+    }
+
+    // piano sequences
+
+    pub fn get_piano_sequences(&self) -> Vec<PianoSequence> {
+        self.piano_sequences.clone()
+    }
+
+    pub fn set_piano_sequences(&mut self, piano_sequences: Vec<PianoSequence>) {
+        self.piano_sequences = piano_sequences;
+    }
 }
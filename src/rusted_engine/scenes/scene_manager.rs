@@ -5,18 +5,62 @@ use rusted_open::framework::graphics::{internal_object::{animation_config::Anima
 use serde::{Deserialize, Serialize};
 use std::io::{self, Read};
 
-use crate::rusted_engine::{entities::{generic_entity::{CollisionMode, GenericEntity}, util::master_entity_list::MasterEntityList}, events::triggers::{SceneTrigger, Trigger}, game_state::GameState};
+use crate::rusted_engine::{entities::{generic_entity::{rectangle_moment_of_inertia, CollisionMode, EntityConfig, GenericEntity}, physics_material::{self, PhysicsMaterial}, util::master_entity_list::MasterEntityList}, events::{piano_sequences::{self, PianoSequence}, sequences::{self, SequenceStep}, triggers::{SceneTrigger, Trigger}}, game_state::GameState};
 
-use super::{scene::Scene, scene_properties::SceneProperties};
+use super::{area::Area, fluid_volume::FluidVolume, physics_zone::PhysicsZone, scene::Scene, scene_properties::SceneProperties};
 
 pub struct SceneManager {
     scenes: HashMap<String, Arc<RwLock<Scene>>>,
+    materials: HashMap<String, PhysicsMaterial>,
+    sequences: HashMap<String, Vec<SequenceStep>>, // Sequences any scene can run
+    piano_sequences: Vec<PianoSequence>, // Piano sequences any scene can match
 }
 
 impl SceneManager {
     pub fn new() -> Self {
         Self {
             scenes: HashMap::new(),
+            materials: HashMap::new(),
+            sequences: HashMap::new(),
+            piano_sequences: Vec::new(),
+        }
+    }
+
+    /// Loads the physics materials that entities can refer to by name. Has to happen before any scenes are loaded.
+    pub fn load_materials_from_json(&mut self, file_path: &str) -> Result<(), String> {
+        let materials = physics_material::load_materials_from_json(file_path).map_err(|e| format!("Error loading materials '{}': {}", file_path, e))?;
+        self.materials.extend(materials);
+        Ok(())
+    }
+
+    pub fn get_material_names(&self) -> HashSet<String> {
+        self.materials.keys().cloned().collect()
+    }
+
+    pub fn get_material(&self, name: &str) -> Option<&PhysicsMaterial> {
+        self.materials.get(name)
+    }
+
+    /// Works out the weight, elasticity and friction of an entity.
+    /// Values given on the entity win, then its material, then the defaults. Weight can also come from the density and the area of the object.
+    pub fn resolve_physical_properties(&self, entity_data: &EntityData, area: f32) -> ResolvedPhysicalProperties {
+        let material = match &entity_data.material {
+            Some(material_name) => {
+                let material = self.get_material(material_name);
+                if material.is_none() {
+                    println!("Entity \"{}\" uses unknown material \"{}\", falling back to defaults.", entity_data.name, material_name);
+                }
+                material
+            }
+            None => None,
+        };
+
+        let density = entity_data.density.or(material.map(|material| material.density));
+
+        ResolvedPhysicalProperties {
+            weight: entity_data.weight.or(density.map(|density| density * area)).unwrap_or(1.0),
+            elasticity: entity_data.elasticity.or(material.map(|material| material.restitution)).unwrap_or(1.0),
+            friction: entity_data.friction.or(material.map(|material| material.friction)).unwrap_or(1.0),
         }
     }
 
@@ -40,11 +84,80 @@ impl SceneManager {
         self.scenes.keys().cloned().collect()
     }
 
+    /// Loads every sequences JSON file in the directory. Later files replace sequences of the same name.
+    pub fn load_sequences_from_directory(&mut self, dir_path: &str) -> Result<(), String> {
+        let paths = fs::read_dir(dir_path).map_err(|_| "Failed to read directory".to_string())?;
+
+        for path in paths {
+            let entry = path.map_err(|_| "Failed to read directory entry".to_string())?;
+            let full_path = entry.path();
+
+            if full_path.is_file() && full_path.extension().is_some_and(|extension| extension == "json") {
+                let file_sequences = sequences::load_sequences_from_json(full_path.to_str().unwrap())
+                    .map_err(|e| format!("Error loading sequences '{}': {}", full_path.display(), e))?;
+                self.sequences.extend(file_sequences);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The names of the sequences any scene can run
+    pub fn get_sequence_names(&self) -> HashSet<String> {
+        self.sequences.keys().cloned().collect()
+    }
+
+    /// Finds a sequence by name, looking in the given scene before the global sequences
+    pub fn get_sequence(&self, scene_name: &str, sequence_name: &str) -> Option<Vec<SequenceStep>> {
+        if let Some(scene) = self.get_scene(scene_name) {
+            if let Some(sequence) = scene.read().unwrap().get_sequence(sequence_name) {
+                return Some(sequence);
+            }
+        }
+        self.sequences.get(sequence_name).cloned()
+    }
+
+    /// Loads every piano sequences JSON file in the directory
+    pub fn load_piano_sequences_from_directory(&mut self, dir_path: &str) -> Result<(), String> {
+        let paths = fs::read_dir(dir_path).map_err(|_| "Failed to read directory".to_string())?;
+
+        for path in paths {
+            let entry = path.map_err(|_| "Failed to read directory entry".to_string())?;
+            let full_path = entry.path();
+
+            if full_path.is_file() && full_path.extension().is_some_and(|extension| extension == "json") {
+                let file_piano_sequences = piano_sequences::load_piano_sequences_from_json(full_path.to_str().unwrap())
+                    .map_err(|e| format!("Error loading piano sequences '{}': {}", full_path.display(), e))?;
+                self.piano_sequences.extend(file_piano_sequences);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// All piano sequences that can be played in the given scene, the scene's own first.
+    /// A scene piano sequence hides any global one with the same name.
+    pub fn get_piano_sequences(&self, scene_name: &str) -> Vec<PianoSequence> {
+        let mut piano_sequences = match self.get_scene(scene_name) {
+            Some(scene) => scene.read().unwrap().get_piano_sequences(),
+            None => Vec::new(),
+        };
+
+        for global_sequence in self.piano_sequences.iter() {
+            if !piano_sequences.iter().any(|scene_sequence| scene_sequence.name == global_sequence.name) {
+                piano_sequences.push(global_sequence.clone());
+            }
+        }
+        piano_sequences
+    }
+
     /// Saves a scene, overwriting the existing scene in the map if the name is already used.
     pub fn save_scene(&mut self, scene_name: &str, master_entity_list: &MasterEntityList, master_graphics_list: &MasterGraphicsList) {        
         let properties = self.get_scene(scene_name).unwrap().read().unwrap().get_properties();
         let scene_triggers = self.get_scene(scene_name).unwrap().read().unwrap().get_triggers();
-        let mut new_scene = Scene::new(properties, scene_triggers);
+        let scene_sequences = self.get_scene(scene_name).unwrap().read().unwrap().get_sequences();
+        let scene_piano_sequences = self.get_scene(scene_name).unwrap().read().unwrap().get_piano_sequences();
+        let mut new_scene = Scene::new(properties, scene_triggers, scene_sequences, scene_piano_sequences);
 
         let entities_map = master_entity_list.get_entities();
         let entities_map_read = entities_map.read().unwrap();
@@ -78,6 +191,10 @@ impl SceneManager {
             if let Ok(scene) = scene.read() {
                 game_state.set_gravity(scene.get_gravity());
                 game_state.set_terminal_velocity(scene.get_terminal_velocity());
+                game_state.clear_physics_overrides();
+                game_state.set_physics_zones(scene.get_physics_zones());
+                game_state.set_fluid_volumes(scene.get_fluid_volumes());
+                game_state.set_areas(scene.get_areas());
             }
             else {
                 println!("Could not get a read lock on Scene with name: {}", scene_name);
@@ -152,11 +269,11 @@ impl SceneManager {
             _ => Vector2::new(f32::MAX, f32::MAX), // Default to (0.0, 0.0) if invalid
         };
 
-        let scene_properties = SceneProperties::new(gravity, terminal_velocity);
+        let scene_properties = SceneProperties::new(gravity, terminal_velocity, scene_data.properties.physics_zones, scene_data.properties.fluid_volumes, scene_data.properties.areas);
 
         let scene_triggers = scene_data.scene_triggers;
 
-        let mut json_scene = Scene::new(scene_properties, scene_triggers);
+        let mut json_scene = Scene::new(scene_properties, scene_triggers, scene_data.sequences, scene_data.piano_sequences);
     
         for obj_data in scene_data.objects {
             let json_shader = CustomShader::new(
@@ -182,7 +299,7 @@ impl SceneManager {
             });
     
             let mut json_collision_modes = HashSet::new();
-            for collision_mode in obj_data.entity.collision_modes {
+            for collision_mode in obj_data.entity.collision_modes.iter() {
                 match collision_mode.as_str() {
                     "AABB" => { json_collision_modes.insert(CollisionMode::AABB); }
                     "Circle" => { json_collision_modes.insert(CollisionMode::Circle); }
@@ -212,26 +329,42 @@ impl SceneManager {
                 animation_config,
             );
 
-            let velocity = obj_data.entity.velocity.unwrap_or_else(|| vec![0.0, 0.0]);
+            let velocity = obj_data.entity.velocity.clone().unwrap_or_else(|| vec![0.0, 0.0]);
             let velocity_vector = Vector2::new(velocity[0], velocity[1]);
 
+            let angular_velocity = obj_data.entity.angular_velocity.unwrap_or(0.0);
+
+            // Materials are resolved here so the entity only ever sees the final values
+            let (width, height) = graphics_object.dimensions();
+            let physical_properties = self.resolve_physical_properties(&obj_data.entity, width * height);
+
+            // Default to a solid rectangle the size of the graphics object
+            let moment_of_inertia = obj_data.entity.moment_of_inertia.unwrap_or_else(|| rectangle_moment_of_inertia(physical_properties.weight, width, height));
+
             // Default collision_priority to 0 if None
             let collision_priority = obj_data.entity.collision_priority.unwrap_or(0);
 
             let triggers = obj_data.entity.triggers.unwrap_or_default();
     
-            let entity = GenericEntity::new(
-                obj_data.entity.name.clone(),
-                obj_data.entity.weight,
-                velocity_vector,
-                obj_data.entity.affected_by_gravity,
-                obj_data.entity.is_static,
-                obj_data.entity.elasticity,
-                obj_data.entity.active_collision,
+            let entity = GenericEntity::new(EntityConfig {
+                name: obj_data.entity.name.clone(),
+                weight: physical_properties.weight,
+                velocity: velocity_vector,
+                angular_velocity,
+                moment_of_inertia,
+                affected_by_gravity: obj_data.entity.affected_by_gravity,
+                gravity_scale: obj_data.entity.gravity_scale.unwrap_or(1.0),
+                linear_drag: obj_data.entity.linear_drag.unwrap_or(0.0),
+                is_static: obj_data.entity.is_static,
+                elasticity: physical_properties.elasticity,
+                friction: physical_properties.friction,
+                active_collision: obj_data.entity.active_collision,
                 collision_priority,
-                json_collision_modes,
+                collision_modes: json_collision_modes,
                 triggers,
-            );
+                allow_sleep: obj_data.entity.allow_sleep.unwrap_or(false),
+                script: obj_data.entity.script.clone(),
+            });
     
             let wrapped_graphics_object = Arc::new(RwLock::new(graphics_object));
             let wrapped_entity = Arc::new(RwLock::new(entity));
@@ -280,16 +413,43 @@ impl SceneManager {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct EntityData {
     pub name: String,
-    pub weight: f32,
+    #[serde(default)]
+    pub material: Option<String>,
+    #[serde(default)]
+    pub weight: Option<f32>,
+    #[serde(default)]
+    pub density: Option<f32>,
     pub velocity: Option<Vec<f32>>,
+    #[serde(default)]
+    pub angular_velocity: Option<f32>,
+    #[serde(default)]
+    pub moment_of_inertia: Option<f32>,
+    #[serde(default)]
+    pub gravity_scale: Option<f32>,
+    #[serde(default)]
+    pub linear_drag: Option<f32>,
     pub affected_by_gravity: bool,
     pub is_static: bool,
-    pub elasticity: f32,
+    #[serde(default)]
+    pub elasticity: Option<f32>,
+    #[serde(default)]
+    pub friction: Option<f32>,
     pub active_collision: bool,
     #[serde(default)]
     pub collision_priority: Option<u64>,
     pub collision_modes: Vec<String>,
     pub triggers: Option<Vec<Trigger>>,
+    #[serde(default)]
+    pub allow_sleep: Option<bool>, // Opt in, only for bodies nothing but physics moves. Defaults to false
+    #[serde(default)]
+    pub script: Option<String>, // File name of a script in the scripts directory, without the .rhai extension
+}
+
+/// The physical values of an entity after its material and overrides have been applied
+pub struct ResolvedPhysicalProperties {
+    pub weight: f32,
+    pub elasticity: f32,
+    pub friction: f32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -313,6 +473,8 @@ pub struct GraphicsData {
 pub struct ObjectData {
     pub entity: EntityData,
     pub graphics: GraphicsData,
+    #[serde(default)]
+    pub relative_to: Option<String>, // Only used by CreateObject, the graphics position is then an offset from this object
 }
 
 #[derive(Deserialize)]
@@ -321,10 +483,20 @@ struct SceneData {
     properties: ScenePropertiesDeserialize,
     #[serde(default)]
     scene_triggers: Vec<SceneTrigger>,
+    #[serde(default)]
+    sequences: HashMap<String, Vec<SequenceStep>>,
+    #[serde(default)]
+    piano_sequences: Vec<PianoSequence>,
 }
 
 #[derive(Deserialize)]
 struct ScenePropertiesDeserialize {
     gravity: Vec<f32>,
     terminal_velocity: Vec<f32>,
+    #[serde(default)]
+    physics_zones: Vec<PhysicsZone>,
+    #[serde(default)]
+    fluid_volumes: Vec<FluidVolume>,
+    #[serde(default)]
+    areas: Vec<Area>,
 }
\ No newline at end of file
@@ -1,16 +1,24 @@
 use nalgebra::Vector2;
 
+use super::{area::Area, fluid_volume::FluidVolume, physics_zone::PhysicsZone};
+
 #[derive(Clone)]
 pub struct SceneProperties {
     gravity: Vector2<f32>,
     terminal_velocity: Vector2<f32>,
+    physics_zones: Vec<PhysicsZone>,
+    fluid_volumes: Vec<FluidVolume>,
+    areas: Vec<Area>,
 }
 
 impl SceneProperties {
-    pub fn new(gravity: Vector2<f32>, terminal_velocity: Vector2<f32>) -> Self {
+    pub fn new(gravity: Vector2<f32>, terminal_velocity: Vector2<f32>, physics_zones: Vec<PhysicsZone>, fluid_volumes: Vec<FluidVolume>, areas: Vec<Area>) -> Self {
         SceneProperties {
             gravity,
             terminal_velocity,
+            physics_zones,
+            fluid_volumes,
+            areas,
         }
     }
 
@@ -29,4 +37,28 @@ impl SceneProperties {
     pub fn get_terminal_velocity(&self) -> Vector2<f32> {
         self.terminal_velocity
     }
+
+    pub fn set_physics_zones(&mut self, physics_zones: Vec<PhysicsZone>) {
+        self.physics_zones = physics_zones;
+    }
+
+    pub fn get_physics_zones(&self) -> &Vec<PhysicsZone> {
+        &self.physics_zones
+    }
+
+    pub fn set_fluid_volumes(&mut self, fluid_volumes: Vec<FluidVolume>) {
+        self.fluid_volumes = fluid_volumes;
+    }
+
+    pub fn get_fluid_volumes(&self) -> &Vec<FluidVolume> {
+        &self.fluid_volumes
+    }
+
+    pub fn set_areas(&mut self, areas: Vec<Area>) {
+        self.areas = areas;
+    }
+
+    pub fn get_areas(&self) -> &Vec<Area> {
+        &self.areas
+    }
 }
\ No newline at end of file
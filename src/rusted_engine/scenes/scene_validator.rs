@@ -0,0 +1,941 @@
+use std::{collections::HashSet, fs::{self, File}, io::Read, path::Path};
+
+use serde_json::Value;
+
+use crate::rusted_engine::{events::placeholders::{OTHER_PLACEHOLDER, SELF_PLACEHOLDER}, util::{char_to_glfw_key::char_to_glfw_key, gamepad_names::{axis_name_to_glfw_axis, button_name_to_glfw_button}}};
+
+/// Collision modes the scene loader understands, anything else is dropped without a word
+const COLLISION_MODES: [&str; 3] = ["AABB", "Circle", "OBB"];
+
+/// One problem found in a scene file. The path points at the offending value, e.g. objects[2].entity.triggers[0].outcome[1].SwapScene.scene_name
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    pub file: String,
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    fn add(&mut self, file: &str, path: &str, message: String) {
+        self.issues.push(ValidationIssue { file: file.to_owned(), path: path.to_owned(), message });
+    }
+
+    /// Adds the issues of another report after these
+    pub fn append(&mut self, other: ValidationReport) {
+        self.issues.extend(other.issues);
+    }
+
+    pub fn print(&self) {
+        if self.issues.is_empty() {
+            println!("Scene validation found no problems.");
+            return;
+        }
+        println!("Scene validation found {} problem(s):", self.issues.len());
+        for issue in self.issues.iter() {
+            println!("  {} at {}: {}", issue.file, issue.path, issue.message);
+        }
+    }
+}
+
+/// Everything scene files are allowed to refer to by name
+pub struct ValidationContext {
+    pub scene_names: HashSet<String>,
+    pub sound_names: HashSet<String>,
+    pub texture_names: HashSet<String>,
+    pub material_names: HashSet<String>,
+    pub sequence_names: HashSet<String>, // Global sequences, each scene adds its own
+    pub script_names: HashSet<String>,
+    pub dialogue_names: HashSet<String>,
+    pub cutscene_names: HashSet<String>,
+    pub quest_names: HashSet<String>,
+    pub action_names: HashSet<String>,
+}
+
+/// Resource files outside the scenes that hold outcomes, each laid out its own way
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutcomeFileKind {
+    Dialogues,
+    Cutscenes,
+    Quests,
+    Sequences,
+    PianoSequences,
+}
+
+/// Names found across every scene before checking, so a reference can point at something another scene or outcome creates
+struct SceneNames {
+    entity_names: HashSet<String>, // Entities in this scene plus anything a CreateObject anywhere can spawn
+    area_names: HashSet<String>,
+    sequence_names: HashSet<String>,
+    trigger_ids: HashSet<String>, // From every scene, trigger state outlives scene swaps
+}
+
+/// Checks every scene JSON file in the directory against the loaded resources. Reads the files again rather than the loaded scenes,
+/// the loader has already thrown away what it didn't understand and the JSON paths are needed for the report.
+pub fn validate_scenes_in_directory(dir_path: &str, context: &ValidationContext) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let scene_files = read_json_files(dir_path, &mut report);
+
+    let mut created_names = HashSet::new();
+    let mut trigger_ids = HashSet::new();
+    for (_, scene) in scene_files.iter() {
+        collect_created_names(scene, &mut created_names);
+        collect_trigger_ids(scene, &mut trigger_ids);
+    }
+
+    for (file, scene) in scene_files.iter() {
+        let mut names = SceneNames {
+            entity_names: created_names.clone(),
+            area_names: HashSet::new(),
+            sequence_names: context.sequence_names.clone(),
+            trigger_ids: trigger_ids.clone(),
+        };
+        names.add_scene(scene);
+
+        let mut validator = SceneValidator { file, context, names: &names, report: &mut report };
+        validator.check_scene(scene);
+    }
+
+    report
+}
+
+/// Checks the outcomes in dialogue, cutscene, quest, sequence and piano sequence files, one directory of each kind.
+/// They can run in any scene, so the names in them are checked against what the scenes in scenes_dir_path have between them.
+pub fn validate_outcome_files(scenes_dir_path: &str, outcome_dirs: &[(&str, OutcomeFileKind)], context: &ValidationContext) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    // Scenes that can't be read are reported by validate_scenes_in_directory
+    let scene_files = read_json_files(scenes_dir_path, &mut ValidationReport::default());
+
+    let mut names = SceneNames {
+        entity_names: HashSet::new(),
+        area_names: HashSet::new(),
+        sequence_names: context.sequence_names.clone(),
+        trigger_ids: HashSet::new(),
+    };
+    for (_, scene) in scene_files.iter() {
+        collect_created_names(scene, &mut names.entity_names);
+        collect_trigger_ids(scene, &mut names.trigger_ids);
+        names.add_scene(scene);
+    }
+
+    for (dir_path, kind) in outcome_dirs {
+        for (file, value) in read_json_files(dir_path, &mut report) {
+            let mut validator = SceneValidator { file: &file, context, names: &names, report: &mut report };
+            validator.check_outcome_file(&value, *kind);
+        }
+    }
+
+    report
+}
+
+impl SceneNames {
+    /// Adds the entities, areas and sequences a scene file declares
+    fn add_scene(&mut self, scene: &Value) {
+        for object in array_at(scene, "objects") {
+            if let Some(name) = object.pointer("/entity/name").and_then(Value::as_str) {
+                self.entity_names.insert(name.to_owned());
+            }
+        }
+        for area in array_at(&scene["properties"], "areas") {
+            if let Some(area_name) = area["name"].as_str() {
+                self.area_names.insert(area_name.to_owned());
+            }
+        }
+        if let Some(sequences) = scene["sequences"].as_object() {
+            self.sequence_names.extend(sequences.keys().cloned());
+        }
+    }
+}
+
+struct SceneValidator<'a> {
+    file: &'a str,
+    context: &'a ValidationContext,
+    names: &'a SceneNames,
+    report: &'a mut ValidationReport,
+}
+
+impl<'a> SceneValidator<'a> {
+    fn issue(&mut self, path: &str, message: String) {
+        self.report.add(self.file, path, message);
+    }
+
+    fn check_scene(&mut self, scene: &Value) {
+        for (index, object) in array_at(scene, "objects").iter().enumerate() {
+            self.check_object(object, &format!("objects[{}]", index));
+        }
+
+        for (index, scene_trigger) in array_at(scene, "scene_triggers").iter().enumerate() {
+            let path = format!("scene_triggers[{}]", index);
+            self.check_trigger(scene_trigger, &path);
+        }
+
+        for (index, fluid_volume) in array_at(&scene["properties"], "fluid_volumes").iter().enumerate() {
+            let path = format!("properties.fluid_volumes[{}]", index);
+            self.check_outcomes(&fluid_volume["on_enter"], &format!("{}.on_enter", path));
+            self.check_outcomes(&fluid_volume["on_exit"], &format!("{}.on_exit", path));
+        }
+
+        if let Some(sequences) = scene["sequences"].as_object() {
+            for (sequence_name, steps) in sequences {
+                for (index, step) in steps.as_array().map(Vec::as_slice).unwrap_or_default().iter().enumerate() {
+                    self.check_outcomes(&step["outcome"], &format!("sequences.{}[{}].outcome", sequence_name, index));
+                }
+            }
+        }
+
+        for (index, piano_sequence) in array_at(scene, "piano_sequences").iter().enumerate() {
+            self.check_outcomes(&piano_sequence["outcome"], &format!("piano_sequences[{}].outcome", index));
+        }
+    }
+
+    /// A dialogue, cutscene, quest, sequence or piano sequence file. Only the outcomes and the names they use are checked.
+    fn check_outcome_file(&mut self, value: &Value, kind: OutcomeFileKind) {
+        match kind {
+            OutcomeFileKind::Dialogues => {
+                for (index, dialogue) in as_slice(value).iter().enumerate() {
+                    let Some(nodes) = dialogue["nodes"].as_object() else { continue; };
+                    for (node_name, node) in nodes {
+                        let path = format!("[{}].nodes.{}", index, node_name);
+                        self.check_outcomes(&node["on_enter"], &format!("{}.on_enter", path));
+                        for (choice_index, choice) in array_at(node, "choices").iter().enumerate() {
+                            self.check_outcomes(&choice["outcome"], &format!("{}.choices[{}].outcome", path, choice_index));
+                        }
+                    }
+                }
+            }
+            OutcomeFileKind::Cutscenes => {
+                for (index, cutscene) in as_slice(value).iter().enumerate() {
+                    let path = format!("[{}]", index);
+                    for (key_index, key) in array_at(cutscene, "outcome_track").iter().enumerate() {
+                        self.check_outcomes(&key["outcome"], &format!("{}.outcome_track[{}].outcome", path, key_index));
+                    }
+                    for (track_index, track) in array_at(cutscene, "position_tracks").iter().enumerate() {
+                        self.check_entity_name(&track["object_name"], &format!("{}.position_tracks[{}].object_name", path, track_index));
+                    }
+                    for (key_index, key) in array_at(cutscene, "camera_target_track").iter().enumerate() {
+                        self.check_entity_name(&key["target"], &format!("{}.camera_target_track[{}].target", path, key_index));
+                    }
+                    self.check_outcomes(&cutscene["on_finished"], &format!("{}.on_finished", path));
+                }
+            }
+            OutcomeFileKind::Quests => {
+                for (index, quest) in as_slice(value).iter().enumerate() {
+                    let path = format!("[{}]", index);
+                    for (objective_index, objective) in array_at(quest, "objectives").iter().enumerate() {
+                        self.check_outcomes(&objective["on_complete"], &format!("{}.objectives[{}].on_complete", path, objective_index));
+                    }
+                    self.check_outcomes(&quest["on_complete"], &format!("{}.on_complete", path));
+                }
+            }
+            OutcomeFileKind::Sequences => {
+                for (sequence_name, steps) in value.as_object().into_iter().flatten() {
+                    for (index, step) in as_slice(steps).iter().enumerate() {
+                        self.check_outcomes(&step["outcome"], &format!("{}[{}].outcome", sequence_name, index));
+                    }
+                }
+            }
+            OutcomeFileKind::PianoSequences => {
+                for (index, piano_sequence) in as_slice(value).iter().enumerate() {
+                    self.check_outcomes(&piano_sequence["outcome"], &format!("[{}].outcome", index));
+                }
+            }
+        }
+    }
+
+    /// A scene object, or the ObjectData of a CreateObject outcome
+    fn check_object(&mut self, object: &Value, path: &str) {
+        let entity = &object["entity"];
+        let graphics = &object["graphics"];
+
+        for (index, collision_mode) in array_at(entity, "collision_modes").iter().enumerate() {
+            let collision_mode = collision_mode.as_str().unwrap_or_default();
+            if !COLLISION_MODES.contains(&collision_mode) {
+                self.issue(&format!("{}.entity.collision_modes[{}]", path, index), format!("Unknown collision mode \"{}\", it will be ignored. Expected one of {:?}", collision_mode, COLLISION_MODES));
+            }
+        }
+
+        if let Some(material) = entity["material"].as_str() {
+            if !self.context.material_names.contains(material) {
+                self.issue(&format!("{}.entity.material", path), format!("No material named \"{}\"", material));
+            }
+        }
+
+        if let Some(script) = entity["script"].as_str() {
+            if !self.context.script_names.contains(script) {
+                self.issue(&format!("{}.entity.script", path), format!("No script named \"{}\"", script));
+            }
+        }
+
+        if let Some(velocity) = entity["velocity"].as_array() {
+            if velocity.len() != 2 {
+                self.issue(&format!("{}.entity.velocity", path), format!("Expected 2 values, found {}", velocity.len()));
+            }
+        }
+
+        if let Some(position) = graphics["position"].as_array() {
+            if position.len() != 3 {
+                self.issue(&format!("{}.graphics.position", path), format!("Expected 3 values, found {}", position.len()));
+            }
+        }
+
+        if let Some(texture_name) = graphics["texture_name"].as_str() {
+            if !self.context.texture_names.contains(texture_name) {
+                self.issue(&format!("{}.graphics.texture_name", path), format!("No texture named \"{}\"", texture_name));
+            }
+        }
+
+        self.check_entity_name(&object["relative_to"], &format!("{}.relative_to", path));
+
+        for (index, trigger) in array_at(entity, "triggers").iter().enumerate() {
+            self.check_trigger(trigger, &format!("{}.entity.triggers[{}]", path, index));
+        }
+    }
+
+    fn check_trigger(&mut self, trigger: &Value, path: &str) {
+        self.check_conditions(&trigger["conditions"], &format!("{}.conditions", path));
+        self.check_outcomes(&trigger["outcome"], &format!("{}.outcome", path));
+    }
+
+    fn check_conditions(&mut self, conditions: &Value, path: &str) {
+        let (kind, args) = match single_entry(conditions) {
+            Some(entry) => entry,
+            None => return,
+        };
+        let path = format!("{}.{}", path, kind);
+
+        match kind {
+            "All" | "Any" => {
+                for (index, child) in args.as_array().map(Vec::as_slice).unwrap_or_default().iter().enumerate() {
+                    self.check_conditions(child, &format!("{}[{}]", path, index));
+                }
+            }
+            "Not" => {
+                if contains_driver(args) {
+                    self.issue(&path, "Timer and speed conditions drive their trigger and can't be negated".to_owned());
+                }
+                self.check_conditions(args, &path);
+            }
+            "CollisionConditions" => self.check_entity_name(&args["collided_with"], &format!("{}.collided_with", path)),
+            "KeyConditions" => {
+                for (index, key) in array_at(args, "keys").iter().enumerate() {
+                    if let Some(key) = key.as_str() {
+                        let mut chars = key.chars();
+                        let is_key = matches!((chars.next(), chars.next()), (Some(c), None) if char_to_glfw_key(c).is_some());
+                        if !is_key {
+                            self.issue(&format!("{}.keys[{}]", path, index), format!("No key types \"{}\", expected a single letter, digit or symbol on the keyboard", key));
+                        }
+                    }
+                }
+            }
+            "ActionConditions" => {
+                for (index, action_name) in args["actions"].as_array().map(Vec::as_slice).unwrap_or_default().iter().enumerate() {
+                    self.check_action_name(action_name, &format!("{}.actions[{}]", path, index));
+                }
+            }
+            "GamepadButtonConditions" => {
+                for (index, button_name) in args["buttons"].as_array().map(Vec::as_slice).unwrap_or_default().iter().enumerate() {
+                    if let Some(button_name) = button_name.as_str() {
+                        if button_name_to_glfw_button(button_name).is_none() {
+                            self.issue(&format!("{}.buttons[{}]", path, index), format!("No gamepad button named \"{}\"", button_name));
+                        }
+                    }
+                }
+            }
+            "GamepadAxisConditions" => {
+                if let Some(axis_name) = args["axis"].as_str() {
+                    if axis_name_to_glfw_axis(axis_name).is_none() {
+                        self.issue(&format!("{}.axis", path), format!("No gamepad axis named \"{}\"", axis_name));
+                    }
+                }
+            }
+            "QuestConditions" => self.check_quest_name(&args["quest_name"], &format!("{}.quest_name", path)),
+            "EntityConditions" => self.check_entity_name(&args["entity_name"], &format!("{}.entity_name", path)),
+            "AreaConditions" => {
+                if let Some(area_name) = args["area_name"].as_str() {
+                    if !self.names.area_names.contains(area_name) {
+                        self.issue(&format!("{}.area_name", path), format!("No area named \"{}\" in this scene", area_name));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// A single outcome or a list of them
+    fn check_outcomes(&mut self, outcomes: &Value, path: &str) {
+        match outcomes {
+            Value::Array(outcomes) => {
+                for (index, outcome) in outcomes.iter().enumerate() {
+                    self.check_outcome(outcome, &format!("{}[{}]", path, index));
+                }
+            }
+            Value::Object(_) => self.check_outcome(outcomes, path),
+            _ => {}
+        }
+    }
+
+    fn check_outcome(&mut self, outcome: &Value, path: &str) {
+        let (kind, args) = match single_entry(outcome) {
+            Some(entry) => entry,
+            None => return,
+        };
+        let path = format!("{}.{}", path, kind);
+
+        // Every outcome that acts on an object names it the same way
+        self.check_entity_name(&args["object_name"], &format!("{}.object_name", path));
+
+        match kind {
+            "SwapScene" => {
+                if let Some(scene_name) = args["scene_name"].as_str() {
+                    if !self.context.scene_names.contains(scene_name) {
+                        self.issue(&format!("{}.scene_name", path), format!("No scene named \"{}\"", scene_name));
+                    }
+                }
+            }
+            "EnqueueAudio" => {
+                if let Some(audio_name) = args["audio_name"].as_str() {
+                    if !self.context.sound_names.contains(audio_name) {
+                        self.issue(&format!("{}.audio_name", path), format!("No sound named \"{}\"", audio_name));
+                    }
+                }
+            }
+            "Sequence" | "StopSequence" => {
+                if let Some(sequence_name) = args["sequence_name"].as_str() {
+                    if !self.names.sequence_names.contains(sequence_name) {
+                        self.issue(&format!("{}.sequence_name", path), format!("No sequence named \"{}\"", sequence_name));
+                    }
+                }
+            }
+            "StartDialogue" => {
+                if let Some(dialogue_name) = args["dialogue_name"].as_str() {
+                    if !self.context.dialogue_names.contains(dialogue_name) {
+                        self.issue(&format!("{}.dialogue_name", path), format!("No dialogue named \"{}\"", dialogue_name));
+                    }
+                }
+            }
+            "PlayCutscene" | "PauseCutscene" | "ResumeCutscene" | "SkipCutscene" => {
+                if let Some(cutscene_name) = args["cutscene_name"].as_str() {
+                    if !self.context.cutscene_names.contains(cutscene_name) {
+                        self.issue(&format!("{}.cutscene_name", path), format!("No cutscene named \"{}\"", cutscene_name));
+                    }
+                }
+            }
+            "RebindAction" => self.check_action_name(&args["action_name"], &format!("{}.action_name", path)),
+            "StartQuest" | "AdvanceObjective" => self.check_quest_name(&args["quest_name"], &format!("{}.quest_name", path)),
+            "Script" => {
+                if let Some(script_name) = args["script_name"].as_str() {
+                    if !self.context.script_names.contains(script_name) {
+                        self.issue(&format!("{}.script_name", path), format!("No script named \"{}\"", script_name));
+                    }
+                }
+            }
+            "EnableTrigger" | "DisableTrigger" => {
+                if let Some(trigger_id) = args["trigger_id"].as_str() {
+                    // "#index" points at a position in a list, only ids can be checked
+                    if !trigger_id.starts_with('#') && !self.names.trigger_ids.contains(trigger_id) {
+                        self.issue(&format!("{}.trigger_id", path), format!("No trigger with id \"{}\"", trigger_id));
+                    }
+                }
+                for (index, owner) in args["owners"].as_array().map(Vec::as_slice).unwrap_or_default().iter().enumerate() {
+                    // Scene triggers belong to their scene
+                    if owner.as_str().is_some_and(|owner| self.context.scene_names.contains(owner)) {
+                        continue;
+                    }
+                    self.check_entity_name(owner, &format!("{}.owners[{}]", path, index));
+                }
+            }
+            "SetCameraTrackingTarget" => self.check_entity_name(&args["tracking_target"], &format!("{}.tracking_target", path)),
+            "TeleportObject" => {
+                self.check_vector(&args["new_position"], &[2, 3], &format!("{}.new_position", path));
+                self.check_entity_name(&args["relative_to"], &format!("{}.relative_to", path));
+            }
+            "AccelerateObject" => self.check_vector(&args["acceleration"], &[2], &format!("{}.acceleration", path)),
+            "SetVelocity" => self.check_vector(&args["velocity"], &[2], &format!("{}.velocity", path)),
+            "ApplyImpulse" => self.check_vector(&args["impulse"], &[2], &format!("{}.impulse", path)),
+            "SetSceneGravity" => self.check_vector(&args["gravity"], &[1, 2], &format!("{}.gravity", path)),
+            "SetTerminalVelocity" => self.check_vector(&args["terminal_velocity"], &[1, 2], &format!("{}.terminal_velocity", path)),
+            "CreateObject" => self.check_object(args, &path),
+            "Delay" | "Repeat" => self.check_outcomes(&args["outcome"], &format!("{}.outcome", path)),
+            "If" => {
+                self.check_conditions(&args["condition"], &format!("{}.condition", path));
+                self.check_outcomes(&args["then"], &format!("{}.then", path));
+                self.check_outcomes(&args["else"], &format!("{}.else", path));
+            }
+            "RandomChoice" => {
+                for (index, choice) in array_at(args, "choices").iter().enumerate() {
+                    self.check_outcomes(&choice["outcome"], &format!("{}.choices[{}].outcome", path, index));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_entity_name(&mut self, name: &Value, path: &str) {
+        if let Some(name) = name.as_str() {
+            // Placeholders are filled in when the trigger fires
+            if name.contains(SELF_PLACEHOLDER) || name.contains(OTHER_PLACEHOLDER) {
+                return;
+            }
+            if !name.is_empty() && !self.names.entity_names.contains(name) {
+                self.issue(path, format!("No entity named \"{}\" in this scene, and no CreateObject makes one", name));
+            }
+        }
+    }
+
+    fn check_action_name(&mut self, action_name: &Value, path: &str) {
+        if let Some(action_name) = action_name.as_str() {
+            if !self.context.action_names.contains(action_name) {
+                self.issue(path, format!("No action named \"{}\" in the bindings", action_name));
+            }
+        }
+    }
+
+    fn check_quest_name(&mut self, quest_name: &Value, path: &str) {
+        if let Some(quest_name) = quest_name.as_str() {
+            if !self.context.quest_names.contains(quest_name) {
+                self.issue(path, format!("No quest named \"{}\"", quest_name));
+            }
+        }
+    }
+
+    fn check_vector(&mut self, vector: &Value, allowed_lengths: &[usize], path: &str) {
+        if let Some(vector) = vector.as_array() {
+            if !allowed_lengths.contains(&vector.len()) {
+                self.issue(path, format!("Expected {:?} values, found {}", allowed_lengths, vector.len()));
+            }
+        }
+    }
+}
+
+/// The file stems of every file with one of the extensions in the directories, for building a ValidationContext
+pub fn names_in_directories(dir_paths: &[&str], extensions: &[&str]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for dir_path in dir_paths {
+        if let Ok(paths) = fs::read_dir(dir_path) {
+            for entry in paths.flatten() {
+                let full_path = entry.path();
+                let has_extension = full_path.extension().and_then(|extension| extension.to_str()).is_some_and(|extension| extensions.contains(&extension));
+                if full_path.is_file() && has_extension {
+                    if let Some(stem) = full_path.file_stem().and_then(|stem| stem.to_str()) {
+                        names.insert(stem.to_owned());
+                    }
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Every JSON file in the directory, anything that can't be read is reported
+fn read_json_files(dir_path: &str, report: &mut ValidationReport) -> Vec<(String, Value)> {
+    let mut files = Vec::new();
+    let paths = match fs::read_dir(dir_path) {
+        Ok(paths) => paths,
+        Err(_) => {
+            report.add(dir_path, "", "Failed to read directory".to_owned());
+            return files;
+        }
+    };
+    for entry in paths.flatten() {
+        let full_path = entry.path();
+        if !full_path.is_file() || full_path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let file = full_path.display().to_string();
+        match read_json(&full_path) {
+            Ok(value) => files.push((file, value)),
+            Err(e) => report.add(&file, "", format!("Could not be read as JSON: {}", e)),
+        }
+    }
+    files
+}
+
+fn read_json(path: &Path) -> Result<Value, Box<dyn std::error::Error>> {
+    let mut file = File::open(path)?;
+    let mut data = String::new();
+    file.read_to_string(&mut data)?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+fn array_at<'v>(value: &'v Value, key: &str) -> &'v [Value] {
+    as_slice(&value[key])
+}
+
+fn as_slice(value: &Value) -> &[Value] {
+    value.as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Externally tagged enums are written as a map with a single key
+fn single_entry(value: &Value) -> Option<(&str, &Value)> {
+    let map = value.as_object()?;
+    if map.len() != 1 {
+        return None;
+    }
+    map.iter().next().map(|(key, value)| (key.as_str(), value))
+}
+
+/// Whether a condition tree has a timer or speed condition in it
+fn contains_driver(conditions: &Value) -> bool {
+    match single_entry(conditions) {
+        Some(("TimerConditions" | "SpeedConditions", _)) => true,
+        Some(("All" | "Any", children)) => children.as_array().is_some_and(|children| children.iter().any(contains_driver)),
+        Some(("Not", child)) => contains_driver(child),
+        _ => false,
+    }
+}
+
+/// Every entity name a CreateObject outcome in the scene can spawn
+fn collect_created_names(value: &Value, names: &mut HashSet<String>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(name) = map.get("CreateObject").and_then(|object| object.pointer("/entity/name")).and_then(Value::as_str) {
+                names.insert(name.to_owned());
+            }
+            for child in map.values() {
+                collect_created_names(child, names);
+            }
+        }
+        Value::Array(values) => {
+            for child in values {
+                collect_created_names(child, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Every trigger id in the scene, found as an "id" next to a "trigger_type" or "scene_trigger_type"
+fn collect_trigger_ids(value: &Value, ids: &mut HashSet<String>) {
+    match value {
+        Value::Object(map) => {
+            if map.contains_key("trigger_type") || map.contains_key("scene_trigger_type") {
+                if let Some(id) = map.get("id").and_then(Value::as_str) {
+                    ids.insert(id.to_owned());
+                }
+            }
+            for child in map.values() {
+                collect_trigger_ids(child, ids);
+            }
+        }
+        Value::Array(values) => {
+            for child in values {
+                collect_trigger_ids(child, ids);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::rusted_engine::util::fixture_dir::FixtureDir;
+
+    use super::*;
+
+    fn names(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn context() -> ValidationContext {
+        ValidationContext {
+            scene_names: names(&["testscene"]),
+            sound_names: names(&["Gravity"]),
+            texture_names: names(&["player"]),
+            material_names: names(&["stone"]),
+            sequence_names: names(&["reset_sequence"]),
+            script_names: names(&["bouncer"]),
+            dialogue_names: names(&["gatekeeper"]),
+            cutscene_names: names(&["reset"]),
+            quest_names: names(&["explorer"]),
+            action_names: names(&["jump"]),
+        }
+    }
+
+    /// A scene with a player and a pond, plus the triggers given on the player
+    fn scene_with_triggers(triggers: Value) -> Value {
+        json!({
+            "objects": [{
+                "entity": { "name": "player", "triggers": triggers },
+                "graphics": { "name": "player", "position": [0.0, 0.0, 0.0], "texture_name": "player" }
+            }],
+            "properties": { "areas": [{ "name": "pond" }] }
+        })
+    }
+
+    fn scene_with_outcomes(outcomes: Value) -> Value {
+        scene_with_triggers(json!([{ "trigger_type": "Collision", "outcome": outcomes }]))
+    }
+
+    fn scene_with_conditions(conditions: Value) -> Value {
+        scene_with_triggers(json!([{ "trigger_type": "Collision", "conditions": conditions, "outcome": [] }]))
+    }
+
+    fn validate_scene(test_name: &str, scene: Value) -> Vec<String> {
+        let fixture_dir = FixtureDir::new(test_name);
+        fixture_dir.write("testscene.json", &scene.to_string());
+        issue_paths(&validate_scenes_in_directory(&fixture_dir.path(), &context()))
+    }
+
+    fn issue_paths(report: &ValidationReport) -> Vec<String> {
+        let mut paths: Vec<String> = report.issues.iter().map(|issue| issue.path.clone()).collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn a_scene_that_only_uses_known_names_has_no_issues() {
+        let scene = scene_with_outcomes(json!([
+            { "SwapScene": { "scene_name": "testscene" } },
+            { "EnqueueAudio": { "audio_name": "Gravity", "audio_type": "UI", "volume": 0.6 } },
+            { "Sequence": { "sequence_name": "reset_sequence" } },
+            { "StartDialogue": { "dialogue_name": "gatekeeper" } },
+            { "PlayCutscene": { "cutscene_name": "reset" } },
+            { "StartQuest": { "quest_name": "explorer" } },
+            { "RebindAction": { "action_name": "jump" } },
+            { "Script": { "script_name": "bouncer" } },
+            { "TeleportObject": { "object_name": "player", "new_position": [0.0, 1.0] } }
+        ]));
+
+        assert!(validate_scene("known_names", scene).is_empty());
+    }
+
+    #[test]
+    fn unknown_object_properties_are_reported() {
+        let scene = json!({
+            "objects": [{
+                "entity": { "name": "player", "collision_modes": ["Box"], "material": "glass", "script": "missing", "velocity": [1.0] },
+                "graphics": { "name": "player", "position": [0.0, 0.0], "texture_name": "missing" }
+            }]
+        });
+
+        assert_eq!(validate_scene("object_properties", scene), vec![
+            "objects[0].entity.collision_modes[0]",
+            "objects[0].entity.material",
+            "objects[0].entity.script",
+            "objects[0].entity.velocity",
+            "objects[0].graphics.position",
+            "objects[0].graphics.texture_name",
+        ]);
+    }
+
+    #[test]
+    fn unknown_names_in_outcomes_are_reported() {
+        let scene = scene_with_outcomes(json!([
+            { "SwapScene": { "scene_name": "nowhere" } },
+            { "EnqueueAudio": { "audio_name": "missing", "audio_type": "UI", "volume": 0.6 } },
+            { "Sequence": { "sequence_name": "missing" } },
+            { "StartDialogue": { "dialogue_name": "missing" } },
+            { "PlayCutscene": { "cutscene_name": "missing" } },
+            { "StartQuest": { "quest_name": "missing" } },
+            { "RebindAction": { "action_name": "missing" } },
+            { "Script": { "script_name": "missing" } },
+            { "EnableTrigger": { "trigger_id": "missing", "owners": ["nobody"] } },
+            { "WakeObject": { "object_name": "nobody" } }
+        ]));
+
+        let prefix = "objects[0].entity.triggers[0].outcome";
+        assert_eq!(validate_scene("outcome_names", scene), vec![
+            format!("{}[0].SwapScene.scene_name", prefix),
+            format!("{}[1].EnqueueAudio.audio_name", prefix),
+            format!("{}[2].Sequence.sequence_name", prefix),
+            format!("{}[3].StartDialogue.dialogue_name", prefix),
+            format!("{}[4].PlayCutscene.cutscene_name", prefix),
+            format!("{}[5].StartQuest.quest_name", prefix),
+            format!("{}[6].RebindAction.action_name", prefix),
+            format!("{}[7].Script.script_name", prefix),
+            format!("{}[8].EnableTrigger.owners[0]", prefix),
+            format!("{}[8].EnableTrigger.trigger_id", prefix),
+            format!("{}[9].WakeObject.object_name", prefix),
+        ]);
+    }
+
+    #[test]
+    fn wrong_vector_sizes_in_outcomes_are_reported() {
+        let scene = scene_with_outcomes(json!([
+            { "TeleportObject": { "object_name": "player", "new_position": [0.0, 1.0, 2.0, 3.0] } },
+            { "AccelerateObject": { "object_name": "player", "acceleration": [1.0, 2.0, 3.0], "max_speed": 1.0 } },
+            { "SetVelocity": { "object_name": "player", "velocity": [1.0] } },
+            { "ApplyImpulse": { "object_name": "player", "impulse": [] } },
+            { "SetSceneGravity": { "gravity": [0.0, -9.8, 0.0] } },
+            { "SetTerminalVelocity": { "terminal_velocity": [] } }
+        ]));
+
+        let prefix = "objects[0].entity.triggers[0].outcome";
+        assert_eq!(validate_scene("vector_sizes", scene), vec![
+            format!("{}[0].TeleportObject.new_position", prefix),
+            format!("{}[1].AccelerateObject.acceleration", prefix),
+            format!("{}[2].SetVelocity.velocity", prefix),
+            format!("{}[3].ApplyImpulse.impulse", prefix),
+            format!("{}[4].SetSceneGravity.gravity", prefix),
+            format!("{}[5].SetTerminalVelocity.terminal_velocity", prefix),
+        ]);
+    }
+
+    #[test]
+    fn unknown_names_in_conditions_are_reported() {
+        let scene = scene_with_conditions(json!({ "All": [
+            { "CollisionConditions": { "collided_with": "nobody" } },
+            { "ActionConditions": { "actions": ["jump", "fly"] } },
+            { "GamepadButtonConditions": { "buttons": ["A", "Z"] } },
+            { "GamepadAxisConditions": { "axis": "Sideways", "threshold": 0.5 } },
+            { "QuestConditions": { "quest_name": "missing", "state": "Active" } },
+            { "EntityConditions": { "entity_name": "nobody", "state": "Sleeping" } },
+            { "AreaConditions": { "area_name": "lake" } }
+        ]}));
+
+        let prefix = "objects[0].entity.triggers[0].conditions.All";
+        assert_eq!(validate_scene("condition_names", scene), vec![
+            format!("{}[0].CollisionConditions.collided_with", prefix),
+            format!("{}[1].ActionConditions.actions[1]", prefix),
+            format!("{}[2].GamepadButtonConditions.buttons[1]", prefix),
+            format!("{}[3].GamepadAxisConditions.axis", prefix),
+            format!("{}[4].QuestConditions.quest_name", prefix),
+            format!("{}[5].EntityConditions.entity_name", prefix),
+            format!("{}[6].AreaConditions.area_name", prefix),
+        ]);
+    }
+
+    #[test]
+    fn key_conditions_need_a_single_character_a_key_types() {
+        let scene = scene_with_conditions(json!({ "KeyConditions": { "keys": ["w", "W", "1", "é", "up", ""] } }));
+
+        let prefix = "objects[0].entity.triggers[0].conditions.KeyConditions";
+        assert_eq!(validate_scene("key_conditions", scene), vec![
+            format!("{}.keys[3]", prefix),
+            format!("{}.keys[4]", prefix),
+            format!("{}.keys[5]", prefix),
+        ]);
+    }
+
+    #[test]
+    fn negated_timer_and_speed_conditions_are_reported() {
+        let scene = scene_with_conditions(json!({ "Any": [
+            { "Not": { "TimerConditions": { "seconds": 1.0 } } },
+            { "Not": { "All": [{ "SpeedConditions": { "speed": 2.0 } }] } },
+            { "Not": { "VariableConditions": { "variable_name": "coins", "comparison": "Equal", "value": 0 } } }
+        ]}));
+
+        let prefix = "objects[0].entity.triggers[0].conditions.Any";
+        assert_eq!(validate_scene("negated_drivers", scene), vec![
+            format!("{}[0].Not", prefix),
+            format!("{}[1].Not", prefix),
+        ]);
+    }
+
+    #[test]
+    fn nested_outcomes_and_other_scene_parts_are_checked() {
+        let mut scene = scene_with_outcomes(json!([
+            { "Delay": { "seconds": 1.0, "outcome": [{ "WakeObject": { "object_name": "nobody" } }] } },
+            { "If": {
+                "condition": { "AreaConditions": { "area_name": "lake" } },
+                "then": [{ "WakeObject": { "object_name": "nobody" } }],
+                "else": [{ "WakeObject": { "object_name": "nobody" } }]
+            } },
+            { "RandomChoice": { "choices": [{ "weight": 1.0, "outcome": [{ "WakeObject": { "object_name": "nobody" } }] }] } }
+        ]));
+        scene["scene_triggers"] = json!([{ "scene_trigger_type": "OnEnter", "outcome": [{ "WakeObject": { "object_name": "nobody" } }] }]);
+        scene["sequences"] = json!({ "local_sequence": [{ "outcome": [{ "WakeObject": { "object_name": "nobody" } }] }] });
+        scene["piano_sequences"] = json!([{ "name": "tune", "notes": ["A4"], "outcome": [{ "Sequence": { "sequence_name": "local_sequence" } }] }]);
+        scene["properties"]["fluid_volumes"] = json!([{ "on_enter": [{ "WakeObject": { "object_name": "nobody" } }] }]);
+
+        let prefix = "objects[0].entity.triggers[0].outcome";
+        assert_eq!(validate_scene("nested_outcomes", scene), vec![
+            format!("{}[0].Delay.outcome[0].WakeObject.object_name", prefix),
+            format!("{}[1].If.condition.AreaConditions.area_name", prefix),
+            format!("{}[1].If.else[0].WakeObject.object_name", prefix),
+            format!("{}[1].If.then[0].WakeObject.object_name", prefix),
+            format!("{}[2].RandomChoice.choices[0].outcome[0].WakeObject.object_name", prefix),
+            "properties.fluid_volumes[0].on_enter[0].WakeObject.object_name".to_owned(),
+            "scene_triggers[0].outcome[0].WakeObject.object_name".to_owned(),
+            "sequences.local_sequence[0].outcome[0].WakeObject.object_name".to_owned(),
+        ]);
+    }
+
+    #[test]
+    fn created_objects_and_placeholders_count_as_entity_names() {
+        let scene = scene_with_outcomes(json!([
+            { "CreateObject": { "entity": { "name": "coin" }, "graphics": { "name": "coin", "position": [0.0, 0.0, 0.0], "texture_name": "player" } } },
+            { "WakeObject": { "object_name": "coin" } },
+            { "DestroyObject": { "object_name": "$self" } },
+            { "WakeObject": { "object_name": "$other" } }
+        ]));
+
+        assert!(validate_scene("created_objects", scene).is_empty());
+    }
+
+    #[test]
+    fn unreadable_files_and_directories_are_reported() {
+        let fixture_dir = FixtureDir::new("unreadable");
+        fixture_dir.write("broken.json", "{ \"objects\": [");
+        fixture_dir.write("notes.txt", "not a scene");
+
+        let report = validate_scenes_in_directory(&fixture_dir.path(), &context());
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].file.ends_with("broken.json"));
+
+        let missing_path = format!("{}_missing", fixture_dir.path());
+        let report = validate_scenes_in_directory(&missing_path, &context());
+        assert_eq!(report.issues[0].message, "Failed to read directory");
+    }
+
+    #[test]
+    fn outcomes_in_resource_files_are_checked_against_every_scene() {
+        let scenes_dir = FixtureDir::new("outcome_files_scenes");
+        scenes_dir.write("testscene.json", &scene_with_outcomes(json!([])).to_string());
+
+        let dialogues_dir = FixtureDir::new("outcome_files_dialogues");
+        dialogues_dir.write("dialogues.json", &json!([{ "name": "gatekeeper", "start_node": "greeting", "nodes": { "greeting": {
+            "speaker": "Gatekeeper", "text": "Halt.",
+            "on_enter": [{ "WakeObject": { "object_name": "player" } }],
+            "choices": [{ "text": "Leave.", "outcome": [{ "SwapScene": { "scene_name": "nowhere" } }] }]
+        } } }]).to_string());
+
+        let cutscenes_dir = FixtureDir::new("outcome_files_cutscenes");
+        cutscenes_dir.write("cutscenes.json", &json!([{
+            "name": "reset",
+            "outcome_track": [{ "time": 0.0, "outcome": [{ "EnqueueAudio": { "audio_name": "missing", "audio_type": "UI", "volume": 0.6 } }] }],
+            "position_tracks": [{ "object_name": "nobody", "keys": [] }],
+            "camera_target_track": [{ "time": 0.0, "target": "player" }],
+            "on_finished": [{ "StartDialogue": { "dialogue_name": "missing" } }]
+        }]).to_string());
+
+        let quests_dir = FixtureDir::new("outcome_files_quests");
+        quests_dir.write("quests.json", &json!([{
+            "name": "explorer",
+            "objectives": [{ "id": "visit", "goal": { "EnterScene": { "scene_name": "testscene" } }, "on_complete": [{ "StartQuest": { "quest_name": "missing" } }] }],
+            "on_complete": [{ "SetVariable": { "variable_name": "explorer_done", "value": true } }]
+        }]).to_string());
+
+        let sequences_dir = FixtureDir::new("outcome_files_sequences");
+        sequences_dir.write("sequences.json", &json!({ "reset_sequence": [{ "outcome": [{ "TeleportObject": { "object_name": "player", "new_position": [0.0] } }] }] }).to_string());
+
+        let piano_sequences_dir = FixtureDir::new("outcome_files_piano_sequences");
+        piano_sequences_dir.write("piano_sequences.json", &json!([{ "name": "tune", "notes": ["A4"], "outcome": [{ "Sequence": { "sequence_name": "missing" } }] }]).to_string());
+
+        let outcome_dirs = [
+            (dialogues_dir.path(), OutcomeFileKind::Dialogues),
+            (cutscenes_dir.path(), OutcomeFileKind::Cutscenes),
+            (quests_dir.path(), OutcomeFileKind::Quests),
+            (sequences_dir.path(), OutcomeFileKind::Sequences),
+            (piano_sequences_dir.path(), OutcomeFileKind::PianoSequences),
+        ];
+        let outcome_dirs: Vec<(&str, OutcomeFileKind)> = outcome_dirs.iter().map(|(path, kind)| (path.as_str(), *kind)).collect();
+
+        let report = validate_outcome_files(&scenes_dir.path(), &outcome_dirs, &context());
+        assert_eq!(issue_paths(&report), vec![
+            "[0].nodes.greeting.choices[0].outcome[0].SwapScene.scene_name",
+            "[0].objectives[0].on_complete[0].StartQuest.quest_name",
+            "[0].on_finished[0].StartDialogue.dialogue_name",
+            "[0].outcome[0].Sequence.sequence_name",
+            "[0].outcome_track[0].outcome[0].EnqueueAudio.audio_name",
+            "[0].position_tracks[0].object_name",
+            "reset_sequence[0].outcome[0].TeleportObject.new_position",
+        ]);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod script_bindings;
+pub mod script_engine;
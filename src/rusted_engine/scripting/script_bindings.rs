@@ -0,0 +1,154 @@
+use std::sync::{Arc, RwLock};
+
+use rhai::{Array, Dynamic, Engine, Map};
+use rusted_open::framework::graphics::{camera::Camera, util::master_graphics_list::MasterGraphicsList};
+
+use crate::rusted_engine::{audio::audio_manager::{AudioManager, AudioType}, entities::util::master_entity_list::MasterEntityList, events::{player_movement::{apply_impulse, set_velocity}, triggers::{ApplyImpulseArgs, Outcome, SetVelocityArgs}}, game_state::{GameState, GameVariable}};
+
+/// Registers the functions scripts can call. Anything not covered here can still be done with queue_outcome,
+/// which takes the same outcome maps the scene files use, e.g. queue_outcome(#{ DestroyObject: #{ object_name: "coin" } }).
+pub fn register_bindings(
+    engine: &mut Engine,
+    master_entity_list: Arc<RwLock<MasterEntityList>>,
+    master_graphics_list: Arc<RwLock<MasterGraphicsList>>,
+    game_state: Arc<RwLock<GameState>>,
+    audio_manager: Arc<RwLock<AudioManager>>,
+    camera: Arc<RwLock<Camera>>,
+    queued_outcomes: Arc<RwLock<Vec<Outcome>>>,
+) {
+    // Entities
+
+    let entity_list = master_entity_list.clone();
+    engine.register_fn("entity_exists", move |name: &str| -> bool {
+        entity_list.read().unwrap().get_entity(name).is_some()
+    });
+
+    let entity_list = master_entity_list.clone();
+    engine.register_fn("get_velocity", move |name: &str| -> Array {
+        match entity_list.read().unwrap().get_entity(name) {
+            Some(entity) => {
+                let velocity = entity.read().unwrap().get_velocity();
+                vec![Dynamic::from_float(velocity.x as f64), Dynamic::from_float(velocity.y as f64)]
+            }
+            None => {
+                println!("Script asked for the velocity of {}, which doesn't exist.", name);
+                Array::new()
+            }
+        }
+    });
+
+    let entity_list = master_entity_list.clone();
+    engine.register_fn("set_velocity", move |name: &str, x: f64, y: f64| {
+        let set_velocity_args = SetVelocityArgs { object_name: name.to_owned(), velocity: vec![x as f32, y as f32] };
+        if let Err(e) = set_velocity(set_velocity_args, &entity_list.read().unwrap()) {
+            println!("Script couldn't set a velocity: {}", e);
+        }
+    });
+
+    let entity_list = master_entity_list.clone();
+    engine.register_fn("apply_impulse", move |name: &str, x: f64, y: f64| {
+        let apply_impulse_args = ApplyImpulseArgs { object_name: name.to_owned(), impulse: vec![x as f32, y as f32] };
+        if let Err(e) = apply_impulse(apply_impulse_args, &entity_list.read().unwrap()) {
+            println!("Script couldn't apply an impulse: {}", e);
+        }
+    });
+
+    let entity_list = master_entity_list.clone();
+    engine.register_fn("is_sleeping", move |name: &str| -> bool {
+        match entity_list.read().unwrap().get_entity(name) {
+            Some(entity) => entity.read().unwrap().is_sleeping(),
+            None => false,
+        }
+    });
+
+    let graphics_list = master_graphics_list.clone();
+    engine.register_fn("get_position", move |name: &str| -> Array {
+        match graphics_list.read().unwrap().get_object(name) {
+            Some(object) => {
+                let position = object.read().unwrap().get_position();
+                vec![Dynamic::from_float(position.x as f64), Dynamic::from_float(position.y as f64)]
+            }
+            None => {
+                println!("Script asked for the position of {}, which doesn't exist.", name);
+                Array::new()
+            }
+        }
+    });
+
+    // Game state
+
+    let state = game_state.clone();
+    engine.register_fn("get_scene_name", move || -> String {
+        state.read().unwrap().get_current_scene_name()
+    });
+
+    let state = game_state.clone();
+    engine.register_fn("get_var", move |name: &str| -> Dynamic {
+        match state.read().unwrap().get_variable(name) {
+            Some(GameVariable::Bool(value)) => Dynamic::from_bool(value),
+            Some(GameVariable::Int(value)) => Dynamic::from_int(value),
+            Some(GameVariable::Float(value)) => Dynamic::from_float(value),
+            Some(GameVariable::String(value)) => Dynamic::from(value),
+            None => Dynamic::UNIT,
+        }
+    });
+
+    let state = game_state.clone();
+    engine.register_fn("set_var", move |name: &str, value: Dynamic| {
+        let variable = if let Ok(value) = value.as_bool() {
+            GameVariable::Bool(value)
+        } else if let Ok(value) = value.as_int() {
+            GameVariable::Int(value)
+        } else if let Ok(value) = value.as_float() {
+            GameVariable::Float(value)
+        } else if value.is_string() {
+            GameVariable::String(value.into_string().unwrap_or_default())
+        } else {
+            println!("Script tried to store a {} in variable {}, only bools, numbers and strings can be stored.", value.type_name(), name);
+            return;
+        };
+        state.write().unwrap().set_variable(name.to_owned(), variable);
+    });
+
+    // Audio
+
+    let audio = audio_manager.clone();
+    engine.register_fn("play_audio", move |name: &str, audio_type: &str, volume: f64| {
+        let audio_type = match audio_type {
+            "Music" => AudioType::Music,
+            "Sound" => AudioType::Sound,
+            "UI" => AudioType::UI,
+            _ => {
+                println!("Unknown audio type {} from script, expected Music, Sound or UI.", audio_type);
+                return;
+            }
+        };
+        audio.read().unwrap().enqueue_audio(name, audio_type, volume as f32, false);
+    });
+
+    let audio = audio_manager.clone();
+    engine.register_fn("stop_audio", move || {
+        audio.read().unwrap().stop_audio();
+    });
+
+    // Camera
+
+    let camera_zoom = camera.clone();
+    engine.register_fn("set_camera_zoom", move |zoom: f64| {
+        camera_zoom.write().unwrap().set_zoom(zoom as f32);
+    });
+
+    let camera_target = camera.clone();
+    engine.register_fn("set_camera_target", move |name: &str| {
+        camera_target.write().unwrap().set_tracking_target(Some(name.to_owned()));
+    });
+
+    // Outcomes
+
+    engine.register_fn("queue_outcome", move |outcome: Map| {
+        match rhai::serde::from_dynamic::<Outcome>(&Dynamic::from_map(outcome)) {
+            Ok(outcome) => queued_outcomes.write().unwrap().push(outcome),
+            Err(e) => println!("Script queued an outcome that couldn't be read: {}", e),
+        }
+    });
+}
@@ -0,0 +1,110 @@
+use std::{collections::HashMap, fs, path::PathBuf, sync::{Arc, RwLock}};
+
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Scope, AST};
+use rusted_open::framework::graphics::{camera::Camera, util::master_graphics_list::MasterGraphicsList};
+
+use crate::rusted_engine::{audio::audio_manager::AudioManager, entities::util::master_entity_list::MasterEntityList, events::triggers::Outcome, game_state::GameState};
+
+use super::script_bindings::register_bindings;
+
+// Limits for a single run, so a script stuck in a loop or recursing forever is stopped instead of freezing the game
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+
+/// Holds the compiled scripts and runs them. Scripts talk back to the engine through the bindings,
+/// outcomes they queue are collected here until the event handler takes them.
+pub struct ScriptEngine {
+    engine: Engine,
+    scripts: HashMap<String, AST>, // Keyed by file name without the extension
+    queued_outcomes: Arc<RwLock<Vec<Outcome>>>,
+}
+
+impl ScriptEngine {
+    pub fn new(
+        master_entity_list: Arc<RwLock<MasterEntityList>>,
+        master_graphics_list: Arc<RwLock<MasterGraphicsList>>,
+        game_state: Arc<RwLock<GameState>>,
+        audio_manager: Arc<RwLock<AudioManager>>,
+        camera: Arc<RwLock<Camera>>,
+    ) -> Self {
+        let queued_outcomes = Arc::new(RwLock::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(MAX_CALL_LEVELS);
+        register_bindings(&mut engine, master_entity_list, master_graphics_list, game_state, audio_manager, camera, queued_outcomes.clone());
+
+        Self {
+            engine,
+            scripts: HashMap::new(),
+            queued_outcomes,
+        }
+    }
+
+    /// Compiles every .rhai file in the directory and runs the top level of each once. A script that fails to compile is reported and skipped.
+    pub fn load_scripts_from_directory(&mut self, dir_path: &str) -> Result<(), String> {
+        let paths = fs::read_dir(dir_path).map_err(|_| "Failed to read directory".to_string())?;
+
+        for path in paths {
+            let entry = path.map_err(|_| "Failed to read directory entry".to_string())?;
+            let full_path = entry.path();
+
+            if full_path.is_file() && full_path.extension().is_some_and(|extension| extension == "rhai") {
+                let script_name = full_path.file_stem().unwrap().to_string_lossy().to_string();
+                match self.engine.compile_file(PathBuf::from(&full_path)) {
+                    Ok(ast) => {
+                        let result = self.engine.run_ast_with_scope(&mut Scope::new(), &ast);
+                        self.report_error(&script_name, result);
+                        self.scripts.insert(script_name, ast);
+                    }
+                    Err(e) => println!("Error compiling script '{}': {}", full_path.display(), e),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn has_function(&self, script_name: &str, function: &str) -> bool {
+        match self.scripts.get(script_name) {
+            Some(ast) => ast.iter_functions().any(|script_function| script_function.name == function),
+            None => false,
+        }
+    }
+
+    /// Calls a function of a script, or runs the whole script from the top if no function is given.
+    /// Calling a function doesn't run the top level again, that only happens when the script loads.
+    pub fn run_script(&self, script_name: &str, function: Option<&str>, args: Vec<Dynamic>) {
+        let ast = match self.scripts.get(script_name) {
+            Some(ast) => ast,
+            None => {
+                println!("No script found for script name: {}", script_name);
+                return;
+            }
+        };
+
+        let mut scope = Scope::new();
+        let result = match function {
+            Some(function) => self.engine.call_fn_with_options::<Dynamic>(CallFnOptions::new().eval_ast(false), &mut scope, ast, function, args).map(|_| ()),
+            None => self.engine.run_ast_with_scope(&mut scope, ast),
+        };
+        self.report_error(script_name, result);
+    }
+
+    fn report_error(&self, script_name: &str, result: Result<(), Box<EvalAltResult>>) {
+        match result.map_err(|e| *e) {
+            Ok(()) => {}
+            Err(EvalAltResult::ErrorTooManyOperations(position)) => {
+                println!("Script '{}' was stopped at {} after {} operations, it may be stuck in a loop.", script_name, position, MAX_OPERATIONS);
+            }
+            Err(EvalAltResult::ErrorStackOverflow(position)) => {
+                println!("Script '{}' was stopped at {} after {} nested calls, it may be recursing forever.", script_name, position, MAX_CALL_LEVELS);
+            }
+            Err(e) => println!("Error in script '{}': {}", script_name, e),
+        }
+    }
+
+    /// Hands over every outcome the scripts queued since the last call
+    pub fn take_outcomes(&self) -> Vec<Outcome> {
+        std::mem::take(&mut *self.queued_outcomes.write().unwrap())
+    }
+}
@@ -1,2 +1,7 @@
 pub mod master_clock;
-pub mod char_to_glfw_key;
\ No newline at end of file
+pub mod char_to_glfw_key;
+pub mod gamepad_names;
+pub mod key_names;
+pub mod random;
+#[cfg(test)]
+pub mod fixture_dir;
\ No newline at end of file
@@ -0,0 +1,40 @@
+use std::{fs, path::PathBuf, process, sync::atomic::{AtomicUsize, Ordering}};
+
+/// Tells apart the directories of tests that share a name, since tests run in parallel
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary directory of fixture files for tests, removed again when dropped
+pub struct FixtureDir {
+    path: PathBuf,
+}
+
+impl FixtureDir {
+    pub fn new(name: &str) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("rusted_engine_{}_{}_{}", name, process::id(), id));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    /// Writes a file into the directory and returns its path
+    pub fn write(&self, file_name: &str, contents: &str) -> String {
+        let file_path = self.path.join(file_name);
+        fs::write(&file_path, contents).unwrap();
+        file_path.display().to_string()
+    }
+
+    pub fn file_path(&self, file_name: &str) -> String {
+        self.path.join(file_name).display().to_string()
+    }
+
+    pub fn path(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+impl Drop for FixtureDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,70 @@
+use glfw::{GamepadAxis, GamepadButton};
+
+/// Every gamepad button, in the order glfw numbers them
+pub const GAMEPAD_BUTTONS: [GamepadButton; 15] = [
+    GamepadButton::ButtonA,
+    GamepadButton::ButtonB,
+    GamepadButton::ButtonX,
+    GamepadButton::ButtonY,
+    GamepadButton::ButtonLeftBumper,
+    GamepadButton::ButtonRightBumper,
+    GamepadButton::ButtonBack,
+    GamepadButton::ButtonStart,
+    GamepadButton::ButtonGuide,
+    GamepadButton::ButtonLeftThumb,
+    GamepadButton::ButtonRightThumb,
+    GamepadButton::ButtonDpadUp,
+    GamepadButton::ButtonDpadRight,
+    GamepadButton::ButtonDpadDown,
+    GamepadButton::ButtonDpadLeft,
+];
+
+/// Every gamepad axis, in the order glfw numbers them
+pub const GAMEPAD_AXES: [GamepadAxis; 6] = [
+    GamepadAxis::AxisLeftX,
+    GamepadAxis::AxisLeftY,
+    GamepadAxis::AxisRightX,
+    GamepadAxis::AxisRightY,
+    GamepadAxis::AxisLeftTrigger,
+    GamepadAxis::AxisRightTrigger,
+];
+
+/// Turns a button name as glfw spells it without the Button prefix ("A", "LeftBumper", "DpadUp") into the button
+pub fn button_name_to_glfw_button(name: &str) -> Option<GamepadButton> {
+    match name {
+        "A" => Some(GamepadButton::ButtonA),
+        "B" => Some(GamepadButton::ButtonB),
+        "X" => Some(GamepadButton::ButtonX),
+        "Y" => Some(GamepadButton::ButtonY),
+        "LeftBumper" => Some(GamepadButton::ButtonLeftBumper),
+        "RightBumper" => Some(GamepadButton::ButtonRightBumper),
+        "Back" => Some(GamepadButton::ButtonBack),
+        "Start" => Some(GamepadButton::ButtonStart),
+        "Guide" => Some(GamepadButton::ButtonGuide),
+        "LeftThumb" => Some(GamepadButton::ButtonLeftThumb),
+        "RightThumb" => Some(GamepadButton::ButtonRightThumb),
+        "DpadUp" => Some(GamepadButton::ButtonDpadUp),
+        "DpadRight" => Some(GamepadButton::ButtonDpadRight),
+        "DpadDown" => Some(GamepadButton::ButtonDpadDown),
+        "DpadLeft" => Some(GamepadButton::ButtonDpadLeft),
+        _ => None,
+    }
+}
+
+/// The name of a button as button_name_to_glfw_button reads it
+pub fn glfw_button_to_button_name(button: GamepadButton) -> String {
+    format!("{:?}", button).trim_start_matches("Button").to_owned()
+}
+
+/// Turns an axis name as glfw spells it without the Axis prefix ("LeftX", "RightTrigger") into the axis
+pub fn axis_name_to_glfw_axis(name: &str) -> Option<GamepadAxis> {
+    match name {
+        "LeftX" => Some(GamepadAxis::AxisLeftX),
+        "LeftY" => Some(GamepadAxis::AxisLeftY),
+        "RightX" => Some(GamepadAxis::AxisRightX),
+        "RightY" => Some(GamepadAxis::AxisRightY),
+        "LeftTrigger" => Some(GamepadAxis::AxisLeftTrigger),
+        "RightTrigger" => Some(GamepadAxis::AxisRightTrigger),
+        _ => None,
+    }
+}
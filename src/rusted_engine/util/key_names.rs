@@ -0,0 +1,118 @@
+use glfw::Key;
+
+/// Turns the name of a key, as glfw spells its variant ("Space", "Kp1", "LeftShift"), into the key
+pub fn key_name_to_glfw_key(name: &str) -> Option<Key> {
+    match name {
+        "Space" => Some(Key::Space),
+        "Apostrophe" => Some(Key::Apostrophe),
+        "Comma" => Some(Key::Comma),
+        "Minus" => Some(Key::Minus),
+        "Period" => Some(Key::Period),
+        "Slash" => Some(Key::Slash),
+        "Num0" => Some(Key::Num0),
+        "Num1" => Some(Key::Num1),
+        "Num2" => Some(Key::Num2),
+        "Num3" => Some(Key::Num3),
+        "Num4" => Some(Key::Num4),
+        "Num5" => Some(Key::Num5),
+        "Num6" => Some(Key::Num6),
+        "Num7" => Some(Key::Num7),
+        "Num8" => Some(Key::Num8),
+        "Num9" => Some(Key::Num9),
+        "Semicolon" => Some(Key::Semicolon),
+        "Equal" => Some(Key::Equal),
+        "A" => Some(Key::A),
+        "B" => Some(Key::B),
+        "C" => Some(Key::C),
+        "D" => Some(Key::D),
+        "E" => Some(Key::E),
+        "F" => Some(Key::F),
+        "G" => Some(Key::G),
+        "H" => Some(Key::H),
+        "I" => Some(Key::I),
+        "J" => Some(Key::J),
+        "K" => Some(Key::K),
+        "L" => Some(Key::L),
+        "M" => Some(Key::M),
+        "N" => Some(Key::N),
+        "O" => Some(Key::O),
+        "P" => Some(Key::P),
+        "Q" => Some(Key::Q),
+        "R" => Some(Key::R),
+        "S" => Some(Key::S),
+        "T" => Some(Key::T),
+        "U" => Some(Key::U),
+        "V" => Some(Key::V),
+        "W" => Some(Key::W),
+        "X" => Some(Key::X),
+        "Y" => Some(Key::Y),
+        "Z" => Some(Key::Z),
+        "LeftBracket" => Some(Key::LeftBracket),
+        "Backslash" => Some(Key::Backslash),
+        "RightBracket" => Some(Key::RightBracket),
+        "GraveAccent" => Some(Key::GraveAccent),
+        "Escape" => Some(Key::Escape),
+        "Enter" => Some(Key::Enter),
+        "Tab" => Some(Key::Tab),
+        "Backspace" => Some(Key::Backspace),
+        "Insert" => Some(Key::Insert),
+        "Delete" => Some(Key::Delete),
+        "Right" => Some(Key::Right),
+        "Left" => Some(Key::Left),
+        "Down" => Some(Key::Down),
+        "Up" => Some(Key::Up),
+        "PageUp" => Some(Key::PageUp),
+        "PageDown" => Some(Key::PageDown),
+        "Home" => Some(Key::Home),
+        "End" => Some(Key::End),
+        "CapsLock" => Some(Key::CapsLock),
+        "ScrollLock" => Some(Key::ScrollLock),
+        "NumLock" => Some(Key::NumLock),
+        "PrintScreen" => Some(Key::PrintScreen),
+        "Pause" => Some(Key::Pause),
+        "F1" => Some(Key::F1),
+        "F2" => Some(Key::F2),
+        "F3" => Some(Key::F3),
+        "F4" => Some(Key::F4),
+        "F5" => Some(Key::F5),
+        "F6" => Some(Key::F6),
+        "F7" => Some(Key::F7),
+        "F8" => Some(Key::F8),
+        "F9" => Some(Key::F9),
+        "F10" => Some(Key::F10),
+        "F11" => Some(Key::F11),
+        "F12" => Some(Key::F12),
+        "Kp0" => Some(Key::Kp0),
+        "Kp1" => Some(Key::Kp1),
+        "Kp2" => Some(Key::Kp2),
+        "Kp3" => Some(Key::Kp3),
+        "Kp4" => Some(Key::Kp4),
+        "Kp5" => Some(Key::Kp5),
+        "Kp6" => Some(Key::Kp6),
+        "Kp7" => Some(Key::Kp7),
+        "Kp8" => Some(Key::Kp8),
+        "Kp9" => Some(Key::Kp9),
+        "KpDecimal" => Some(Key::KpDecimal),
+        "KpDivide" => Some(Key::KpDivide),
+        "KpMultiply" => Some(Key::KpMultiply),
+        "KpSubtract" => Some(Key::KpSubtract),
+        "KpAdd" => Some(Key::KpAdd),
+        "KpEnter" => Some(Key::KpEnter),
+        "KpEqual" => Some(Key::KpEqual),
+        "LeftShift" => Some(Key::LeftShift),
+        "LeftControl" => Some(Key::LeftControl),
+        "LeftAlt" => Some(Key::LeftAlt),
+        "LeftSuper" => Some(Key::LeftSuper),
+        "RightShift" => Some(Key::RightShift),
+        "RightControl" => Some(Key::RightControl),
+        "RightAlt" => Some(Key::RightAlt),
+        "RightSuper" => Some(Key::RightSuper),
+        "Menu" => Some(Key::Menu),
+        _ => None,
+    }
+}
+
+/// The name of a key as key_name_to_glfw_key reads it
+pub fn glfw_key_to_key_name(key: Key) -> String {
+    format!("{:?}", key)
+}
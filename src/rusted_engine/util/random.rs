@@ -0,0 +1,113 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A small xorshift random number generator. Plenty for gameplay rolls, not for anything that needs to be secure.
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    /// Seeds the generator from the system clock
+    pub fn new() -> Self {
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_nanos() as u64).unwrap_or(0);
+        Self::with_seed(seed)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        // Xorshift gets stuck on zero
+        Self {
+            state: if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    /// A float in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        // The top 24 bits fit exactly in an f32 mantissa
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Picks an index in proportion to the weights. Negative weights count as zero, None if nothing has a positive weight.
+    pub fn choose_weighted(&mut self, weights: &[f32]) -> Option<usize> {
+        let total_weight: f32 = weights.iter().map(|weight| weight.max(0.0)).sum();
+        if total_weight <= 0.0 {
+            return None;
+        }
+
+        let mut roll = self.next_f32() * total_weight;
+        for (index, weight) in weights.iter().enumerate() {
+            let weight = weight.max(0.0);
+            if roll < weight {
+                return Some(index);
+            }
+            roll -= weight;
+        }
+
+        // Only reachable through float rounding, which lands on the last index that could have been picked
+        weights.iter().rposition(|weight| *weight > 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_gives_the_same_numbers() {
+        let mut first = Random::with_seed(42);
+        let mut second = Random::with_seed(42);
+
+        for _ in 0..100 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+    }
+
+    #[test]
+    fn a_zero_seed_still_produces_numbers() {
+        let mut random = Random::with_seed(0);
+
+        assert_ne!(random.next_u64(), 0);
+    }
+
+    #[test]
+    fn floats_stay_below_one() {
+        let mut random = Random::with_seed(7);
+
+        for _ in 0..10_000 {
+            let value = random.next_f32();
+            assert!((0.0..1.0).contains(&value), "value was {}", value);
+        }
+    }
+
+    #[test]
+    fn choices_without_weight_are_never_picked() {
+        let mut random = Random::with_seed(1234);
+
+        for _ in 0..1000 {
+            assert_eq!(random.choose_weighted(&[0.0, 1.0, -3.0]), Some(1));
+        }
+        assert_eq!(random.choose_weighted(&[0.0, -1.0]), None);
+        assert_eq!(random.choose_weighted(&[]), None);
+    }
+
+    #[test]
+    fn choices_are_picked_in_proportion_to_their_weights() {
+        let mut random = Random::with_seed(99);
+        let mut counts = [0; 2];
+
+        for _ in 0..10_000 {
+            counts[random.choose_weighted(&[1.0, 3.0]).unwrap()] += 1;
+        }
+
+        // A quarter and three quarters, give or take
+        assert!((2000..3000).contains(&counts[0]), "counts were {:?}", counts);
+        assert!((7000..8000).contains(&counts[1]), "counts were {:?}", counts);
+    }
+}